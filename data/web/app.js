@@ -0,0 +1,163 @@
+"use strict";
+
+// 访问令牌通过 ?token= 传入后保存在浏览器中
+const params = new URLSearchParams(location.search);
+if (params.has("token")) {
+    localStorage.setItem("token", params.get("token"));
+    history.replaceState(null, "", location.pathname);
+}
+let token = localStorage.getItem("token") || "";
+if (!token) {
+    token = prompt("Access token") || "";
+    localStorage.setItem("token", token);
+}
+
+const $ = (id) => document.getElementById(id);
+let status = null;
+let seeking = false;
+
+async function api(method, path, body) {
+    const res = await fetch(path, {
+        method,
+        headers: {
+            "Authorization": `Bearer ${token}`,
+            "Content-Type": "application/json",
+        },
+        body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    if (res.status === 401) {
+        localStorage.removeItem("token");
+    }
+    if (!res.ok) {
+        throw new Error(`${method} ${path}: ${res.status}`);
+    }
+    return res.json();
+}
+
+function formatTime(ms) {
+    const sec = Math.floor(ms / 1000);
+    const pad = (n) => String(n).padStart(2, "0");
+    return `${pad(Math.floor(sec / 60))}:${pad(sec % 60)}`;
+}
+
+function updatePosition(position) {
+    $("position").textContent = formatTime(position);
+    if (!seeking) {
+        $("seek").value = position;
+    }
+}
+
+function updatePlaying(playing) {
+    $("toggle").innerHTML = playing ? "&#9208;" : "&#9654;";
+}
+
+async function refreshStatus() {
+    status = await api("GET", "/api/status");
+    if (!status) {
+        $("title").textContent = "Nothing is playing";
+        return;
+    }
+    $("title").textContent = status.title;
+    $("artist").textContent = status.artist;
+    $("album").textContent = status.album;
+    $("lyric").textContent = status.lyric;
+    $("translation").textContent = status.translation;
+    $("seek").max = status.duration;
+    $("duration").textContent = formatTime(status.duration);
+    $("volume").value = status.volume;
+    $("like").classList.toggle("liked", status.liked);
+    $("like").innerHTML = status.liked ? "&#9733;" : "&#9734;";
+    // 已缓存的封面为本地路径，由接口读取
+    const cover = status.cover.startsWith("http")
+        ? status.cover
+        : `/api/cover?token=${encodeURIComponent(token)}&id=${status.id}`;
+    if ($("cover").getAttribute("src") !== cover) {
+        $("cover").src = cover;
+    }
+    updatePlaying(status.playing);
+    updatePosition(status.position);
+}
+
+function songItem(song, current, onPlay, onAdd) {
+    const li = document.createElement("li");
+    li.classList.toggle("current", current);
+    const name = document.createElement("div");
+    name.className = "name";
+    name.textContent = song.title;
+    const detail = document.createElement("small");
+    detail.textContent = `${song.artist} · ${formatTime(song.duration)}`;
+    name.appendChild(detail);
+    name.onclick = onPlay;
+    li.appendChild(name);
+    if (onAdd) {
+        const add = document.createElement("button");
+        add.textContent = "+";
+        add.title = "Add to queue";
+        add.onclick = onAdd;
+        li.appendChild(add);
+    }
+    return li;
+}
+
+function addSong(id, play) {
+    return api("POST", "/api/queue", { id, play }).then(() => setTimeout(refreshQueue, 1000));
+}
+
+async function refreshQueue() {
+    const queue = await api("GET", "/api/queue");
+    const list = $("queue-list");
+    list.replaceChildren(...queue.songs.map((song, index) =>
+        songItem(song, index === queue.current, () => addSong(song.id, true))));
+}
+
+async function search(text) {
+    const songs = await api("GET", `/api/search?q=${encodeURIComponent(text)}`);
+    $("search-results").replaceChildren(...songs.map((song) =>
+        songItem(song, false, () => addSong(song.id, true), () => addSong(song.id, false))));
+}
+
+function connectEvents() {
+    const scheme = location.protocol === "https:" ? "wss" : "ws";
+    const ws = new WebSocket(`${scheme}://${location.host}/api/events?token=${encodeURIComponent(token)}`);
+    ws.onmessage = (message) => {
+        const event = JSON.parse(message.data);
+        switch (event.event) {
+            case "play_start":
+                refreshStatus();
+                refreshQueue();
+                break;
+            case "state":
+                updatePlaying(event.state === "playing");
+                break;
+            case "position":
+                updatePosition(event.position);
+                // 歌词随进度变化，每秒刷新一次状态
+                refreshStatus();
+                break;
+        }
+    };
+    ws.onclose = () => setTimeout(connectEvents, 3000);
+}
+
+$("toggle").onclick = () => api("POST", "/api/toggle");
+$("prev").onclick = () => api("POST", "/api/prev");
+$("next").onclick = () => api("POST", "/api/next");
+$("like").onclick = () => api("POST", "/api/like", { like: !(status && status.liked) })
+    .then(() => setTimeout(refreshStatus, 1000));
+$("volume").onchange = (e) => api("POST", "/api/volume", { volume: Number(e.target.value) });
+$("seek").oninput = () => { seeking = true; };
+$("seek").onchange = (e) => {
+    seeking = false;
+    api("POST", "/api/seek", { position: Number(e.target.value) });
+};
+$("search-form").onsubmit = (e) => {
+    e.preventDefault();
+    const text = $("search-text").value.trim();
+    if (text) {
+        search(text);
+    }
+};
+
+refreshStatus();
+refreshQueue();
+connectEvents();
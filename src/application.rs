@@ -1,4 +1,7 @@
-use adw::{prelude::AdwDialogExt, subclass::prelude::*};
+use adw::{
+    prelude::{AdwDialogExt, AlertDialogExt},
+    subclass::prelude::*,
+};
 use async_channel::{Receiver, Sender, unbounded};
 use gettextrs::gettext;
 use gio::Settings;
@@ -11,16 +14,36 @@ use ncm_api::{
 };
 use once_cell::sync::{Lazy, OnceCell};
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
+    collections::HashMap,
     fs,
     path::PathBuf,
+    rc::Rc,
     sync::{Arc, Mutex},
     time::Duration,
 };
 
 use crate::{
-    MAINCONTEXT, NeteaseCloudMusicGtk4Window, audio::MprisController, config::VERSION,
-    gui::NeteaseCloudMusicGtk4Preferences, model::*, ncmapi::*, path::CACHE, utils::*,
+    MAINCONTEXT, NeteaseCloudMusicGtk4Window,
+    audio::{
+        LocalLibrary, LocalTrack, LoopsState, MATCH_THRESHOLD, MprisController, PlayList, Playback,
+        best_match, is_local_song, local_library_dirs, match_keyword, netease_song_id,
+    },
+    cloud_disk::parse_song_id,
+    config::VERSION,
+    gui::{
+        CloudUploadDialog, DesktopLyricsWindow, LocalMatchDialog, LyricsSyncDialog,
+        NeteaseCloudMusicGtk4Preferences, SearchSongPage, TrayHandle,
+    },
+    links::{NeteaseLink, link_position, parse_link},
+    lyrics::Lyrics,
+    lyrics_index::{rebuild_lyrics_index, search_lyrics},
+    model::*,
+    ncmapi::*,
+    now_playing::NowPlaying,
+    path::{CACHE, add_unmatched_legacy_lyrics, legacy_lyric_files, migrate_legacy_lyric},
+    remote::{MpdServer, RemoteApi, RemoteEvent, RemoteSong, generate_token},
+    utils::*,
 };
 
 // implements Debug for Fn(Targ) using "blanket implementations"
@@ -84,9 +107,9 @@ async fn favorite_playlist_id(ncmapi: &NcmClient, uid: u64) -> Result<u64, &'sta
 }
 
 // 列表循环模式追加失败/为空时：从头播放列表第一首
-async fn playlist_restart(window: &NeteaseCloudMusicGtk4Window, sender: &Sender<Action>) {
-    if let Some(first) = window.playlist_first_restart() {
-        let index = window.playlist_position();
+async fn playlist_restart(playback: &Playback, sender: &Sender<Action>) {
+    if let Some(first) = playback.playlist_first_restart() {
+        let index = playback.playlist_position();
         sender.send(Action::Play(first.to_owned())).await.unwrap();
         sender.send(Action::UpdatePlayListStatus(index)).await.unwrap();
     }
@@ -100,9 +123,19 @@ pub enum Action {
     Search(String, SearchType, u16, u16, ActionCallback<SearchResult>),
     // (url,path,width,height)
     DownloadImage(String, PathBuf, u16, u16, Option<ActionCallback>),
+    // 获取在线歌曲的曲目序号
+    GetTrackNumber(u64, ActionCallback<Option<u32>>),
     LikeSongList(u64, bool, Option<ActionCallback>),
     LikeAlbum(u64, bool, Option<ActionCallback>),
     LikeSong(u64, bool, Option<ActionCallback>),
+    // 收藏/取消收藏当前歌曲，None 表示切换
+    LikeCurrentSong(Option<bool>),
+    // 将当前歌曲移入私人 FM 垃圾桶并跳过
+    TrashCurrentSong,
+    DownloadSong(SongInfo),
+    // 批量下载到指定目录
+    DownloadSongs(Vec<SongInfo>, PathBuf),
+    UploadToCloud(Vec<PathBuf>),
     Moved(SongInfo),
     // 心动模式播放完时扩展推荐（参考歌曲，追加来源）
     HeartbeatExtend(SongInfo, HeartbeatExtendMode),
@@ -114,10 +147,26 @@ pub enum Action {
     Play(SongInfo),
     PlayStart(SongInfo),
     TogglePlayPause,
+    SetPlaying(bool),
+    // 跳转到指定位置（毫秒）
+    SeekTo(u64),
     // (歌单, 是否立即播放)
     AddPlayList(Vec<SongInfo>, bool),
     PlayListStart,
+    // (歌曲 ID, 是否立即播放)，不立即播放时追加到播放列表末尾
+    AddTrackById(u64, bool),
+    // (歌曲 ID, 播放位置（秒）)，从分享链接附带的位置开始播放
+    PlayTrackAt(u64, u64),
+    ClearPlayList,
+    // 播放指定 ID 的歌单
+    PlaySongList(u64),
     PersistVolume(f64),
+    SetVolume(f64),
+    // 相对调整音量
+    AdjustVolume(f64),
+    SetLoops(LoopsState),
+    SetMusicRate(u32),
+    ToggleHeartbeat,
     GetSongUrl(SongInfo),
     SetSongUrl(SongInfo),
 
@@ -159,9 +208,29 @@ pub enum Action {
     // playlist
     ToPlayListLyricsPage(Vec<SongInfo>, SongInfo),
     UpdateLyrics(SongInfo, u64),
+    // 调整当前歌曲的歌词偏移（毫秒），正值表示歌词提前
+    AdjustLyricsOffset(i64),
+    ShowLyricsSyncDialog,
+    // (歌曲, [(原时间, 新时间)])
+    SaveSyncedLyrics(SongInfo, Vec<(u64, u64)>),
+    // 通过搜索确定旧版歌词文件对应的歌曲 ID 并迁移
+    MigrateLegacyLyrics(Vec<PathBuf>),
+    // 获取当前播放位置（毫秒）
+    GetPlayPosition(ActionCallback<u64>),
+    GetNowPlaying(ActionCallback<Option<NowPlaying>>),
+    // (播放列表, 当前歌曲位置)
+    GetPlayList(ActionCallback<(Vec<SongInfo>, usize)>),
     UpdatePlayListStatus(usize),
     RemoveFromPlayList(SongInfo),
 
+    // 搜索歌曲并显示窗口
+    SearchSongs(String),
+    // 打开网易云音乐分享链接并显示窗口
+    // 链接及其中附带的播放位置（秒）
+    OpenLink(NeteaseLink, Option<u64>),
+    // (链接, 标题, 播放位置（秒）)
+    ShareLink(NeteaseLink, String, Option<u64>),
+
     // page routing
     ToTopPicksPage,
     ToAllAlbumsPage,
@@ -173,8 +242,19 @@ pub enum Action {
     ToMyPageHeartbeat,
     ToMyPageRadio,
     ToMyPageCloudDisk,
+    RefreshCloudDisk,
+    DeleteCloudSong(SongInfo),
+    RematchCloudSong(SongInfo),
     ToMyPageAlbums,
     ToMyPageSonglist,
+    ToMyPageLocalMusic,
+    AddLocalMusicFolder(PathBuf),
+    ScanLocalMusic,
+    ShowLocalMatchDialog,
+    // 为未确认的本地歌曲匹配网易云歌曲，完成后回调最新索引
+    MatchLocalMusic(Option<ActionCallback<Vec<LocalTrack>>>),
+    // (本地歌曲 ID, 确认的网易云歌曲)
+    ConfirmLocalMatch(u64, Option<SongInfo>),
     PageBack,
 
     // gst
@@ -192,6 +272,10 @@ pub enum Action {
     // system tray
     UpdateTrayPlaying(bool),
     UpdateTraySongTitle(String, String, u64),
+    UpdateTrayLike(bool),
+    UpdateTrayLoops(LoopsState),
+    ToggleDesktopLyrics,
+    ToggleDesktopLyricsLock,
     ShowMainWindow,
     ShowPlayerBar,
 }
@@ -203,11 +287,35 @@ mod imp {
     use super::*;
 
     pub struct NeteaseCloudMusicGtk4Application {
-        pub window: OnceCell<WeakRef<NeteaseCloudMusicGtk4Window>>,
+        pub window: WeakRef<NeteaseCloudMusicGtk4Window>,
+        pub settings: OnceCell<Settings>,
+        // 播放核心，首次启动时创建，不依赖主窗口
+        pub playback: OnceCell<Playback>,
+        pub tray_handle: RefCell<TrayHandle>,
+        pub user_info: RefCell<UserInfo>,
+        // 本地歌词搜索结果的匹配时间（歌曲 ID -> 毫秒），播放时跳转
+        pub lyric_jumps: RefCell<HashMap<u64, u64>>,
+        // 无窗口模式：不创建主窗口，保持程序运行
+        pub headless: Cell<bool>,
+        pub hold_guard: RefCell<Option<gio::ApplicationHoldGuard>>,
+        pub quitting: Cell<bool>,
         pub sender: Sender<Action>,
         pub receiver: RefCell<Option<Receiver<Action>>>,
         pub unikey: Arc<RwLock<String>>,
         pub ncmapi: RefCell<Option<NcmClient>>,
+        // 本地音乐目录监视
+        pub local_music_monitors: RefCell<Vec<gio::FileMonitor>>,
+        pub local_music_debounce: Arc<Debounce>,
+        pub local_music_page: RefCell<Option<WeakRef<SearchSongPage>>>,
+        pub cloud_disk_page: RefCell<Option<WeakRef<SearchSongPage>>>,
+        // 远程控制接口，未启用时为 None
+        pub remote_api: RefCell<Option<RemoteApi>>,
+        // 等待启动远程控制接口的定时器
+        pub remote_api_timer: RefCell<Option<glib::SourceId>>,
+        // MPD 协议服务，未启用时为 None
+        pub mpd_server: RefCell<Option<MpdServer>>,
+        // 桌面歌词窗口，首次打开主窗口时创建，由所有主窗口共用
+        pub desktop_lyrics: OnceCell<DesktopLyricsWindow>,
     }
 
     #[glib::object_subclass]
@@ -218,16 +326,32 @@ mod imp {
         fn new() -> Self {
             let (sender, r) = unbounded();
             let receiver = RefCell::new(Some(r));
-            let window = OnceCell::new();
+            let window = WeakRef::new();
             let unikey = Arc::new(RwLock::new(String::new()));
             let ncmapi = RefCell::new(None);
 
             Self {
                 window,
+                settings: OnceCell::new(),
+                playback: OnceCell::new(),
+                tray_handle: RefCell::new(TrayHandle::new()),
+                user_info: RefCell::new(UserInfo::default()),
+                lyric_jumps: RefCell::new(HashMap::new()),
+                headless: Cell::new(false),
+                hold_guard: RefCell::new(None),
+                quitting: Cell::new(false),
                 sender,
                 receiver,
                 unikey,
                 ncmapi,
+                local_music_monitors: RefCell::new(Vec::new()),
+                local_music_debounce: Arc::new(Debounce::new()),
+                local_music_page: RefCell::new(None),
+                cloud_disk_page: RefCell::new(None),
+                remote_api: RefCell::new(None),
+                remote_api_timer: RefCell::new(None),
+                mpd_server: RefCell::new(None),
+                desktop_lyrics: OnceCell::new(),
             }
         }
     }
@@ -237,7 +361,9 @@ mod imp {
             let obj = self.obj();
             self.parent_constructed();
 
+            obj.setup_settings();
             obj.setup_gactions();
+            obj.setup_command_line_options();
             obj.setup_cache_clear();
             obj.set_accels_for_action("app.quit", &["<primary>q"]);
             obj.set_accels_for_action("win.search-button", &["<primary>f", "slash"]);
@@ -256,31 +382,22 @@ mod imp {
                 .downcast_ref::<super::NeteaseCloudMusicGtk4Application>()
                 .unwrap();
 
-            if let Some(weak_window) = self.window.get() {
-                weak_window.upgrade().unwrap().present();
-                return;
+            // 首次启动时创建播放核心，无窗口模式下不创建窗口
+            if self.playback.get().is_none() {
+                app.setup_core();
+                if self.headless.get() {
+                    app.start_headless();
+                    return;
+                }
             }
 
-            let window = app.create_window();
-            let _ = self.window.set(window.downgrade());
-
-            // Setup action channel
-            let receiver = self.receiver.borrow_mut().take().unwrap();
-            MAINCONTEXT.spawn_local_with_priority(
-                Priority::HIGH,
-                clone!(
-                    #[strong]
-                    app,
-                    async move {
-                        while let Ok(action) = receiver.recv().await {
-                            app.process_action(action);
-                        }
-                    }
-                ),
-            );
-
             // Ask the window manager/compositor to present the window
-            window.present();
+            app.present_window();
+        }
+
+        // 命令行参数总是在主实例中处理，再次启动时由主实例接收并执行
+        fn command_line(&self, command_line: &gio::ApplicationCommandLine) -> glib::ExitCode {
+            self.obj().handle_command_line(command_line)
         }
     }
 
@@ -302,27 +419,828 @@ impl NeteaseCloudMusicGtk4Application {
             .build()
     }
 
+    fn setup_settings(&self) {
+        let settings = Settings::new(crate::APP_ID);
+        self.imp()
+            .settings
+            .set(settings)
+            .expect("Could not set `Settings`.");
+    }
+
+    fn settings(&self) -> &Settings {
+        self.imp().settings.get().expect("Could not get settings.")
+    }
+
+    pub fn playback(&self) -> &Playback {
+        self.imp().playback.get().expect("Could not get playback.")
+    }
+
+    fn window(&self) -> Option<NeteaseCloudMusicGtk4Window> {
+        self.imp().window.upgrade()
+    }
+
+    // 创建播放核心并启动不依赖窗口的服务
+    fn setup_core(&self) {
+        let imp = self.imp();
+        imp.playback.set(Playback::new(imp.sender.clone())).unwrap();
+
+        // Setup action channel
+        let receiver = imp.receiver.borrow_mut().take().unwrap();
+        MAINCONTEXT.spawn_local_with_priority(
+            Priority::HIGH,
+            clone!(
+                #[strong(rename_to = app)]
+                self,
+                async move {
+                    while let Ok(action) = receiver.recv().await {
+                        app.process_action(action);
+                    }
+                }
+            ),
+        );
+
+        self.setup_tray();
+        self.watch_local_music();
+        self.setup_user_lyrics();
+        self.setup_remote_api();
+        self.setup_mpd_server();
+    }
+
+    // 无窗口模式：保持程序运行，并使用保存的登录信息登录
+    fn start_headless(&self) {
+        let imp = self.imp();
+        info!("以无窗口模式运行");
+        imp.hold_guard.replace(Some(self.hold()));
+        if let Some(cookie_jar) = NcmClient::load_cookie_jar_from_file() {
+            imp.sender
+                .send_blocking(Action::CheckLogin(UserMenuChild::Qr, cookie_jar))
+                .unwrap();
+        }
+    }
+
+    // 显示主窗口，尚未创建（或已关闭）时创建
+    fn present_window(&self) -> NeteaseCloudMusicGtk4Window {
+        if let Some(window) = self.window() {
+            window.present();
+            return window;
+        }
+        self.create_window()
+    }
+
     fn create_window(&self) -> NeteaseCloudMusicGtk4Window {
         let imp = self.imp();
-        let window = NeteaseCloudMusicGtk4Window::new(&self.clone(), imp.sender.clone());
+        let window = NeteaseCloudMusicGtk4Window::new(self, imp.sender.clone());
+        imp.window.set(Some(&window));
+        self.setup_desktop_lyrics();
+        // 无窗口模式下打开窗口时可能已在播放
+        window.update_playing(self.playback().is_playing());
+
+        // 关闭窗口即退出程序时保存播放列表并清理系统托盘
+        window.connect_close_request(clone!(
+            #[weak(rename_to = app)]
+            self,
+            #[upgrade_or]
+            glib::Propagation::Proceed,
+            move |window| {
+                let imp = app.imp();
+                if !window.hides_on_close() && !imp.headless.get() && !imp.quitting.get() {
+                    app.playback().save_current_state();
+                    imp.tray_handle.borrow_mut().stop();
+                    if let Some(desktop_lyrics) = imp.desktop_lyrics.get() {
+                        desktop_lyrics.destroy();
+                    }
+                }
+                glib::Propagation::Proceed
+            }
+        ));
 
         window.present();
         window
     }
 
+    // 创建桌面歌词窗口，只创建一次，之后打开的主窗口共用
+    fn setup_desktop_lyrics(&self) {
+        let imp = self.imp();
+        if imp.desktop_lyrics.get().is_some() {
+            return;
+        }
+        let settings = self.settings();
+        let desktop_lyrics = DesktopLyricsWindow::new();
+        desktop_lyrics.set_visible(settings.boolean("desktop-lyrics"));
+        imp.desktop_lyrics.set(desktop_lyrics).unwrap();
+        // 加载当前歌曲的歌词，之后中途开启桌面歌词时也重新加载
+        let load_lyrics = clone!(
+            #[weak(rename_to = app)]
+            self,
+            move || {
+                if let Some(si) = app.playback().get_current_song() {
+                    app.imp()
+                        .sender
+                        .send_blocking(Action::UpdateLyrics(si, 0))
+                        .unwrap();
+                }
+            }
+        );
+        if settings.boolean("desktop-lyrics") {
+            load_lyrics();
+        }
+        settings.connect_changed(Some("desktop-lyrics"), move |settings, key| {
+            if settings.boolean(key) {
+                load_lyrics();
+            }
+        });
+    }
+
+    // 启动系统托盘，并同步播放状态与相关设置
+    fn setup_tray(&self) {
+        let imp = self.imp();
+        imp.tray_handle.borrow_mut().start(imp.sender.clone());
+        imp.tray_handle
+            .borrow()
+            .update_loops(self.playback().property("loops"));
+        self.update_tray_settings();
+        for key in ["music-rate", "desktop-lyrics", "desktop-lyrics-locked"] {
+            self.settings().connect_changed(
+                Some(key),
+                clone!(
+                    #[weak(rename_to = app)]
+                    self,
+                    move |_, _| app.update_tray_settings()
+                ),
+            );
+        }
+    }
+
+    fn update_tray_settings(&self) {
+        let settings = self.settings();
+        let tray_handle = self.imp().tray_handle.borrow();
+        tray_handle.update_quality(settings.uint("music-rate"));
+        tray_handle.update_desktop_lyrics(
+            settings.boolean("desktop-lyrics"),
+            settings.boolean("desktop-lyrics-locked"),
+        );
+    }
+
+    // 有窗口时显示通知，否则写入日志
+    fn add_toast(&self, mes: String) {
+        match self.window() {
+            Some(window) => window.add_toast(mes),
+            None => info!("{}", mes),
+        }
+    }
+
+    pub fn get_uid(&self) -> u64 {
+        self.imp().user_info.borrow().uid
+    }
+
+    fn set_uid(&self, uid: u64) {
+        self.imp().user_info.borrow_mut().uid = uid;
+    }
+
+    pub fn is_logined(&self) -> bool {
+        self.get_uid() != 0u64
+    }
+
+    fn logout(&self) {
+        self.imp().user_info.take();
+    }
+
+    pub fn get_song_likes(&self, sis: &[SongInfo]) -> Vec<bool> {
+        let user_info = self.imp().user_info.borrow();
+        sis.iter()
+            .map(|si| {
+                netease_song_id(si)
+                    .map(|id| user_info.like_songs.contains(&id))
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
+
+    fn set_like_song(&self, id: u64, val: bool) {
+        let playback = self.playback();
+        if let Some(song) = playback.get_current_song() {
+            if netease_song_id(&song) == Some(id) {
+                playback.set_property("like", val);
+            }
+        }
+
+        let mut user_info = self.imp().user_info.borrow_mut();
+        if val {
+            user_info.like_songs.insert(id);
+        } else {
+            user_info.like_songs.remove(&id);
+        }
+    }
+
+    fn set_user_like_songs(&self, song_ids: &[u64]) {
+        self.imp()
+            .user_info
+            .borrow_mut()
+            .like_songs
+            .extend(song_ids.iter().copied());
+    }
+
+    // 心动模式开启/关闭时同步按钮与托盘状态
+    fn set_heartbeat_active(&self, active: bool) {
+        if let Some(window) = self.window() {
+            window.set_heartbeat_active(active);
+        }
+        self.imp().tray_handle.borrow().update_heartbeat(active);
+    }
+
+    fn play(&self, song_info: SongInfo) {
+        let playback = self.playback();
+        let like = self.get_song_likes(std::slice::from_ref(&song_info))[0];
+        playback.set_property("like", like);
+        playback.play(song_info.clone());
+        if let Some(window) = self.window() {
+            window.play(&song_info);
+        }
+        self.imp().tray_handle.borrow().add_recent(song_info);
+    }
+
+    // 更新歌词内容，不调整位置
+    fn update_lyrics(&self, si: &SongInfo, lrc: Lyrics) {
+        self.playback().set_lyrics(si, &lrc);
+        if let Some(desktop_lyrics) = self.imp().desktop_lyrics.get() {
+            desktop_lyrics.set_lyrics(&lrc);
+        }
+        if let Some(window) = self.window() {
+            window.update_lyrics(lrc);
+        }
+    }
+
+    // 更新歌词高亮位置，并发布当前歌词行到 MPRIS、托盘及正在播放文件
+    fn update_lyrics_timestamp(&self, time: u64) {
+        if let Some(desktop_lyrics) = self.imp().desktop_lyrics.get() {
+            desktop_lyrics.update_time(time);
+        }
+        if let Some(window) = self.window() {
+            window.update_lyrics_timestamp(time);
+        }
+        if let Some(now_playing) = self.playback().update_now_playing(time) {
+            self.imp().tray_handle.borrow().update_progress(
+                now_playing.position / 1000,
+                now_playing.duration / 1000,
+                now_playing.lyric,
+            );
+        }
+    }
+
+    async fn action_search(
+        &self,
+        ncmapi: NcmClient,
+        text: String,
+        search_type: SearchType,
+        offset: u16,
+        limit: u16,
+    ) -> Option<SearchResult> {
+        let imp = self.imp();
+        let sender = imp.sender.clone();
+
+        let res = match search_type {
+            SearchType::Song => ncmapi
+                .client
+                .search_song(text, offset, limit)
+                .await
+                .map(|res| {
+                    debug!("搜索歌曲：{:?}", res);
+                    let likes = self.get_song_likes(&res);
+                    SearchResult::Songs(res, likes)
+                }),
+            SearchType::Singer => {
+                ncmapi
+                    .client
+                    .search_singer(text, offset, limit)
+                    .await
+                    .map(|res| {
+                        debug!("搜索歌手：{:?}", res);
+                        SearchResult::Singers(res)
+                    })
+            }
+            SearchType::Album => ncmapi
+                .client
+                .search_album(text, offset, limit)
+                .await
+                .map(|res| {
+                    debug!("搜索专辑：{:?}", res);
+                    SearchResult::SongLists(res)
+                }),
+            SearchType::Lyrics => {
+                ncmapi
+                    .client
+                    .search_lyrics(text, offset, limit)
+                    .await
+                    .map(|res| {
+                        debug!("搜索歌词：{:?}", res);
+                        let likes = self.get_song_likes(&res);
+                        SearchResult::Songs(res, likes)
+                    })
+            }
+            SearchType::LocalLyrics => {
+                let res = search_lyrics(&text, offset as usize, limit as usize);
+                debug!("搜索本地歌词：{:?}", res);
+                let mut jumps = imp.lyric_jumps.borrow_mut();
+                if offset == 0 {
+                    jumps.clear();
+                }
+                jumps.extend(res.iter().map(|(si, time)| (si.id, *time)));
+                let sis: Vec<SongInfo> = res.into_iter().map(|(si, _)| si).collect();
+                let likes = self.get_song_likes(&sis);
+                Ok(SearchResult::Songs(sis, likes))
+            }
+            SearchType::SongList => ncmapi
+                .client
+                .search_songlist(text, offset, limit)
+                .await
+                .map(|res| {
+                    debug!("搜索歌单：{:?}", res);
+                    SearchResult::SongLists(res)
+                }),
+            SearchType::TopPicks => ncmapi
+                .client
+                .top_song_list("全部", "hot", offset, limit)
+                .await
+                .map(|res| {
+                    debug!("获取歌单：{:?}", res);
+                    SearchResult::SongLists(res)
+                }),
+            SearchType::AllAlbums => {
+                ncmapi
+                    .client
+                    .new_albums("ALL", offset, limit)
+                    .await
+                    .map(|res| {
+                        debug!("获取专辑：{:?}", res);
+                        SearchResult::SongLists(res)
+                    })
+            }
+            SearchType::Radio => ncmapi
+                .client
+                .user_radio_sublist(offset, limit)
+                .await
+                .map(|res| {
+                    debug!("获取电台：{:?}", res);
+                    SearchResult::SongLists(res)
+                }),
+            SearchType::LikeAlbums => ncmapi.client.album_sublist(offset, limit).await.map(|res| {
+                debug!("获取收藏的专辑：{:?}", res);
+                SearchResult::SongLists(res)
+            }),
+            SearchType::LikeSongList => {
+                let uid = self.get_uid();
+                ncmapi
+                    .client
+                    .user_song_list(uid, offset, limit)
+                    .await
+                    .map(|res| {
+                        debug!("获取收藏的歌单：{:?}", res);
+                        SearchResult::SongLists(res)
+                    })
+            }
+            _ => Err(anyhow::anyhow!("")),
+        };
+        if let Err(err) = &res {
+            error!("{:?}", err);
+            sender
+                .send_blocking(Action::AddToast(gettext(
+                    "Request for interface failed, please try again!",
+                )))
+                .unwrap();
+        }
+        res.ok()
+    }
+
+    fn setup_command_line_options(&self) {
+        let no_short = glib::Char::from(0u8);
+        let flags = glib::OptionFlags::NONE;
+        let options = [
+            ("play-pause", glib::OptionArg::None, gettext("Toggle play/pause"), None),
+            ("next", glib::OptionArg::None, gettext("Play the next song"), None),
+            ("prev", glib::OptionArg::None, gettext("Play the previous song"), None),
+            (
+                "volume",
+                glib::OptionArg::Int,
+                gettext("Set the volume (0-100)"),
+                Some("VOLUME"),
+            ),
+            ("like", glib::OptionArg::None, gettext("Like the current song"), None),
+            (
+                "search",
+                glib::OptionArg::String,
+                gettext("Search for songs"),
+                Some("TEXT"),
+            ),
+            (
+                "play-url",
+                glib::OptionArg::String,
+                gettext("Play a music.163.com song link"),
+                Some("URL"),
+            ),
+            ("status", glib::OptionArg::None, gettext("Print the current song"), None),
+            ("json", glib::OptionArg::None, gettext("Print status as JSON"), None),
+            (
+                "headless",
+                glib::OptionArg::None,
+                gettext("Run in the background without a window"),
+                None,
+            ),
+        ];
+        for (name, arg, description, arg_description) in options {
+            self.add_main_option(name, no_short, flags, arg, &description, arg_description);
+        }
+        self.set_option_context_parameter_string(Some("[LINK…]"));
+    }
+
+    fn handle_command_line(&self, command_line: &gio::ApplicationCommandLine) -> glib::ExitCode {
+        let options = command_line.options_dict();
+        let playback = self.imp().playback.get();
+        // 无窗口模式仅在首次启动时生效
+        let headless = options.contains("headless");
+        if headless && playback.is_none() {
+            self.imp().headless.set(true);
+        }
+
+        if options.contains("status") {
+            let Some(now_playing) = playback.and_then(|p| p.now_playing()) else {
+                command_line.printerr_literal(&format!("{}\n", gettext("Nothing is playing")));
+                return glib::ExitCode::FAILURE;
+            };
+            let status = if options.contains("json") {
+                now_playing.to_json()
+            } else {
+                now_playing.to_text()
+            };
+            command_line.print_literal(&format!("{}\n", status));
+            return glib::ExitCode::SUCCESS;
+        }
+
+        let mut actions = Vec::new();
+        if options.contains("play-pause") {
+            actions.push(Action::TogglePlayPause);
+        }
+        if options.contains("next") {
+            actions.push(Action::PlayNextSong);
+        }
+        if options.contains("prev") {
+            actions.push(Action::PlayPreviousSong);
+        }
+        if let Ok(Some(volume)) = options.lookup::<i32>("volume") {
+            actions.push(Action::SetVolume(volume.clamp(0, 100) as f64 / 100.0));
+        }
+        if options.contains("like") {
+            actions.push(Action::LikeCurrentSong(Some(true)));
+        }
+        if let Ok(Some(url)) = options.lookup::<String>("play-url") {
+            match parse_link(&url) {
+                Some(NeteaseLink::Song(id)) => match link_position(&url) {
+                    Some(position) => actions.push(Action::PlayTrackAt(id, position)),
+                    None => actions.push(Action::AddTrackById(id, true)),
+                },
+                // 歌单、专辑及歌手链接与打开分享链接相同处理
+                Some(link) => actions.push(Action::OpenLink(link, None)),
+                None => {
+                    command_line.printerr_literal(&format!(
+                        "{}\n",
+                        gettext_f("Invalid song link: {url}", &[("url", &url)])
+                    ));
+                    return glib::ExitCode::FAILURE;
+                }
+            }
+        }
+        if let Ok(Some(text)) = options.lookup::<String>("search") {
+            actions.push(Action::SearchSongs(text));
+        }
+        // 其余参数为分享链接，例如桌面环境打开 orpheus:// 链接时传入
+        for arg in command_line.arguments().iter().skip(1) {
+            let arg = arg.to_string_lossy();
+            match parse_link(&arg) {
+                Some(link) => actions.push(Action::OpenLink(link, link_position(&arg))),
+                None => {
+                    command_line.printerr_literal(&format!(
+                        "{}\n",
+                        gettext_f("Invalid link: {url}", &[("url", &arg)])
+                    ));
+                    return glib::ExitCode::FAILURE;
+                }
+            }
+        }
+
+        // 未指定控制命令或首次启动时显示窗口（无窗口模式除外），否则不打扰当前界面
+        if (actions.is_empty() && !headless) || playback.is_none() {
+            self.activate();
+        }
+        for action in actions {
+            self.imp().sender.send_blocking(action).unwrap();
+        }
+        glib::ExitCode::SUCCESS
+    }
+
     pub fn graceful_quit(&self) {
-        if let Some(window) = self.imp().window.get().and_then(WeakRef::upgrade) {
-            window.imp().graceful_quitting.set(true);
-            window.imp().player_controls.save_current_state();
-            window.imp().tray_handle.borrow_mut().stop();
+        let imp = self.imp();
+        imp.quitting.set(true);
+        if let Some(playback) = imp.playback.get() {
+            playback.save_current_state();
         }
+        imp.tray_handle.borrow_mut().stop();
+        imp.hold_guard.take();
         self.quit();
     }
 
+    // 根据设置启动远程控制接口，相关设置变化时重启
+    fn setup_remote_api(&self) {
+        let settings = self.settings();
+        for key in [
+            "remote-api",
+            "remote-api-port",
+            "remote-api-lan",
+            "remote-api-token",
+        ] {
+            settings.connect_changed(
+                Some(key),
+                clone!(
+                    #[weak(rename_to = app)]
+                    self,
+                    move |_, _| app.restart_remote_api()
+                ),
+            );
+        }
+        self.restart_remote_api();
+    }
+
+    // 生成远程控制令牌并写入设置，HTTP 接口与 MPD 服务共用
+    fn generate_remote_token(&self) {
+        match generate_token() {
+            Ok(token) => {
+                self.settings().set_string("remote-api-token", &token).ok();
+            }
+            Err(err) => error!("生成远程控制令牌失败: {:?}", err),
+        }
+    }
+
+    fn restart_remote_api(&self) {
+        let imp = self.imp();
+        let stopped = imp.remote_api.take().is_some();
+        let pending = imp.remote_api_timer.take().map(|id| id.remove()).is_some();
+        let settings = self.settings();
+        if !settings.boolean("remote-api") {
+            return;
+        }
+        // 首次启用时生成随机令牌，写入设置后会再次触发重启
+        if settings.string("remote-api-token").is_empty() {
+            self.generate_remote_token();
+            return;
+        }
+        // 旧服务在后台线程中关闭，稍后再监听以免端口仍被占用；
+        // 连续修改端口时只在最后一次修改后启动
+        let delay = if stopped || pending { 1000 } else { 0 };
+        let id = glib::timeout_add_local_once(
+            Duration::from_millis(delay),
+            clone!(
+                #[weak(rename_to = app)]
+                self,
+                move || {
+                    app.imp().remote_api_timer.take();
+                    app.start_remote_api();
+                }
+            ),
+        );
+        imp.remote_api_timer.replace(Some(id));
+    }
+
+    fn start_remote_api(&self) {
+        let imp = self.imp();
+        let settings = self.settings();
+        if !settings.boolean("remote-api") || imp.remote_api.borrow().is_some() {
+            return;
+        }
+        let port = settings.uint("remote-api-port") as u16;
+        let lan = settings.boolean("remote-api-lan");
+        let token = settings.string("remote-api-token").to_string();
+        match RemoteApi::start(imp.sender.clone(), port, lan, token) {
+            Ok(api) => {
+                imp.remote_api.replace(Some(api));
+            }
+            Err(err) => {
+                error!("启动远程控制服务失败: {:?}", err);
+                self.add_toast(gettext("Failed to start remote control server!"));
+            }
+        }
+    }
+
+    // 根据设置启动 MPD 服务，相关设置变化时重启
+    fn setup_mpd_server(&self) {
+        let settings = self.settings();
+        for key in [
+            "mpd-server",
+            "mpd-server-port",
+            "mpd-server-lan",
+            "remote-api-token",
+        ] {
+            settings.connect_changed(
+                Some(key),
+                clone!(
+                    #[weak(rename_to = app)]
+                    self,
+                    move |_, _| app.restart_mpd_server()
+                ),
+            );
+        }
+        self.restart_mpd_server();
+    }
+
+    fn restart_mpd_server(&self) {
+        let imp = self.imp();
+        let stopped = imp.mpd_server.take().is_some();
+        let settings = self.settings();
+        if !settings.boolean("mpd-server") {
+            return;
+        }
+        // 允许局域网访问时使用远程控制的令牌作为密码，没有令牌时先生成
+        if settings.boolean("mpd-server-lan") && settings.string("remote-api-token").is_empty() {
+            self.generate_remote_token();
+            return;
+        }
+        // 等待旧服务释放端口
+        let delay = if stopped { 500 } else { 0 };
+        glib::timeout_add_local_once(
+            Duration::from_millis(delay),
+            clone!(
+                #[weak(rename_to = app)]
+                self,
+                move || app.start_mpd_server()
+            ),
+        );
+    }
+
+    fn start_mpd_server(&self) {
+        let imp = self.imp();
+        let settings = self.settings();
+        if !settings.boolean("mpd-server") || imp.mpd_server.borrow().is_some() {
+            return;
+        }
+        let port = settings.uint("mpd-server-port") as u16;
+        let lan = settings.boolean("mpd-server-lan");
+        let password = lan.then(|| settings.string("remote-api-token").to_string());
+        match MpdServer::start(imp.sender.clone(), port, lan, password) {
+            Ok(server) => {
+                imp.mpd_server.replace(Some(server));
+            }
+            Err(err) => {
+                error!("启动 MPD 服务失败: {:?}", err);
+                self.add_toast(gettext("Failed to start MPD server!"));
+            }
+        }
+    }
+
+    fn notify_remote(&self, event: RemoteEvent) {
+        let imp = self.imp();
+        if let Some(api) = imp.remote_api.borrow().as_ref() {
+            api.broadcast(&event);
+        }
+        if let Some(server) = imp.mpd_server.borrow().as_ref() {
+            server.broadcast(&event);
+        }
+    }
+
+    fn local_music_folders(&self) -> Vec<PathBuf> {
+        self.settings()
+            .strv("local-music-folders")
+            .iter()
+            .map(|f| PathBuf::from(f.as_str()))
+            .collect()
+    }
+
+    // 扫描用户歌词目录，相关设置变化时重新扫描
+    fn setup_user_lyrics(&self) {
+        for key in ["prefer-user-lyrics", "user-lyrics-folders"] {
+            self.settings().connect_changed(
+                Some(key),
+                clone!(
+                    #[weak(rename_to = app)]
+                    self,
+                    move |_, _| {
+                        MAINCONTEXT.spawn_local_with_priority(Priority::LOW, async move {
+                            app.scan_user_lyrics().await;
+                        });
+                    }
+                ),
+            );
+        }
+        let s = self.clone();
+        MAINCONTEXT.spawn_local_with_priority(Priority::LOW, async move {
+            s.scan_user_lyrics().await;
+            // 歌词索引会用到用户歌词文件，扫描完成后再迁移
+            s.migrate_lyrics();
+        });
+    }
+
+    async fn scan_user_lyrics(&self) {
+        let settings = self.settings();
+        let folders: Vec<PathBuf> = if settings.boolean("prefer-user-lyrics") {
+            settings
+                .strv("user-lyrics-folders")
+                .iter()
+                .map(|f| PathBuf::from(f.as_str()))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        match gio::spawn_blocking(move || NcmClient::scan_user_lyric_files(&folders)).await {
+            Ok(num) => debug!("用户歌词目录中共有 {} 个歌词文件", num),
+            Err(err) => error!("扫描用户歌词目录失败: {:?}", err),
+        }
+    }
+
+    // 将旧版以歌曲信息命名的歌词缓存迁移为以歌曲 ID 命名，并重建歌词索引
+    // 播放列表与本地音乐中的歌曲直接迁移，歌词目录中剩余的旧版文件通过搜索确定歌曲 ID
+    fn migrate_lyrics(&self) {
+        let sender = self.imp().sender.clone();
+        MAINCONTEXT.spawn_local_with_priority(Priority::LOW, async move {
+            let res = gio::spawn_blocking(|| {
+                let mut sis = PlayList::load_from_file().get_list();
+                sis.extend(LocalLibrary::load_from_file().song_infos());
+                let migrated = sis
+                    .iter()
+                    .filter_map(|si| {
+                        netease_song_id(si).map(|id| (SongInfo { id, ..si.to_owned() }, id))
+                    })
+                    .filter(|(si, id)| migrate_legacy_lyric(si, *id))
+                    .count();
+                (migrated, rebuild_lyrics_index(sis), legacy_lyric_files())
+            })
+            .await;
+            match res {
+                Ok((migrated, indexed, legacy)) => {
+                    info!(
+                        "已迁移 {} 个歌词文件，索引 {} 首歌曲的歌词",
+                        migrated, indexed
+                    );
+                    if !legacy.is_empty() {
+                        sender
+                            .send(Action::MigrateLegacyLyrics(legacy))
+                            .await
+                            .unwrap();
+                    }
+                }
+                Err(err) => error!("迁移歌词文件失败: {:?}", err),
+            }
+        });
+    }
+
+    // 监视本地音乐目录，文件变动时重新扫描
+    fn watch_local_music(&self) {
+        let folders = self.local_music_folders();
+        let s = self.clone();
+        MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+            match gio::spawn_blocking(move || local_library_dirs(&folders)).await {
+                Ok(dirs) => s.watch_local_dirs(&dirs),
+                Err(err) => error!("列出本地音乐目录失败: {:?}", err),
+            }
+        });
+    }
+
+    // 替换为监视给定目录，扫描后调用以包含新建的子目录
+    fn watch_local_dirs(&self, dirs: &[PathBuf]) {
+        let imp = self.imp();
+        let mut monitors = imp.local_music_monitors.borrow_mut();
+        monitors.clear();
+        for dir in dirs {
+            let file = gio::File::for_path(dir);
+            match file.monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE)
+            {
+                Ok(monitor) => {
+                    let sender = imp.sender.clone();
+                    let debounce = imp.local_music_debounce.clone();
+                    monitor.connect_changed(move |_, _, _, event| {
+                        use gio::FileMonitorEvent::*;
+                        // 原地修改标签时只产生 Changed 及 ChangesDoneHint 事件
+                        if matches!(
+                            event,
+                            Created
+                                | Deleted
+                                | MovedIn
+                                | MovedOut
+                                | Renamed
+                                | Changed
+                                | ChangesDoneHint
+                        ) {
+                            let sender = sender.clone();
+                            debounce.debounce(3, move || {
+                                sender.send_blocking(Action::ScanLocalMusic).unwrap();
+                            });
+                        }
+                    });
+                    monitors.push(monitor);
+                }
+                Err(err) => warn!("无法监视目录 {:?}: {:?}", dir, err),
+            }
+        }
+    }
+
     fn init_ncmapi(&self, cli: NcmClient) -> NcmClient {
-        let window = self.imp().window.get().unwrap().upgrade().unwrap();
         let mut ncmapi = cli;
-        let proxy_address = window.settings().string("proxy-address").to_string();
+        let proxy_address = self.settings().string("proxy-address").to_string();
         if !proxy_address.is_empty() && ncmapi.set_proxy(proxy_address).is_err() {
             // do nothing
         }
@@ -335,11 +1253,9 @@ impl NeteaseCloudMusicGtk4Application {
             self.graceful_quit();
             return glib::ControlFlow::Continue;
         }
-        if self.active_window().is_none() {
-            return glib::ControlFlow::Continue;
-        }
 
-        let window = imp.window.get().unwrap().upgrade().unwrap();
+        let playback = self.playback().clone();
+        let window = self.window();
         let ncmapi = {
             let ncmapi_opt = { imp.ncmapi.borrow().as_ref().cloned() };
             if let Some(ncmapi) = ncmapi_opt {
@@ -358,54 +1274,65 @@ impl NeteaseCloudMusicGtk4Application {
                 let s = self.clone();
 
                 MAINCONTEXT.spawn_local_with_priority(Priority::HIGH_IDLE, async move {
-                    if !window.is_logined() {
-                        match ncmapi.client.login_status().await {
-                            Ok(login_info) => {
-                                debug!("获取用户信息成功: {:?}", login_info);
-                                window.set_uid(login_info.uid);
-
-                                ncmapi.save_cookie_jar_to_file();
-                                s.imp().ncmapi.replace(Some(ncmapi));
-
-                                sender
-                                    .send(Action::InitUserInfo(login_info.to_owned()))
-                                    .await
-                                    .unwrap();
-                                sender
-                                    .send(Action::SwitchUserMenuToUser(login_info, user_menu))
-                                    .await
-                                    .unwrap();
-                                sender.send(Action::InitMyPage).await.unwrap();
-                                sender
-                                    .send(Action::AddToast(gettext("Login successful!")))
-                                    .await
-                                    .unwrap();
-                            }
-                            Err(err) => {
-                                error!("获取用户信息失败！{:?}", err);
-                                sender
-                                    .send(Action::AddToast(gettext("Login failed!")))
-                                    .await
-                                    .unwrap();
-
-                                s.imp().ncmapi.replace(None);
-                                NcmClient::clean_cookie_file();
-                            }
+                    // 无窗口模式下已登录，之后打开的窗口只需刷新用户菜单与我的页面
+                    if s.is_logined() {
+                        if let Ok(login_info) = ncmapi.client.login_status().await {
+                            sender
+                                .send(Action::SwitchUserMenuToUser(login_info, user_menu))
+                                .await
+                                .unwrap();
+                            sender.send(Action::InitMyPage).await.unwrap();
                         }
+                        return;
                     }
-                });
-            }
-            Action::Logout => {
-                let sender = imp.sender.clone();
-                let s = self.clone();
-                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    match ncmapi.client.login_status().await {
+                        Ok(login_info) => {
+                            debug!("获取用户信息成功: {:?}", login_info);
+                            s.set_uid(login_info.uid);
+
+                            ncmapi.save_cookie_jar_to_file();
+                            s.imp().ncmapi.replace(Some(ncmapi));
+
+                            sender
+                                .send(Action::InitUserInfo(login_info.to_owned()))
+                                .await
+                                .unwrap();
+                            sender
+                                .send(Action::SwitchUserMenuToUser(login_info, user_menu))
+                                .await
+                                .unwrap();
+                            sender.send(Action::InitMyPage).await.unwrap();
+                            sender
+                                .send(Action::AddToast(gettext("Login successful!")))
+                                .await
+                                .unwrap();
+                        }
+                        Err(err) => {
+                            error!("获取用户信息失败！{:?}", err);
+                            sender
+                                .send(Action::AddToast(gettext("Login failed!")))
+                                .await
+                                .unwrap();
+
+                            s.imp().ncmapi.replace(None);
+                            NcmClient::clean_cookie_file();
+                        }
+                    }
+                });
+            }
+            Action::Logout => {
+                let sender = imp.sender.clone();
+                let s = self.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
                     ncmapi.client.logout().await;
 
                     s.imp().ncmapi.replace(None);
                     NcmClient::clean_cookie_file();
 
-                    window.logout();
-                    window.switch_my_page_to_logout();
+                    s.logout();
+                    if let Some(window) = window {
+                        window.switch_my_page_to_logout();
+                    }
                     sender.send(Action::SwitchUserMenuToQr).await.unwrap();
                     sender
                         .send(Action::AddToast(gettext("Logout!")))
@@ -414,418 +1341,124 @@ impl NeteaseCloudMusicGtk4Application {
                 });
             }
             Action::InitUserInfo(login_info) => {
+                let s = self.clone();
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
                     match ncmapi.client.user_song_id_list(login_info.uid).await {
                         Ok(song_ids) => {
-                            window.set_user_like_songs(&song_ids);
+                            s.set_user_like_songs(&song_ids);
                         }
                         Err(err) => error!("{:?}", err),
                     }
                 });
             }
-            Action::TryUpdateQrCode => {
-                if !window.is_logined() && window.is_user_menu_active(UserMenuChild::Qr) {
-                    let sender = imp.sender.clone();
-                    MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                        if let Ok((path, unikey)) = ncmapi.create_qrcode().await {
-                            sender.send(Action::SetQrImage(path)).await.unwrap();
-                            sender.send(Action::CheckQrTimeout(unikey)).await.unwrap();
-                        }
-                    });
-                }
-            }
-            Action::SetQrImage(path) => {
-                window.set_user_qrimage(path);
-            }
-            Action::CheckQrTimeout(unikey) => {
-                if let Ok(key) = imp.unikey.read() {
-                    if unikey != *key {
-                        let sender = imp.sender.clone();
-                        sender
-                            .send_blocking(Action::CheckQrTimeoutCb(unikey))
-                            .unwrap();
-                    }
-                }
+            Action::AddToast(mes) => {
+                self.add_toast(mes);
             }
-            Action::CheckQrTimeoutCb(unikey) => {
-                debug!("检查登录二维码状态，unikey={}", unikey);
-                {
-                    let mut key = imp.unikey.write().unwrap();
-                    *key = unikey.clone();
-                }
-                let sender = imp.sender.clone();
-                let key = imp.unikey.clone();
+            Action::DownloadImage(url, path, width, height, callback) => {
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    let mut send_toast = true;
-                    loop {
-                        {
-                            let key = key.read().unwrap();
-                            if *key != unikey {
-                                warn!("unikey 已失效，unikey={}", unikey);
-                                break;
-                            }
-                        }
-                        match  ncmapi.client.login_qr_check(unikey.to_owned()).await {
-                            Ok(msg) => {
-                                match msg.code {
-                                    // 已过期
-                                    800 => {
-                                        debug!("二维码已过期，unikey={}", unikey);
-                                        sender.send(Action::SetQrImageTimeout).await.unwrap();
-                                        break;
-                                    }
-                                    // 等待扫码
-                                    801 => {
-                                        debug!("等待扫码，unikey={}", unikey);
-                                    },
-                                    // 等待确认
-                                    802 => {
-                                        debug!("等待app端确认，unikey={}", unikey);
-                                        if send_toast {
-                                            sender
-                                                .send(Action::AddToast(gettext("Have scanned the QR code, waiting for confirmation!")))
-                                                .await.unwrap();
-                                            send_toast = false;
-                                        }
-                                    }
-                                    // 登录成功
-                                    803 => {
-                                        debug!("扫码登录成功，unikey={}", unikey);
-                                        let cookie_jar = ncmapi.client.cookie_jar().cloned().unwrap_or_else(|| {
-                                            error!("No login cookie found");
-                                            CookieJar::new()
-                                        });
-                                        sender.send(Action::CheckLogin(UserMenuChild::Qr, cookie_jar)).await.unwrap();
-                                        break;
-                                    }
-                                    _ => break,
-                                }
-                            },
-                            Err(err) => error!("{:?}", err),
+                    if ncmapi
+                        .client
+                        .download_img(url, path, width, height)
+                        .await
+                        .is_ok()
+                    {
+                        if let Some(cb) = callback {
+                            cb(());
                         }
-                        timeout_future_seconds(1).await;
                     }
                 });
             }
-            Action::SetQrImageTimeout => {
-                window.set_user_qrimage_timeout();
-            }
-            Action::SwitchUserMenuToPhone => {
-                window.switch_user_menu_to_phone();
-            }
-            Action::SwitchUserMenuToQr => {
-                window.switch_user_menu_to_qr();
-            }
-            Action::GetCaptcha(ctcode, phone) => {
-                let sender = imp.sender.clone();
+            Action::GetTrackNumber(id, callback) => {
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    match ncmapi.client.captcha(ctcode, phone).await {
-                        Ok(..) => {
-                            debug!("发送获取验证码请求...");
-                            sender
-                            .send(Action::AddToast(gettext(
-                                "Please pay attention to check the cell phone verification code!",
-                            )))
-                            .await.unwrap();
-                        }
-                        Err(err) => {
-                            warn!("获取验证码失败! {:?}", err);
-                            sender
-                                .send(Action::AddToast(gettext(
-                                    "Failed to get verification code!",
-                                )))
-                                .await
-                                .unwrap();
-                        }
+                    match ncmapi.track_number(id).await {
+                        Ok(track) => callback(track),
+                        Err(err) => debug!("获取曲目序号失败: {:?}", err),
                     }
                 });
             }
-            Action::CaptchaLogin(ctcode, phone, captcha) => {
+            Action::AddPlay(song_info) => {
+                playback.add_song(song_info.clone());
                 let sender = imp.sender.clone();
-                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    debug!("使用验证码登录：{}", captcha);
-                    if let Ok(_login_info) =
-                        ncmapi.client.login_cellphone(ctcode, phone, captcha).await
-                    {
-                        let cookie_jar = ncmapi.client.cookie_jar().cloned().unwrap_or_else(|| {
-                            error!("No login cookie found");
-                            CookieJar::new()
-                        });
-                        sender
-                            .send(Action::CheckLogin(UserMenuChild::Phone, cookie_jar))
-                            .await
-                            .unwrap();
-                    } else {
-                        error!("登录失败！");
-                        sender
-                            .send(Action::AddToast(gettext("Login failed!")))
-                            .await
-                            .unwrap();
-                    }
-                });
+                sender.send_blocking(Action::Play(song_info)).unwrap();
             }
-            Action::SwitchUserMenuToUser(login_info, menu) => {
-                window.switch_user_menu_to_user(login_info.clone(), menu);
-                let avatar_url = login_info.avatar_url;
-                let mut path = CACHE.clone();
-                path.push("avatar.jpg");
-                window.set_avatar(avatar_url, path);
+            Action::PlayNextSong => {
+                playback.next_song();
             }
-            Action::AddToast(mes) => {
-                window.add_toast(mes);
+            Action::PlayPreviousSong => {
+                playback.prev_song();
             }
-            Action::InitCarousel => {
+            Action::Play(song_info) => {
                 let sender = imp.sender.clone();
-                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    match ncmapi.client.banners().await {
-                        Ok(banners) => {
-                            debug!("获取轮播信息: {:?}", banners);
-                            for banner in banners {
-                                window.add_carousel(banner);
-                            }
+                let music_rate = song_info
+                    .quality
+                    .selected
+                    .map(NcmClient::get_quality_index)
+                    .unwrap_or_else(|| playback.settings().uint("music-rate"));
+                let path = crate::path::get_music_cache_path(song_info.id, music_rate);
 
-                            // auto check login after banners
-                            // https://github.com/Binaryify/NeteaseCloudMusicApi/issues/1217
-                            if let Some(cookie_jar) = NcmClient::load_cookie_jar_from_file() {
+                if !path.exists() {
+                    MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                        if song_info.song_url.is_empty() {
+                            if let Ok(song_url) =
+                                ncmapi.songs_url(&[song_info.id], music_rate).await
+                            {
+                                debug!("获取歌曲播放链接: {:?}", song_url);
+                                if let Some(song_url) = song_url.first() {
+                                    let mut song_info = song_info;
+                                    song_info.song_url = song_url.url.to_owned();
+                                    song_info.quality.actual = Some(song_url.quality);
+                                    if !song_info.quality.available.contains(&song_url.quality) {
+                                        song_info.quality.available.push(song_url.quality);
+                                    }
+                                    if song_info.quality.selected.is_none() {
+                                        song_info.quality.selected = Some(song_url.quality);
+                                    }
+                                    sender.send(Action::PlayStart(song_info)).await.unwrap();
+                                } else {
+                                    error!("获取歌曲播放链接失败: {:?}", &[song_info.id]);
+                                    sender
+                                        .send(Action::AddToast(gettext_f(
+                                            "Get [{name}] Playback link failed!",
+                                            &[("name", &song_info.name)],
+                                        )))
+                                        .await
+                                        .unwrap();
+                                    timeout_future_seconds(2).await;
+                                    sender.send(Action::PlayNextSong).await.unwrap();
+                                }
+                            } else {
+                                error!("获取歌曲播放链接失败: {:?}", &[song_info.id]);
                                 sender
-                                    .send(Action::CheckLogin(UserMenuChild::Qr, cookie_jar))
+                                    .send(Action::AddToast(gettext_f(
+                                        "Get [{name}] Playback link failed!",
+                                        &[("name", &song_info.name)],
+                                    )))
                                     .await
                                     .unwrap();
+                                timeout_future_seconds(2).await;
+                                sender.send(Action::PlayNextSong).await.unwrap();
                             }
+                        } else {
+                            sender.send(Action::PlayStart(song_info)).await.unwrap();
                         }
-                        Err(err) => {
-                            error!("获取首页轮播信息失败！{:?}", err);
-                            timeout_future(Duration::from_millis(500)).await;
-                            sender.send(Action::InitCarousel).await.unwrap();
-                        }
-                    }
-                });
+                    });
+                } else {
+                    let song_info = SongInfo {
+                        song_url: format!("file://{}", path.to_str().unwrap().to_owned()),
+                        ..song_info
+                    };
+                    sender.send_blocking(Action::PlayStart(song_info)).unwrap();
+                }
             }
-            Action::InitTopPicks => {
+            Action::PlayStart(song_info) => {
+                self.notify_remote(RemoteEvent::PlayStart {
+                    song: RemoteSong::from(&song_info),
+                });
+                // 加载歌词，桌面歌词、MPRIS 与正在播放文件均需要当前歌词
                 let sender = imp.sender.clone();
-                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    match ncmapi.client.top_song_list("全部", "hot", 0, 8).await {
-                        Ok(song_list) => {
-                            debug!("获取热门推荐信息：{:?}", song_list);
-                            sender.send(Action::SetupTopPicks(song_list)).await.unwrap();
-                        }
-                        Err(err) => {
-                            error!("获取热门推荐信息失败！{:?}", err);
-                            timeout_future(Duration::from_millis(500)).await;
-                            sender.send(Action::InitTopPicks).await.unwrap();
-                        }
-                    }
-                });
-            }
-            Action::ToTopPicksPage => {
-                let page = window.init_picks_songlist();
-                window.page_new(&page, gettext("all top picks").as_str(), "ToTopPicksPage");
-                let page = page.downgrade();
-
-                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    if let Some(SearchResult::SongLists(sls)) = window
-                        .action_search(ncmapi, String::new(), SearchType::TopPicks, 0, 50)
-                        .await
-                    {
-                        if let Some(page) = page.upgrade() {
-                            page.update_songlist(&sls);
-                        }
-                    }
-                });
-            }
-            Action::DownloadImage(url, path, width, height, callback) => {
-                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    if ncmapi
-                        .client
-                        .download_img(url, path, width, height)
-                        .await
-                        .is_ok()
-                    {
-                        if let Some(cb) = callback {
-                            cb(());
-                        }
-                    }
-                });
-            }
-            Action::SetupTopPicks(song_list) => {
-                window.setup_top_picks(song_list);
-            }
-            Action::InitNewAlbums => {
-                let sender = imp.sender.clone();
-                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    match ncmapi.client.new_albums("ALL", 0, 8).await {
-                        Ok(song_list) => {
-                            debug!("获取新碟上架信息：{:?}", song_list);
-                            sender
-                                .send(Action::SetupNewAlbums(song_list))
-                                .await
-                                .unwrap();
-                        }
-                        Err(err) => {
-                            error!("获取新碟上架信息失败！{:?}", err);
-                            timeout_future(Duration::from_millis(500)).await;
-                            sender.send(Action::InitNewAlbums).await.unwrap();
-                        }
-                    }
-                });
-            }
-            Action::ToAllAlbumsPage => {
-                let page = window.init_all_albums();
-
-                window.page_new(&page, gettext("all new albums").as_str(), "ToAllAlbumsPage");
-                let page = page.downgrade();
-
-                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    if let Some(SearchResult::SongLists(sls)) = window
-                        .action_search(ncmapi, String::new(), SearchType::AllAlbums, 0, 50)
-                        .await
-                    {
-                        if let Some(page) = page.upgrade() {
-                            page.update_songlist(&sls);
-                        }
-                    }
-                });
-            }
-            Action::SetupNewAlbums(song_list) => {
-                window.setup_new_albums(song_list);
-            }
-            Action::BannerTo(banner) => {
-                let sender = imp.sender.clone();
-                match banner.target_type {
-                    TargetType::Song => {
-                        MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                            match ncmapi.client.songs_detail(&[banner.target_id]).await {
-                                Ok(songs) => {
-                                    debug!("获取轮播歌曲信息：{:?}", songs);
-                                    if let Some(song) = songs.first() {
-                                        sender.send(Action::AddPlay(song.clone())).await.unwrap();
-                                    }
-                                }
-                                Err(err) => {
-                                    error!("获取轮播歌曲信息失败！{:?}", err);
-                                }
-                            }
-                        });
-                    }
-                    TargetType::Album => {
-                        MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                            match ncmapi.client.album(banner.target_id).await {
-                                Ok(album) => {
-                                    debug!("获取轮播专辑信息：{:?}", album);
-                                    let page = window.init_songlist_page(
-                                        &SongList {
-                                            id: album.id,
-                                            name: album.name.to_owned(),
-                                            cover_img_url: album.pic_url.to_owned(),
-                                            author: album.artist_name.to_owned(),
-                                            special_type: 0,
-                                        },
-                                        true,
-                                    );
-                                    window.page_new(&page, &album.name, "Album");
-                                    let page = page.downgrade();
-                                    let detal_dynamic_as =
-                                        ncmapi.client.album_detail_dynamic(album.id);
-                                    let dy = detal_dynamic_as.await.unwrap_or_else(|err| {
-                                        error!("{:?}", err);
-                                        AlbumDetailDynamic::default()
-                                    });
-                                    let detail = SongListDetail::Album(album, dy);
-                                    if let Some(page) = page.upgrade() {
-                                        window.update_songlist_page(page, &detail);
-                                    }
-                                }
-                                Err(err) => {
-                                    error!("获取轮播专辑信息失败！{:?}", err);
-                                }
-                            }
-                        });
-                    }
-                    TargetType::Unknown => (),
-                }
-            }
-            Action::AddPlay(song_info) => {
-                window.add_play(song_info.clone());
-                let sender = imp.sender.clone();
-                sender.send_blocking(Action::Play(song_info)).unwrap();
-            }
-            Action::PlayNextSong => {
-                window.play_next();
-            }
-            Action::PlayPreviousSong => {
-                window.play_prev();
-            }
-            Action::Play(song_info) => {
-                let sender = imp.sender.clone();
-                let music_rate = song_info
-                    .quality
-                    .selected
-                    .map(NcmClient::get_quality_index)
-                    .unwrap_or_else(|| window.settings().uint("music-rate"));
-                let path = crate::path::get_music_cache_path(song_info.id, music_rate);
-
-                if !path.exists() {
-                    MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                        if song_info.song_url.is_empty() {
-                            if let Ok(song_url) =
-                                ncmapi.songs_url(&[song_info.id], music_rate).await
-                            {
-                                debug!("获取歌曲播放链接: {:?}", song_url);
-                                if let Some(song_url) = song_url.first() {
-                                    let mut song_info = song_info;
-                                    song_info.song_url = song_url.url.to_owned();
-                                    song_info.quality.actual = Some(song_url.quality);
-                                    if !song_info.quality.available.contains(&song_url.quality) {
-                                        song_info.quality.available.push(song_url.quality);
-                                    }
-                                    if song_info.quality.selected.is_none() {
-                                        song_info.quality.selected = Some(song_url.quality);
-                                    }
-                                    sender.send(Action::PlayStart(song_info)).await.unwrap();
-                                } else {
-                                    error!("获取歌曲播放链接失败: {:?}", &[song_info.id]);
-                                    sender
-                                        .send(Action::AddToast(gettext_f(
-                                            "Get [{name}] Playback link failed!",
-                                            &[("name", &song_info.name)],
-                                        )))
-                                        .await
-                                        .unwrap();
-                                    timeout_future_seconds(2).await;
-                                    sender.send(Action::PlayNextSong).await.unwrap();
-                                }
-                            } else {
-                                error!("获取歌曲播放链接失败: {:?}", &[song_info.id]);
-                                sender
-                                    .send(Action::AddToast(gettext_f(
-                                        "Get [{name}] Playback link failed!",
-                                        &[("name", &song_info.name)],
-                                    )))
-                                    .await
-                                    .unwrap();
-                                timeout_future_seconds(2).await;
-                                sender.send(Action::PlayNextSong).await.unwrap();
-                            }
-                        } else {
-                            sender.send(Action::PlayStart(song_info)).await.unwrap();
-                        }
-                    });
-                } else {
-                    let song_info = SongInfo {
-                        song_url: format!("file://{}", path.to_str().unwrap().to_owned()),
-                        ..song_info
-                    };
-                    sender.send_blocking(Action::PlayStart(song_info)).unwrap();
-                }
-            }
-            Action::PlayStart(song_info) => {
-                // 启用桌面歌词
-                if window.settings().boolean("desktop-lyrics") {
-                    let sender = imp.sender.clone();
-                    sender
-                        .send_blocking(Action::UpdateLyrics(song_info.to_owned(), 0))
-                        .unwrap();
-                };
+                sender
+                    .send_blocking(Action::UpdateLyrics(song_info.to_owned(), 0))
+                    .unwrap();
                 debug!("播放歌曲: {:?}", song_info);
 
                 let sender = imp.sender.clone();
@@ -838,42 +1471,30 @@ impl NeteaseCloudMusicGtk4Application {
                     .unwrap();
                 sender.send_blocking(Action::UpdateTrayPlaying(true)).unwrap();
 
-                window.play(song_info);
-            }
-            Action::ToSongListPage(songlist) => {
-                let page = window.init_songlist_page(&songlist, false);
-                window.page_new(&page, &songlist.name, "ToSongListPage");
-                let page = page.downgrade();
-
-                let sender = imp.sender.clone();
-                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    let detal_dynamic_as = ncmapi.client.songlist_detail_dynamic(songlist.id);
-                    match ncmapi.client.song_list_detail(songlist.id).await {
-                        Ok(detail) => {
-                            debug!("获取歌单详情: {:?}", detail);
-                            let dy = detal_dynamic_as.await.unwrap_or_else(|err| {
-                                error!("{:?}", err);
-                                PlayListDetailDynamic::default()
-                            });
-                            let detail = SongListDetail::PlayList(detail, dy);
-                            if let Some(page) = page.upgrade() {
-                                window.update_songlist_page(page, &detail);
-                            }
-                        }
-                        Err(err) => {
-                            error!("获取歌单详情失败: {:?}", err);
-                            sender
-                                .send(Action::AddToast(gettext(
-                                    "Failed to get song list details!",
-                                )))
-                                .await
-                                .unwrap();
-                        }
-                    }
-                });
+                self.play(song_info);
             }
             Action::PersistVolume(value) => {
-                window.persist_volume(value);
+                playback.persist_volume(value);
+            }
+            Action::SetVolume(volume) => {
+                playback.set_volume(volume);
+            }
+            Action::AdjustVolume(delta) => {
+                let volume = playback.property::<f64>("volume") + delta;
+                playback.set_volume(volume.clamp(0.0, 1.0));
+            }
+            Action::SetLoops(state) => {
+                playback.set_loops(state);
+            }
+            Action::SetMusicRate(rate) => {
+                self.settings().set_uint("music-rate", rate).ok();
+            }
+            Action::ToggleHeartbeat => match playback.get_current_song() {
+                Some(si) => imp.sender.send_blocking(Action::Moved(si)).unwrap(),
+                None => self.add_toast(gettext("No playable songs found！")),
+            },
+            Action::GetSongUrl(song_info) if is_local_song(&song_info) => {
+                playback.set_song_url(song_info);
             }
             Action::GetSongUrl(song_info) => {
                 let sender = imp.sender.clone();
@@ -881,7 +1502,7 @@ impl NeteaseCloudMusicGtk4Application {
                     .quality
                     .selected
                     .map(NcmClient::get_quality_index)
-                    .unwrap_or_else(|| window.settings().uint("music-rate"));
+                    .unwrap_or_else(|| playback.settings().uint("music-rate"));
                 let path = crate::path::get_music_cache_path(song_info.id, music_rate);
 
                 if !path.exists() {
@@ -907,75 +1528,112 @@ impl NeteaseCloudMusicGtk4Application {
                         song_url: format!("file://{}", path.to_str().unwrap().to_owned()),
                         ..song_info
                     };
-                    window.set_song_url(song_info);
+                    playback.set_song_url(song_info);
                 }
             }
             Action::SetSongUrl(song_info) => {
-                window.set_song_url(song_info);
+                playback.set_song_url(song_info);
             }
-            Action::ToAlbumPage(songlist) => {
-                let page = window.init_songlist_page(&songlist, true);
-                window.page_new(&page, &songlist.name, "ToAlbumPage");
-                let page = page.downgrade();
-
-                let sender = imp.sender.clone();
+            Action::AddPlayList(sis, is_play) => {
+                playback.add_list(sis);
+                if is_play {
+                    imp.sender.send_blocking(Action::PlayListStart).unwrap();
+                }
+            }
+            Action::PlayListStart => match playback.get_current_song() {
+                Some(song_info) => imp.sender.send_blocking(Action::Play(song_info)).unwrap(),
+                None => self.add_toast(gettext("No playable songs found！")),
+            },
+            Action::ClearPlayList => {
+                playback.switch_stop();
+                playback.add_list(Vec::new());
+                if let Some(window) = window {
+                    window.clear_playlist();
+                }
+            }
+            Action::AddTrackById(id, is_play) => {
+                let sender = imp.sender.clone();
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    let detal_dynamic_as = ncmapi.client.album_detail_dynamic(songlist.id);
-                    match ncmapi.client.album(songlist.id).await {
-                        Ok(detail) => {
-                            debug!("获取专辑详情: {:?}", detail);
-                            let dy = detal_dynamic_as.await.unwrap_or_else(|err| {
-                                error!("{:?}", err);
-                                AlbumDetailDynamic::default()
-                            });
-                            let detail = SongListDetail::Album(detail, dy);
-                            if let Some(page) = page.upgrade() {
-                                window.update_songlist_page(page, &detail);
+                    match ncmapi.client.songs_detail(&[id]).await {
+                        Ok(songs) => {
+                            let Some(song) = songs.into_iter().next() else {
+                                return;
+                            };
+                            if is_play {
+                                sender.send(Action::AddPlay(song)).await.unwrap();
+                            } else {
+                                playback.append_list(&mut vec![song]);
                             }
                         }
                         Err(err) => {
-                            error!("获取专辑详情失败: {:?}", err);
+                            error!("获取歌曲信息失败: {:?}", err);
                             sender
-                                .send(Action::AddToast(gettext("Failed to get album details!")))
+                                .send(Action::AddToast(gettext(
+                                    "Request for interface failed, please try again!",
+                                )))
                                 .await
                                 .unwrap();
                         }
                     }
                 });
             }
-            Action::ToRadioPage(songlist) => {
-                let page = window.init_songlist_page(&songlist, true);
-                window.page_new(&page, &songlist.name, "ToRadioPage");
-                let page = page.downgrade();
-
+            Action::PlayTrackAt(id, position) => {
                 let sender = imp.sender.clone();
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    match ncmapi.client.radio_program(songlist.id, 0, 1001).await {
+                    match ncmapi.client.songs_detail(&[id]).await {
+                        Ok(songs) => {
+                            let Some(song) = songs.into_iter().next() else {
+                                return;
+                            };
+                            playback.add_song(song.clone());
+                            playback.seek_on_start(position * 1_000_000);
+                            sender.send(Action::Play(song)).await.unwrap();
+                        }
+                        Err(err) => {
+                            error!("获取歌曲信息失败: {:?}", err);
+                            sender
+                                .send(Action::AddToast(gettext(
+                                    "Request for interface failed, please try again!",
+                                )))
+                                .await
+                                .unwrap();
+                        }
+                    }
+                });
+            }
+            Action::PlaySongList(id) => {
+                let sender = imp.sender.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    match ncmapi.client.song_list_detail(id).await {
                         Ok(detail) => {
-                            debug!("获取电台详情: {:?}", detail);
-                            let detail = SongListDetail::Radio(detail);
-                            if let Some(page) = page.upgrade() {
-                                window.update_songlist_page(page, &detail);
-                            }
+                            debug!("获取歌单详情: {:?}", detail);
+                            sender
+                                .send(Action::AddPlayList(detail.songs, true))
+                                .await
+                                .unwrap();
                         }
                         Err(err) => {
-                            error!("获取电台详情失败: {:?}", err);
+                            error!("获取歌单详情失败: {:?}", err);
                             sender
-                                .send(Action::AddToast(gettext("Failed to get radio details!")))
+                                .send(Action::AddToast(gettext(
+                                    "Failed to get song list details!",
+                                )))
                                 .await
                                 .unwrap();
                         }
                     }
                 });
             }
-            Action::AddPlayList(sis, is_play) => {
-                window.add_playlist(sis, is_play);
+            Action::TogglePlayPause => {
+                playback.toggle_play_pause();
             }
-            Action::PlayListStart => {
-                window.playlist_start();
+            Action::SetPlaying(playing) => {
+                if playback.is_playing() != playing {
+                    playback.toggle_play_pause();
+                }
             }
-            Action::TogglePlayPause => {
-                window.toggle_play_pause();
+            Action::SeekTo(position) => {
+                playback.seek(position * 1000);
             }
             Action::LikeSongList(id, is_like, callback) => {
                 let sender = imp.sender.clone();
@@ -1037,10 +1695,11 @@ impl NeteaseCloudMusicGtk4Application {
             }
             Action::LikeSong(id, is_like, callback) => {
                 let sender = imp.sender.clone();
+                let s = self.clone();
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
                     if ncmapi.client.like(is_like, id).await {
                         debug!("收藏/取消收藏歌曲: {:?}", id);
-                        window.set_like_song(id, is_like);
+                        s.set_like_song(id, is_like);
                         sender
                             .send(Action::AddToast(if is_like {
                                 gettext("Songs have been collected!")
@@ -1065,114 +1724,1032 @@ impl NeteaseCloudMusicGtk4Application {
                     }
                 });
             }
-            Action::Moved(si) => {
+            Action::LikeCurrentSong(like) => {
+                let Some(si) = playback.get_current_song() else {
+                    return glib::ControlFlow::Continue;
+                };
+                let Some(id) = netease_song_id(&si) else {
+                    self.add_toast(gettext(
+                        "Local songs not matched to NetEase cannot be liked!",
+                    ));
+                    return glib::ControlFlow::Continue;
+                };
+                let like =
+                    like.unwrap_or_else(|| !self.get_song_likes(std::slice::from_ref(&si))[0]);
+                let sender = imp.sender.clone();
+                sender.send_blocking(Action::LikeSong(id, like, None)).unwrap();
+            }
+            Action::TrashCurrentSong => {
+                let Some(si) = playback.get_current_song() else {
+                    return glib::ControlFlow::Continue;
+                };
+                let Some(id) = netease_song_id(&si) else {
+                    return glib::ControlFlow::Continue;
+                };
+                let sender = imp.sender.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    if ncmapi.client.fm_trash(id).await {
+                        debug!("歌曲移入垃圾桶: {:?}", id);
+                        playback.remove_song(si);
+                        if let Some(window) = window {
+                            window.update_playlist_page();
+                        }
+                        sender
+                            .send(Action::AddToast(gettext("Song moved to trash!")))
+                            .await
+                            .unwrap();
+                    } else {
+                        error!("歌曲移入垃圾桶失败: {:?}", id);
+                        sender
+                            .send(Action::AddToast(gettext("Failed to move song to trash!")))
+                            .await
+                            .unwrap();
+                    }
+                });
+            }
+            Action::DownloadSong(song_info) => {
+                let sender = imp.sender.clone();
+                let settings = self.settings();
+                let music_rate = song_info
+                    .quality
+                    .selected
+                    .map(NcmClient::get_quality_index)
+                    .unwrap_or_else(|| settings.uint("music-rate"));
+                let download_path = settings.string("download-path");
+                let dir = if download_path.is_empty() {
+                    glib::user_special_dir(glib::UserDirectory::Music)
+                        .unwrap_or_else(glib::home_dir)
+                } else {
+                    PathBuf::from(download_path.as_str())
+                };
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    match ncmapi.download_song(&song_info, music_rate, &dir).await {
+                        Ok(path) => {
+                            debug!("下载歌曲完成: {:?}", path);
+                            sender
+                                .send(Action::AddToast(gettext_f(
+                                    "Downloaded [{name}]",
+                                    &[("name", &song_info.name)],
+                                )))
+                                .await
+                                .unwrap();
+                        }
+                        Err(err) => {
+                            error!("下载歌曲失败: {:?}", err);
+                            sender
+                                .send(Action::AddToast(gettext_f(
+                                    "Failed to download [{name}]!",
+                                    &[("name", &song_info.name)],
+                                )))
+                                .await
+                                .unwrap();
+                        }
+                    }
+                });
+            }
+            Action::DownloadSongs(sis, dir) => {
+                let sender = imp.sender.clone();
+                let music_rate = self.settings().uint("music-rate");
+                sender
+                    .send_blocking(Action::AddToast(gettext_f(
+                        "Downloading {num} songs…",
+                        &[("num", &sis.len().to_string())],
+                    )))
+                    .unwrap();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    let mut failed = 0;
+                    for si in &sis {
+                        let rate = si
+                            .quality
+                            .selected
+                            .map(NcmClient::get_quality_index)
+                            .unwrap_or(music_rate);
+                        match ncmapi.download_song(si, rate, &dir).await {
+                            Ok(path) => debug!("下载歌曲完成: {:?}", path),
+                            Err(err) => {
+                                error!("下载歌曲失败: {} {:?}", si.name, err);
+                                failed += 1;
+                            }
+                        }
+                    }
+                    sender
+                        .send(Action::AddToast(gettext_f(
+                            "Downloaded {num} songs, {failed} failed",
+                            &[
+                                ("num", &(sis.len() - failed).to_string()),
+                                ("failed", &failed.to_string()),
+                            ],
+                        )))
+                        .await
+                        .unwrap();
+                });
+            }
+            Action::Moved(si) => {
+                let sender = imp.sender.clone();
+                let s = self.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    // toggle：已开启则直接关闭，不再拉取/追加
+                    let is_active = { *HEARTBEAT_ACTIVE.lock().unwrap() };
+                    if is_active {
+                        *HEARTBEAT_ACTIVE.lock().unwrap() = false;
+                        s.set_heartbeat_active(false);
+                        sender
+                            .send(Action::AddToast(gettext("Intelligent Mode Off")))
+                            .await
+                            .unwrap();
+                        return;
+                    }
+
+                    let uid = s.get_uid();
+                    if uid == 0 {
+                        sender
+                            .send(Action::AddToast(gettext("Please log in first!")))
+                            .await
+                            .unwrap();
+                        return;
+                    }
+
+                    // 本地歌曲使用匹配到的网易云歌曲 ID
+                    let Some(song_id) = netease_song_id(&si) else {
+                        sender
+                            .send(Action::AddToast(gettext("This local song is not matched yet!")))
+                            .await
+                            .unwrap();
+                        return;
+                    };
+
+                    let playlist_id = match favorite_playlist_id(&ncmapi, uid).await {
+                        Ok(pid) => pid,
+                        Err(toast) => {
+                            sender
+                                .send(Action::AddToast(gettext(toast)))
+                                .await
+                                .unwrap();
+                            return;
+                        }
+                    };
+
+                    match ncmapi
+                        .client
+                        .playmode_intelligence_list(song_id, playlist_id)
+                        .await
+                    {
+                        Ok(mut pl) => {
+                            debug!("获取心动歌曲：{:?}", pl);
+                            *HEARTBEAT_ACTIVE.lock().unwrap() = true;
+                            s.set_heartbeat_active(true);
+                            let mut pla = vec![si];
+                            pla.append(&mut pl);
+                            sender.send(Action::AddPlayList(pla, false)).await.unwrap();
+                            sender
+                                .send(Action::AddToast(gettext("Intelligent Mode")))
+                                .await
+                                .unwrap();
+                        }
+                        Err(err) => {
+                            error!("获取心动歌曲 {} 失败! {:?}", si.name, err);
+                            *HEARTBEAT_ACTIVE.lock().unwrap() = false;
+                            s.set_heartbeat_active(false);
+                            sender
+                                .send(Action::AddToast(gettext("Intelligent mode failed!")))
+                                .await
+                                .unwrap();
+                        }
+                    }
+                });
+            }
+            Action::HeartbeatExtend(si, ext_mode) => {
+                let sender = imp.sender.clone();
+                let s = self.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    if !*HEARTBEAT_ACTIVE.lock().unwrap() {
+                        return;
+                    }
+                    let uid = s.get_uid();
+                    if uid == 0 {
+                        return;
+                    }
+                    let Some(song_id) = netease_song_id(&si) else {
+                        return;
+                    };
+                    let playlist_id = match favorite_playlist_id(&ncmapi, uid).await {
+                        Ok(pid) => pid,
+                        Err(toast) => {
+                            sender
+                                .send(Action::AddToast(gettext(toast)))
+                                .await
+                                .unwrap();
+                            return;
+                        }
+                    };
+                    match ncmapi.client.playmode_intelligence_list(song_id, playlist_id).await {
+                        Ok(pl) => {
+                            debug!("追加心动歌曲：{:?}", pl);
+                            let mut new_list = pl;
+                            let appended = playback.append_list(&mut new_list);
+                            if let Some(song) = appended.first() {
+                                // 追加成功：自动播放追加的第一首并同步播放位置
+                                playback.sync_playlist_position(song.id);
+                                let index = playback.playlist_position();
+                                sender.send(Action::Play(song.to_owned())).await.unwrap();
+                                sender.send(Action::UpdatePlayListStatus(index)).await.unwrap();
+                            } else if ext_mode == HeartbeatExtendMode::ListLoop {
+                                // 追加为空（全部重复）：列表循环恢复从头播放
+                                playlist_restart(&playback, &sender).await;
+                            }
+                        }
+                        Err(err) => {
+                            error!("获取心动歌曲失败! {:?}", err);
+                            if ext_mode == HeartbeatExtendMode::ListLoop {
+                                // 追加失败：列表循环恢复从头播放
+                                playlist_restart(&playback, &sender).await;
+                            }
+                        }
+                    }
+                });
+            }
+            Action::Search(text, search_type, offset, limit, callback) => {
+                let s = self.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    let res = s
+                        .action_search(ncmapi, text, search_type, offset, limit)
+                        .await;
+                    if let Some(res) = res {
+                        callback(res);
+                    }
+                });
+            }
+            Action::AddLocalMusicFolder(path) => {
+                let settings = self.settings();
+                let mut folders = settings.strv("local-music-folders");
+                let folder = path.to_string_lossy();
+                if !folders.iter().any(|f| f.as_str() == folder) {
+                    folders.push(folder.as_ref().into());
+                    if let Err(err) = settings.set_strv("local-music-folders", folders) {
+                        error!("保存本地音乐目录失败: {:?}", err);
+                    }
+                }
+                imp.sender.send_blocking(Action::ScanLocalMusic).unwrap();
+            }
+            Action::ScanLocalMusic => {
+                let folders = self.local_music_folders();
+                let page = imp.local_music_page.borrow().clone();
+                let s = self.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    let res = blocking(move || {
+                        let mut library = LocalLibrary::load_from_file().scan(&folders);
+                        // 保存前合并扫描期间确认的匹配
+                        let sis = LocalLibrary::update(move |current| {
+                            library.keep_matches(current);
+                            *current = library;
+                            current.song_infos()
+                        });
+                        Ok((sis, local_library_dirs(&folders)))
+                    })
+                    .await;
+                    match res {
+                        Ok((sis, dirs)) => {
+                            s.watch_local_dirs(&dirs);
+                            if let Some(page) = page.and_then(|p| p.upgrade()) {
+                                page.init_page(&gettext("Local Music"), SearchType::LocalMusic);
+                                page.update_songs(&sis, &s.get_song_likes(&sis));
+                            }
+                        }
+                        Err(err) => error!("扫描本地音乐失败: {:?}", err),
+                    }
+                });
+            }
+            Action::MatchLocalMusic(callback) => {
+                let sender = imp.sender.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    let tracks = match blocking(|| Ok(LocalLibrary::load_from_file().tracks)).await
+                    {
+                        Ok(tracks) => tracks,
+                        Err(err) => {
+                            error!("读取本地音乐索引失败: {:?}", err);
+                            return;
+                        }
+                    };
+                    let mut matched = HashMap::new();
+                    for track in tracks.iter().filter(|t| !t.confirmed) {
+                        let si = track.to_song_info();
+                        match ncmapi.client.search_song(match_keyword(&si), 0, 10).await {
+                            Ok(candidates) => {
+                                if let Some((candidate, score)) = best_match(&si, &candidates) {
+                                    debug!(
+                                        "匹配本地歌曲 {} -> {} ({})",
+                                        si.name, candidate.name, score
+                                    );
+                                    if score >= MATCH_THRESHOLD {
+                                        matched.insert(si.id, candidate.to_owned());
+                                    }
+                                }
+                            }
+                            Err(err) => error!("搜索匹配歌曲失败: {:?}", err),
+                        }
+                        // 避免请求过于频繁
+                        timeout_future(Duration::from_millis(300)).await;
+                    }
+                    // 匹配期间索引可能已被扫描或手动确认修改，重新读取后只更新仍未确认的歌曲
+                    let res = blocking(move || {
+                        Ok(LocalLibrary::update(|library| {
+                            let mut num = 0;
+                            for track in library.tracks.iter_mut().filter(|t| !t.confirmed) {
+                                if let Some(song) = matched.get(&track.id()) {
+                                    track.set_match(Some(song), false);
+                                    num += 1;
+                                }
+                            }
+                            (num, library.tracks.to_owned())
+                        }))
+                    })
+                    .await;
+                    let (num, tracks) = match res {
+                        Ok(res) => res,
+                        Err(err) => {
+                            error!("保存本地音乐匹配结果失败: {:?}", err);
+                            return;
+                        }
+                    };
+                    sender
+                        .send(Action::AddToast(gettext_f(
+                            "Matched {num} local songs",
+                            &[("num", &num.to_string())],
+                        )))
+                        .await
+                        .unwrap();
+                    if let Some(callback) = callback {
+                        callback(tracks);
+                    }
+                });
+            }
+            Action::ConfirmLocalMatch(local_id, song_info) => {
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    let res = blocking(move || {
+                        LocalLibrary::update(|library| {
+                            library.set_match(local_id, song_info.as_ref(), true)
+                        });
+                        Ok(())
+                    })
+                    .await;
+                    if let Err(err) = res {
+                        error!("保存本地音乐匹配结果失败: {:?}", err);
+                    }
+                });
+            }
+            Action::UpdateLyrics(si, time) => {
+                let s = self.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    if time == 0 {
+                        // 当新曲目播放时，写入歌词内容
+                        if let Some(window) = s.window() {
+                            window.update_lyrics_text(&gettext("Loading lyrics..."));
+                        }
+                        match ncmapi.get_lyrics(si.clone()).await {
+                            Ok(lrc) => {
+                                debug!("获取歌词：{:?}", lrc);
+                                s.update_lyrics(&si, lrc);
+                            }
+                            Err(e) => debug!("{}", e),
+                        }
+                    }
+                    // 更新歌词高亮位置
+                    s.update_lyrics_timestamp(time);
+                });
+            }
+            Action::AdjustLyricsOffset(delta) => {
+                let Some(si) = playback.get_current_song() else {
+                    return glib::ControlFlow::Continue;
+                };
+                match NcmClient::adjust_lyric_offset(&si, delta) {
+                    Ok(offset) => {
+                        debug!("歌词偏移调整为 {}ms: {}", offset, si.name);
+                        let s = self.clone();
+                        MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                            if let Ok(lrc) = ncmapi.get_lyrics(si.clone()).await {
+                                s.update_lyrics(&si, lrc);
+                                s.update_lyrics_timestamp(playback.get_position());
+                            }
+                        });
+                    }
+                    Err(err) => {
+                        error!("调整歌词偏移失败: {:?}", err);
+                        self.add_toast(gettext("No lyrics found!"));
+                    }
+                }
+            }
+            Action::SaveSyncedLyrics(si, moved) => {
+                if let Err(err) = NcmClient::save_synced_lyrics(&si, &moved) {
+                    error!("保存歌词失败: {:?}", err);
+                    self.add_toast(gettext("Failed to save lyrics!"));
+                    return glib::ControlFlow::Continue;
+                }
+                self.add_toast(gettext("Lyrics saved"));
+                if playback.get_current_song().is_some_and(|s| s.id == si.id) {
+                    let s = self.clone();
+                    MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                        if let Ok(lrc) = ncmapi.get_lyrics(si.clone()).await {
+                            s.update_lyrics(&si, lrc);
+                            s.update_lyrics_timestamp(playback.get_position());
+                        }
+                    });
+                }
+            }
+            Action::MigrateLegacyLyrics(files) => {
+                MAINCONTEXT.spawn_local_with_priority(Priority::LOW, async move {
+                    let mut migrated = Vec::new();
+                    let mut unmatched = Vec::new();
+                    for path in files {
+                        let Some(stem) = path.file_stem().map(|s| s.to_string_lossy()) else {
+                            continue;
+                        };
+                        // 文件名为 {歌名}-{歌手}-{专辑}，搜索结果中生成相同文件名的歌曲即为原歌曲
+                        let keyword = stem.replace('-', " ");
+                        match ncmapi.client.search_song(keyword, 0, 10).await {
+                            Ok(candidates) => {
+                                match candidates
+                                    .into_iter()
+                                    .find(|si| migrate_legacy_lyric(si, si.id))
+                                {
+                                    Some(si) => migrated.push(si),
+                                    None => unmatched.push(path),
+                                }
+                            }
+                            Err(err) => {
+                                // 网络问题，下次启动时重试
+                                error!("搜索旧版歌词文件对应的歌曲失败: {:?}", err);
+                                break;
+                            }
+                        }
+                        // 避免请求过于频繁
+                        timeout_future(Duration::from_millis(300)).await;
+                    }
+                    info!(
+                        "通过搜索迁移 {} 个歌词文件，{} 个无法匹配",
+                        migrated.len(),
+                        unmatched.len()
+                    );
+                    let res = gio::spawn_blocking(move || {
+                        add_unmatched_legacy_lyrics(&unmatched);
+                        (!migrated.is_empty()).then(|| rebuild_lyrics_index(migrated))
+                    })
+                    .await;
+                    if let Ok(Some(indexed)) = res {
+                        info!("索引 {} 首歌曲的歌词", indexed);
+                    }
+                });
+            }
+            Action::GetPlayPosition(callback) => {
+                callback(playback.get_position());
+            }
+            Action::GetNowPlaying(callback) => {
+                callback(playback.now_playing());
+            }
+            Action::GetPlayList(callback) => {
+                callback((playback.get_list(), playback.playlist_position()));
+            }
+            Action::RemoveFromPlayList(song_info) => {
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    playback.remove_song(song_info);
+                    if let Some(window) = window {
+                        window.remove_from_playlist();
+                    }
+                });
+            }
+            Action::GstDurationChanged(usec) => {
+                playback.gst_duration_changed(usec);
+                if let Some(window) = window {
+                    window.update_duration(usec);
+                }
+                // 从本地歌词搜索结果播放时跳转到匹配的歌词行
+                if let Some(si) = playback.get_current_song() {
+                    let time = imp.lyric_jumps.borrow_mut().remove(&si.id);
+                    if let Some(time) = time {
+                        playback.gst_position_update(time * 1000);
+                    }
+                }
+            }
+            Action::GstStateChanged(state) => {
+                let state_name = match state {
+                    gstreamer_play::PlayState::Playing => "playing",
+                    gstreamer_play::PlayState::Paused => "paused",
+                    gstreamer_play::PlayState::Buffering => "buffering",
+                    _ => "stopped",
+                };
+                self.notify_remote(RemoteEvent::State {
+                    state: state_name.to_owned(),
+                });
+                playback.gst_state_changed(state);
+                let is_playing = matches!(state, gstreamer_play::PlayState::Playing);
+                imp.tray_handle.borrow().update_playing(is_playing);
+                if let Some(window) = window {
+                    window.update_playing(is_playing);
+                }
+            }
+            Action::GstVolumeChanged(volume) => {
+                playback.gst_volume_changed(volume);
+            }
+            Action::GstCacheDownloadComplete(loc) => {
+                playback.gst_cache_download_complete(loc);
+            }
+            Action::ScaleSeekUpdate(usec) => {
+                self.notify_remote(RemoteEvent::Position {
+                    position: usec / 1000,
+                });
+                playback.update_position(usec);
+                if let Some(window) = window {
+                    window.update_position(usec);
+                }
+            }
+            Action::InitMpris(mpris) => {
+                playback.init_mpris(mpris);
+            }
+            Action::UpdateTrayPlaying(playing) => {
+                imp.tray_handle.borrow().update_playing(playing);
+            }
+            Action::UpdateTraySongTitle(title, artist, album_id) => {
+                imp.tray_handle
+                    .borrow()
+                    .update_song_title(title, artist, album_id);
+            }
+            Action::UpdateTrayLike(like) => {
+                imp.tray_handle.borrow().update_like(like);
+            }
+            Action::UpdateTrayLoops(state) => {
+                imp.tray_handle.borrow().update_loops(state);
+            }
+            Action::ToggleDesktopLyrics => {
+                let settings = self.settings();
+                let visible = settings.boolean("desktop-lyrics");
+                settings.set_boolean("desktop-lyrics", !visible).ok();
+            }
+            Action::ToggleDesktopLyricsLock => {
+                let settings = self.settings();
+                let locked = settings.boolean("desktop-lyrics-locked");
+                settings.set_boolean("desktop-lyrics-locked", !locked).ok();
+            }
+            Action::ShowMainWindow => {
+                self.present_window();
+            }
+            Action::Quit => unreachable!("quit is handled before dispatch"),
+            action => return self.process_window_action(action, ncmapi),
+        }
+        glib::ControlFlow::Continue
+    }
+
+    // 需要主窗口的动作，无窗口模式下窗口未打开时忽略
+    fn process_window_action(&self, action: Action, ncmapi: NcmClient) -> glib::ControlFlow {
+        let imp = self.imp();
+        let window = match self.window() {
+            Some(window) => window,
+            // 搜索与打开链接时显示窗口
+            None if matches!(action, Action::SearchSongs(_) | Action::OpenLink(..)) => {
+                self.present_window()
+            }
+            None => {
+                debug!("无窗口，忽略动作: {:?}", action);
+                return glib::ControlFlow::Continue;
+            }
+        };
+
+        match action {
+            Action::TryUpdateQrCode => {
+                if !self.is_logined() && window.is_user_menu_active(UserMenuChild::Qr) {
+                    let sender = imp.sender.clone();
+                    MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                        if let Ok((path, unikey)) = ncmapi.create_qrcode().await {
+                            sender.send(Action::SetQrImage(path)).await.unwrap();
+                            sender.send(Action::CheckQrTimeout(unikey)).await.unwrap();
+                        }
+                    });
+                }
+            }
+            Action::SetQrImage(path) => {
+                window.set_user_qrimage(path);
+            }
+            Action::CheckQrTimeout(unikey) => {
+                if let Ok(key) = imp.unikey.read() {
+                    if unikey != *key {
+                        let sender = imp.sender.clone();
+                        sender
+                            .send_blocking(Action::CheckQrTimeoutCb(unikey))
+                            .unwrap();
+                    }
+                }
+            }
+            Action::CheckQrTimeoutCb(unikey) => {
+                debug!("检查登录二维码状态，unikey={}", unikey);
+                {
+                    let mut key = imp.unikey.write().unwrap();
+                    *key = unikey.clone();
+                }
+                let sender = imp.sender.clone();
+                let key = imp.unikey.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    let mut send_toast = true;
+                    loop {
+                        {
+                            let key = key.read().unwrap();
+                            if *key != unikey {
+                                warn!("unikey 已失效，unikey={}", unikey);
+                                break;
+                            }
+                        }
+                        match  ncmapi.client.login_qr_check(unikey.to_owned()).await {
+                            Ok(msg) => {
+                                match msg.code {
+                                    // 已过期
+                                    800 => {
+                                        debug!("二维码已过期，unikey={}", unikey);
+                                        sender.send(Action::SetQrImageTimeout).await.unwrap();
+                                        break;
+                                    }
+                                    // 等待扫码
+                                    801 => {
+                                        debug!("等待扫码，unikey={}", unikey);
+                                    },
+                                    // 等待确认
+                                    802 => {
+                                        debug!("等待app端确认，unikey={}", unikey);
+                                        if send_toast {
+                                            sender
+                                                .send(Action::AddToast(gettext("Have scanned the QR code, waiting for confirmation!")))
+                                                .await.unwrap();
+                                            send_toast = false;
+                                        }
+                                    }
+                                    // 登录成功
+                                    803 => {
+                                        debug!("扫码登录成功，unikey={}", unikey);
+                                        let cookie_jar = ncmapi.client.cookie_jar().cloned().unwrap_or_else(|| {
+                                            error!("No login cookie found");
+                                            CookieJar::new()
+                                        });
+                                        sender.send(Action::CheckLogin(UserMenuChild::Qr, cookie_jar)).await.unwrap();
+                                        break;
+                                    }
+                                    _ => break,
+                                }
+                            },
+                            Err(err) => error!("{:?}", err),
+                        }
+                        timeout_future_seconds(1).await;
+                    }
+                });
+            }
+            Action::SetQrImageTimeout => {
+                window.set_user_qrimage_timeout();
+            }
+            Action::SwitchUserMenuToPhone => {
+                window.switch_user_menu_to_phone();
+            }
+            Action::SwitchUserMenuToQr => {
+                window.switch_user_menu_to_qr();
+            }
+            Action::GetCaptcha(ctcode, phone) => {
+                let sender = imp.sender.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    match ncmapi.client.captcha(ctcode, phone).await {
+                        Ok(..) => {
+                            debug!("发送获取验证码请求...");
+                            sender
+                            .send(Action::AddToast(gettext(
+                                "Please pay attention to check the cell phone verification code!",
+                            )))
+                            .await.unwrap();
+                        }
+                        Err(err) => {
+                            warn!("获取验证码失败! {:?}", err);
+                            sender
+                                .send(Action::AddToast(gettext(
+                                    "Failed to get verification code!",
+                                )))
+                                .await
+                                .unwrap();
+                        }
+                    }
+                });
+            }
+            Action::CaptchaLogin(ctcode, phone, captcha) => {
+                let sender = imp.sender.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    debug!("使用验证码登录：{}", captcha);
+                    if let Ok(_login_info) =
+                        ncmapi.client.login_cellphone(ctcode, phone, captcha).await
+                    {
+                        let cookie_jar = ncmapi.client.cookie_jar().cloned().unwrap_or_else(|| {
+                            error!("No login cookie found");
+                            CookieJar::new()
+                        });
+                        sender
+                            .send(Action::CheckLogin(UserMenuChild::Phone, cookie_jar))
+                            .await
+                            .unwrap();
+                    } else {
+                        error!("登录失败！");
+                        sender
+                            .send(Action::AddToast(gettext("Login failed!")))
+                            .await
+                            .unwrap();
+                    }
+                });
+            }
+            Action::SwitchUserMenuToUser(login_info, menu) => {
+                window.switch_user_menu_to_user(login_info.clone(), menu);
+                let avatar_url = login_info.avatar_url;
+                let mut path = CACHE.clone();
+                path.push("avatar.jpg");
+                window.set_avatar(avatar_url, path);
+            }
+            Action::InitCarousel => {
+                let sender = imp.sender.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    match ncmapi.client.banners().await {
+                        Ok(banners) => {
+                            debug!("获取轮播信息: {:?}", banners);
+                            for banner in banners {
+                                window.add_carousel(banner);
+                            }
+
+                            // auto check login after banners
+                            // https://github.com/Binaryify/NeteaseCloudMusicApi/issues/1217
+                            if let Some(cookie_jar) = NcmClient::load_cookie_jar_from_file() {
+                                sender
+                                    .send(Action::CheckLogin(UserMenuChild::Qr, cookie_jar))
+                                    .await
+                                    .unwrap();
+                            }
+                        }
+                        Err(err) => {
+                            error!("获取首页轮播信息失败！{:?}", err);
+                            timeout_future(Duration::from_millis(500)).await;
+                            sender.send(Action::InitCarousel).await.unwrap();
+                        }
+                    }
+                });
+            }
+            Action::InitTopPicks => {
+                let sender = imp.sender.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    match ncmapi.client.top_song_list("全部", "hot", 0, 8).await {
+                        Ok(song_list) => {
+                            debug!("获取热门推荐信息：{:?}", song_list);
+                            sender.send(Action::SetupTopPicks(song_list)).await.unwrap();
+                        }
+                        Err(err) => {
+                            error!("获取热门推荐信息失败！{:?}", err);
+                            timeout_future(Duration::from_millis(500)).await;
+                            sender.send(Action::InitTopPicks).await.unwrap();
+                        }
+                    }
+                });
+            }
+            Action::ToTopPicksPage => {
+                let page = window.init_picks_songlist();
+                window.page_new(&page, gettext("all top picks").as_str(), "ToTopPicksPage");
+                let page = page.downgrade();
+
+                let s = self.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    if let Some(SearchResult::SongLists(sls)) = s
+                        .action_search(ncmapi, String::new(), SearchType::TopPicks, 0, 50)
+                        .await
+                    {
+                        if let Some(page) = page.upgrade() {
+                            page.update_songlist(&sls);
+                        }
+                    }
+                });
+            }
+            Action::SetupTopPicks(song_list) => {
+                window.setup_top_picks(song_list);
+            }
+            Action::InitNewAlbums => {
+                let sender = imp.sender.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    match ncmapi.client.new_albums("ALL", 0, 8).await {
+                        Ok(song_list) => {
+                            debug!("获取新碟上架信息：{:?}", song_list);
+                            sender
+                                .send(Action::SetupNewAlbums(song_list))
+                                .await
+                                .unwrap();
+                        }
+                        Err(err) => {
+                            error!("获取新碟上架信息失败！{:?}", err);
+                            timeout_future(Duration::from_millis(500)).await;
+                            sender.send(Action::InitNewAlbums).await.unwrap();
+                        }
+                    }
+                });
+            }
+            Action::ToAllAlbumsPage => {
+                let page = window.init_all_albums();
+
+                window.page_new(&page, gettext("all new albums").as_str(), "ToAllAlbumsPage");
+                let page = page.downgrade();
+
+                let s = self.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    if let Some(SearchResult::SongLists(sls)) = s
+                        .action_search(ncmapi, String::new(), SearchType::AllAlbums, 0, 50)
+                        .await
+                    {
+                        if let Some(page) = page.upgrade() {
+                            page.update_songlist(&sls);
+                        }
+                    }
+                });
+            }
+            Action::SetupNewAlbums(song_list) => {
+                window.setup_new_albums(song_list);
+            }
+            Action::BannerTo(banner) => {
+                let sender = imp.sender.clone();
+                match banner.target_type {
+                    TargetType::Song => {
+                        MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                            match ncmapi.client.songs_detail(&[banner.target_id]).await {
+                                Ok(songs) => {
+                                    debug!("获取轮播歌曲信息：{:?}", songs);
+                                    if let Some(song) = songs.first() {
+                                        sender.send(Action::AddPlay(song.clone())).await.unwrap();
+                                    }
+                                }
+                                Err(err) => {
+                                    error!("获取轮播歌曲信息失败！{:?}", err);
+                                }
+                            }
+                        });
+                    }
+                    TargetType::Album => {
+                        MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                            match ncmapi.client.album(banner.target_id).await {
+                                Ok(album) => {
+                                    debug!("获取轮播专辑信息：{:?}", album);
+                                    let page = window.init_songlist_page(
+                                        &SongList {
+                                            id: album.id,
+                                            name: album.name.to_owned(),
+                                            cover_img_url: album.pic_url.to_owned(),
+                                            author: album.artist_name.to_owned(),
+                                            special_type: 0,
+                                        },
+                                        true,
+                                    );
+                                    window.page_new(&page, &album.name, "Album");
+                                    let page = page.downgrade();
+                                    let detal_dynamic_as =
+                                        ncmapi.client.album_detail_dynamic(album.id);
+                                    let dy = detal_dynamic_as.await.unwrap_or_else(|err| {
+                                        error!("{:?}", err);
+                                        AlbumDetailDynamic::default()
+                                    });
+                                    let detail = SongListDetail::Album(album, dy);
+                                    if let Some(page) = page.upgrade() {
+                                        window.update_songlist_page(page, &detail);
+                                    }
+                                }
+                                Err(err) => {
+                                    error!("获取轮播专辑信息失败！{:?}", err);
+                                }
+                            }
+                        });
+                    }
+                    TargetType::Unknown => (),
+                }
+            }
+            Action::ToSongListPage(songlist) => {
+                let page = window.init_songlist_page(&songlist, false);
+                window.page_new(&page, &songlist.name, "ToSongListPage");
+                let page = page.downgrade();
+
+                let sender = imp.sender.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    let detal_dynamic_as = ncmapi.client.songlist_detail_dynamic(songlist.id);
+                    match ncmapi.client.song_list_detail(songlist.id).await {
+                        Ok(detail) => {
+                            debug!("获取歌单详情: {:?}", detail);
+                            let dy = detal_dynamic_as.await.unwrap_or_else(|err| {
+                                error!("{:?}", err);
+                                PlayListDetailDynamic::default()
+                            });
+                            let detail = SongListDetail::PlayList(detail, dy);
+                            if let Some(page) = page.upgrade() {
+                                window.update_songlist_page(page, &detail);
+                            }
+                        }
+                        Err(err) => {
+                            error!("获取歌单详情失败: {:?}", err);
+                            sender
+                                .send(Action::AddToast(gettext(
+                                    "Failed to get song list details!",
+                                )))
+                                .await
+                                .unwrap();
+                        }
+                    }
+                });
+            }
+            Action::ToAlbumPage(songlist) => {
+                let page = window.init_songlist_page(&songlist, true);
+                window.page_new(&page, &songlist.name, "ToAlbumPage");
+                let page = page.downgrade();
+
                 let sender = imp.sender.clone();
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    // toggle：已开启则直接关闭，不再拉取/追加
-                    let is_active = { *HEARTBEAT_ACTIVE.lock().unwrap() };
-                    if is_active {
-                        *HEARTBEAT_ACTIVE.lock().unwrap() = false;
-                        window.set_heartbeat_active(false);
-                        sender
-                            .send(Action::AddToast(gettext("Intelligent Mode Off")))
-                            .await
-                            .unwrap();
-                        return;
-                    }
-
-                    let uid = window.get_uid();
-                    if uid == 0 {
-                        sender
-                            .send(Action::AddToast(gettext("Please log in first!")))
-                            .await
-                            .unwrap();
-                        return;
-                    }
-
-                    let playlist_id = match favorite_playlist_id(&ncmapi, uid).await {
-                        Ok(pid) => pid,
-                        Err(toast) => {
+                    let detal_dynamic_as = ncmapi.client.album_detail_dynamic(songlist.id);
+                    match ncmapi.client.album(songlist.id).await {
+                        Ok(detail) => {
+                            debug!("获取专辑详情: {:?}", detail);
+                            let dy = detal_dynamic_as.await.unwrap_or_else(|err| {
+                                error!("{:?}", err);
+                                AlbumDetailDynamic::default()
+                            });
+                            let detail = SongListDetail::Album(detail, dy);
+                            if let Some(page) = page.upgrade() {
+                                window.update_songlist_page(page, &detail);
+                            }
+                        }
+                        Err(err) => {
+                            error!("获取专辑详情失败: {:?}", err);
                             sender
-                                .send(Action::AddToast(gettext(toast)))
+                                .send(Action::AddToast(gettext("Failed to get album details!")))
                                 .await
                                 .unwrap();
-                            return;
                         }
-                    };
+                    }
+                });
+            }
+            Action::ToRadioPage(songlist) => {
+                let page = window.init_songlist_page(&songlist, true);
+                window.page_new(&page, &songlist.name, "ToRadioPage");
+                let page = page.downgrade();
 
-                    match ncmapi
-                        .client
-                        .playmode_intelligence_list(si.id, playlist_id)
-                        .await
-                    {
-                        Ok(mut pl) => {
-                            debug!("获取心动歌曲：{:?}", pl);
-                            *HEARTBEAT_ACTIVE.lock().unwrap() = true;
-                            window.set_heartbeat_active(true);
-                            let mut pla = vec![si];
-                            pla.append(&mut pl);
-                            sender.send(Action::AddPlayList(pla, false)).await.unwrap();
-                            sender
-                                .send(Action::AddToast(gettext("Intelligent Mode")))
-                                .await
-                                .unwrap();
+                let sender = imp.sender.clone();
+                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                    match ncmapi.client.radio_program(songlist.id, 0, 1001).await {
+                        Ok(detail) => {
+                            debug!("获取电台详情: {:?}", detail);
+                            let detail = SongListDetail::Radio(detail);
+                            if let Some(page) = page.upgrade() {
+                                window.update_songlist_page(page, &detail);
+                            }
                         }
                         Err(err) => {
-                            error!("获取心动歌曲 {} 失败! {:?}", si.name, err);
-                            *HEARTBEAT_ACTIVE.lock().unwrap() = false;
-                            window.set_heartbeat_active(false);
+                            error!("获取电台详情失败: {:?}", err);
                             sender
-                                .send(Action::AddToast(gettext("Intelligent mode failed!")))
+                                .send(Action::AddToast(gettext("Failed to get radio details!")))
                                 .await
                                 .unwrap();
                         }
                     }
                 });
             }
-            Action::HeartbeatExtend(si, ext_mode) => {
+            Action::UploadToCloud(paths) => {
                 let sender = imp.sender.clone();
+                let dialog = CloudUploadDialog::new();
+                dialog.set_files(&paths);
+                dialog.present(Some(&window));
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    if !*HEARTBEAT_ACTIVE.lock().unwrap() {
-                        return;
-                    }
-                    let uid = window.get_uid();
-                    if uid == 0 {
-                        return;
-                    }
-                    let playlist_id = match favorite_playlist_id(&ncmapi, uid).await {
-                        Ok(pid) => pid,
-                        Err(toast) => {
+                    let uploader = match ncmapi.cloud_uploader() {
+                        Ok(uploader) => uploader,
+                        Err(err) => {
+                            error!("创建云盘上传器失败: {:?}", err);
                             sender
-                                .send(Action::AddToast(gettext(toast)))
+                                .send(Action::AddToast(gettext("Upload to cloud failed!")))
                                 .await
                                 .unwrap();
                             return;
                         }
                     };
-                    match ncmapi.client.playmode_intelligence_list(si.id, playlist_id).await {
-                        Ok(pl) => {
-                            debug!("追加心动歌曲：{:?}", pl);
-                            let mut new_list = pl;
-                            let appended = window.append_playlist(&mut new_list);
-                            if let Some(song) = appended.first() {
-                                // 追加成功：自动播放追加的第一首并同步播放位置
-                                window.sync_playlist_position(song.id);
-                                let index = window.playlist_position();
-                                sender.send(Action::Play(song.to_owned())).await.unwrap();
-                                sender.send(Action::UpdatePlayListStatus(index)).await.unwrap();
-                            } else if ext_mode == HeartbeatExtendMode::ListLoop {
-                                // 追加为空（全部重复）：列表循环恢复从头播放
-                                playlist_restart(&window, &sender).await;
+                    let mut reports = Vec::new();
+                    let mut failed = 0;
+                    for (index, path) in paths.iter().enumerate() {
+                        let res = uploader
+                            .upload(path, |fraction| dialog.set_progress(index, fraction))
+                            .await;
+                        match res {
+                            Ok(report) => {
+                                debug!("上传云盘完成: {:?}", report);
+                                dialog.set_result(index, &Ok(report.clone()));
+                                reports.push(report);
                             }
-                        }
-                        Err(err) => {
-                            error!("获取心动歌曲失败! {:?}", err);
-                            if ext_mode == HeartbeatExtendMode::ListLoop {
-                                // 追加失败：列表循环恢复从头播放
-                                playlist_restart(&window, &sender).await;
+                            Err(err) => {
+                                error!("上传云盘失败: {:?} {:?}", path, err);
+                                dialog.set_result(index, &Err(err.to_string()));
+                                failed += 1;
                             }
                         }
                     }
+                    dialog.finish(&reports, failed);
+                    sender
+                        .send(Action::AddToast(gettext_f(
+                            "Uploaded {num} files to cloud",
+                            &[("num", &reports.len().to_string())],
+                        )))
+                        .await
+                        .unwrap();
                 });
             }
             Action::GetToplist => {
@@ -1220,16 +2797,6 @@ impl NeteaseCloudMusicGtk4Application {
             Action::UpdateTopList(sis) => {
                 window.update_toplist(sis);
             }
-            Action::Search(text, search_type, offset, limit, callback) => {
-                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    let res = window
-                        .action_search(ncmapi, text, search_type, offset, limit)
-                        .await;
-                    if let Some(res) = res {
-                        callback(res);
-                    }
-                });
-            }
             Action::ToSingerSongsPage(singer) => {
                 let title = &singer.name;
                 let page = window.init_search_song_page(title, SearchType::SingerSongs);
@@ -1291,8 +2858,8 @@ impl NeteaseCloudMusicGtk4Application {
                 let page = page.downgrade();
 
                 let sender = imp.sender.clone();
+                let uid = self.get_uid();
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    let uid = window.get_uid();
                     match ncmapi.client.user_song_list(uid, 0, 1).await {
                         Ok(sls) => {
                             debug!("获取心动歌单：{:?}", sls);
@@ -1331,15 +2898,24 @@ impl NeteaseCloudMusicGtk4Application {
                 let title = gettext("Cloud Music");
                 let page = window.init_search_song_page(&title, SearchType::CloudDisk);
                 window.page_new(&page, &title, "ToMyPageCloudDisk");
-                let page = page.downgrade();
-
+                imp.cloud_disk_page.replace(Some(page.downgrade()));
+                imp.sender.send_blocking(Action::RefreshCloudDisk).unwrap();
+            }
+            Action::RefreshCloudDisk => {
+                let Some(page) = imp.cloud_disk_page.borrow().clone() else {
+                    return glib::ControlFlow::Continue;
+                };
                 let sender = imp.sender.clone();
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    match ncmapi.client.user_cloud_disk().await {
-                        Ok(sis) => {
-                            debug!("获取云盘音乐：{:?}", sis);
+                    let res = match ncmapi.cloud_disk() {
+                        Ok(cloud_disk) => cloud_disk.songs().await,
+                        Err(err) => Err(err),
+                    };
+                    match res {
+                        Ok(songs) => {
+                            debug!("获取云盘音乐：{}", songs.len());
                             if let Some(page) = page.upgrade() {
-                                window.update_search_song_page(page, sis);
+                                page.update_cloud_songs(songs);
                             }
                         }
                         Err(err) => {
@@ -1354,14 +2930,201 @@ impl NeteaseCloudMusicGtk4Application {
                     }
                 });
             }
+            Action::DeleteCloudSong(si) => {
+                let dialog = adw::AlertDialog::new(
+                    Some(&gettext("Delete from Cloud?")),
+                    Some(&gettext_f(
+                        "[{name}] will be permanently removed from your cloud disk.",
+                        &[("name", &si.name)],
+                    )),
+                );
+                dialog.add_responses(&[
+                    ("cancel", &gettext("Cancel")),
+                    ("delete", &gettext("Delete")),
+                ]);
+                dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+                let sender = imp.sender.clone();
+                dialog.connect_response(Some("delete"), move |_, _| {
+                    let sender = sender.clone();
+                    let ncmapi = ncmapi.clone();
+                    let si = si.clone();
+                    MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                        let res = match ncmapi.cloud_disk() {
+                            Ok(cloud_disk) => cloud_disk.delete(&[si.id]).await,
+                            Err(err) => Err(err),
+                        };
+                        match res {
+                            Ok(_) => {
+                                sender
+                                    .send(Action::AddToast(gettext_f(
+                                        "Deleted [{name}] from cloud",
+                                        &[("name", &si.name)],
+                                    )))
+                                    .await
+                                    .unwrap();
+                                sender.send(Action::RefreshCloudDisk).await.unwrap();
+                            }
+                            Err(err) => {
+                                error!("删除云盘歌曲失败: {:?}", err);
+                                sender
+                                    .send(Action::AddToast(gettext(
+                                        "Request for interface failed, please try again!",
+                                    )))
+                                    .await
+                                    .unwrap();
+                            }
+                        }
+                    });
+                });
+                dialog.present(Some(&window));
+            }
+            Action::RematchCloudSong(si) => {
+                // 按关键词搜索候选歌曲，也可直接输入歌曲 ID 或链接
+                let entry = gtk::SearchEntry::builder()
+                    .placeholder_text(gettext("Search songs, or enter a song ID or link"))
+                    .text(format!("{} {}", si.name, si.singer).trim())
+                    .activates_default(true)
+                    .build();
+                let list = gtk::ListBox::builder()
+                    .selection_mode(gtk::SelectionMode::Single)
+                    .css_classes(["boxed-list"])
+                    .build();
+                let scrolled = gtk::ScrolledWindow::builder()
+                    .hscrollbar_policy(gtk::PolicyType::Never)
+                    .min_content_height(240)
+                    .child(&list)
+                    .build();
+                let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+                content.append(&entry);
+                content.append(&scrolled);
+                let candidates: Rc<RefCell<Vec<u64>>> = Rc::new(RefCell::new(Vec::new()));
+                // 只显示最后一次搜索的结果
+                let generation = Rc::new(Cell::new(0u32));
+                let search = Rc::new(clone!(
+                    #[strong]
+                    ncmapi,
+                    #[strong]
+                    list,
+                    #[strong]
+                    candidates,
+                    move |text: String| {
+                        generation.set(generation.get().wrapping_add(1));
+                        let current = generation.get();
+                        let generation = generation.clone();
+                        let ncmapi = ncmapi.clone();
+                        let list = list.clone();
+                        let candidates = candidates.clone();
+                        MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                            let songs = if text.trim().is_empty() || parse_song_id(&text).is_some()
+                            {
+                                Vec::new()
+                            } else {
+                                match ncmapi.client.search_song(text.to_owned(), 0, 20).await {
+                                    Ok(songs) => songs,
+                                    Err(err) => {
+                                        error!("搜索候选歌曲失败: {:?}", err);
+                                        return;
+                                    }
+                                }
+                            };
+                            if generation.get() != current {
+                                return;
+                            }
+                            list.remove_all();
+                            let mut ids = candidates.borrow_mut();
+                            ids.clear();
+                            for song in songs {
+                                let row = adw::ActionRow::builder()
+                                    .title(glib::markup_escape_text(&song.name))
+                                    .subtitle(glib::markup_escape_text(&format!(
+                                        "{} - {}",
+                                        song.singer, song.album
+                                    )))
+                                    .build();
+                                list.append(&row);
+                                ids.push(song.id);
+                            }
+                            if let Some(row) = list.row_at_index(0) {
+                                list.select_row(Some(&row));
+                            }
+                        });
+                    }
+                ));
+                entry.connect_search_changed(clone!(
+                    #[strong]
+                    search,
+                    move |entry| search(entry.text().to_string())
+                ));
+                search(entry.text().to_string());
+                let dialog = adw::AlertDialog::new(
+                    Some(&gettext("Rematch Song")),
+                    Some(&gettext_f(
+                        "Link [{name}] to a song in the NetEase library to fix its metadata.",
+                        &[("name", &si.name)],
+                    )),
+                );
+                dialog.set_extra_child(Some(&content));
+                dialog.add_responses(&[
+                    ("cancel", &gettext("Cancel")),
+                    ("rematch", &gettext("Rematch")),
+                ]);
+                dialog.set_response_appearance("rematch", adw::ResponseAppearance::Suggested);
+                dialog.set_default_response(Some("rematch"));
+                dialog.set_close_response("cancel");
+                let sender = imp.sender.clone();
+                let uid = self.get_uid();
+                dialog.connect_response(Some("rematch"), move |_, _| {
+                    let selected = list
+                        .selected_row()
+                        .and_then(|row| candidates.borrow().get(row.index() as usize).copied());
+                    let Some(adjust_id) = selected.or_else(|| parse_song_id(&entry.text())) else {
+                        sender
+                            .send_blocking(Action::AddToast(gettext("Invalid song ID!")))
+                            .unwrap();
+                        return;
+                    };
+                    let sender = sender.clone();
+                    let ncmapi = ncmapi.clone();
+                    let si = si.clone();
+                    MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                        let res = match ncmapi.cloud_disk() {
+                            Ok(cloud_disk) => cloud_disk.rematch(uid, si.id, adjust_id).await,
+                            Err(err) => Err(err),
+                        };
+                        match res {
+                            Ok(_) => {
+                                debug!("云盘歌曲重新匹配: {} -> {}", si.id, adjust_id);
+                                sender
+                                    .send(Action::AddToast(gettext("Song rematched!")))
+                                    .await
+                                    .unwrap();
+                                sender.send(Action::RefreshCloudDisk).await.unwrap();
+                            }
+                            Err(err) => {
+                                error!("云盘歌曲重新匹配失败: {:?}", err);
+                                sender
+                                    .send(Action::AddToast(gettext(
+                                        "Request for interface failed, please try again!",
+                                    )))
+                                    .await
+                                    .unwrap();
+                            }
+                        }
+                    });
+                });
+                dialog.present(Some(&window));
+            }
             Action::ToMyPageRadio => {
                 let title = gettext("My Radio");
                 let page = window.init_search_songlist_page(&title, SearchType::Radio);
                 window.page_new(&page, &title, "ToMyPageRadio");
                 let page = page.downgrade();
 
+                let s = self.clone();
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    let res = window
+                    let res = s
                         .action_search(ncmapi, String::new(), SearchType::Radio, 0, 1001)
                         .await;
                     if let Some(page) = page.upgrade() {
@@ -1377,8 +3140,9 @@ impl NeteaseCloudMusicGtk4Application {
                 window.page_new(&page, &title, "ToMyPageAlbums");
                 let page = page.downgrade();
 
+                let s = self.clone();
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    let res = window
+                    let res = s
                         .action_search(ncmapi, String::new(), SearchType::LikeAlbums, 0, 50)
                         .await;
                     if let Some(page) = page.upgrade() {
@@ -1394,8 +3158,9 @@ impl NeteaseCloudMusicGtk4Application {
                 window.page_new(&page, &title, "ToMyPageSonglist");
                 let page = page.downgrade();
 
+                let s = self.clone();
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    let res = window
+                    let res = s
                         .action_search(ncmapi, String::new(), SearchType::LikeSongList, 0, 1001)
                         .await;
                     if let Some(page) = page.upgrade() {
@@ -1405,6 +3170,21 @@ impl NeteaseCloudMusicGtk4Application {
                     }
                 });
             }
+            Action::ToMyPageLocalMusic => {
+                let title = gettext("Local Music");
+                let page = window.init_search_song_page(&title, SearchType::LocalMusic);
+                window.page_new(&page, &title, "ToMyPageLocalMusic");
+                // 先显示已有索引，再后台扫描更新
+                let sis = LocalLibrary::load_from_file().song_infos();
+                window.update_search_song_page(page.clone(), sis);
+                imp.local_music_page.replace(Some(page.downgrade()));
+                imp.sender.send_blocking(Action::ScanLocalMusic).unwrap();
+            }
+            Action::ShowLocalMatchDialog => {
+                let dialog = LocalMatchDialog::new(imp.sender.clone());
+                dialog.update_tracks(&LocalLibrary::load_from_file().tracks);
+                dialog.present(Some(&window));
+            }
             Action::InitMyPage => {
                 window.switch_my_page_to_login();
                 let sender = imp.sender.clone();
@@ -1435,46 +3215,24 @@ impl NeteaseCloudMusicGtk4Application {
                     sender.send_blocking(Action::PageBack).unwrap();
                 }
             }
-            Action::UpdateLyrics(si, time) => {
+            Action::ShowLyricsSyncDialog => {
+                let Some(si) = self.playback().get_current_song() else {
+                    return glib::ControlFlow::Continue;
+                };
+                let sender = imp.sender.clone();
                 MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    if time == 0 {
-                        // 当新曲目播放时，写入歌词内容
-                        window.update_lyrics_text(&gettext("Loading lyrics..."));
-                        match ncmapi.get_lyrics(si).await {
-                            Ok(lrc) => {
-                                debug!("获取歌词：{:?}", lrc);
-                                window.update_lyrics(lrc);
-                            }
-                            Err(e) => debug!("{}", e),
+                    match ncmapi.get_lyrics(si.clone()).await {
+                        Ok(lrc) if !lrc.is_empty() => {
+                            let dialog = LyricsSyncDialog::new(sender, si, lrc);
+                            dialog.present(Some(&window));
                         }
+                        _ => window.add_toast(gettext("No lyrics found!")),
                     }
-                    // 更新歌词高亮位置
-                    window.update_lyrics_timestamp(time);
                 });
             }
             Action::UpdatePlayListStatus(index) => {
                 window.update_playlist_status(index);
             }
-            Action::RemoveFromPlayList(song_info) => {
-                MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
-                    window.remove_from_playlist(song_info);
-                });
-            }
-            Action::GstDurationChanged(sec) => {
-                window.gst_duration_changed(sec);
-            }
-            Action::GstStateChanged(state) => {
-                window.gst_state_changed(state);
-            }
-            Action::GstVolumeChanged(volume) => {
-                window.gst_volume_changed(volume);
-            }
-            Action::GstCacheDownloadComplete(loc) => {
-                window.gst_cache_download_complete(loc);
-            }
-            Action::ScaleSeekUpdate(sec) => {
-                window.scale_seek_update(sec);
-            }
             Action::ScaleValueUpdate => {
                 window.scale_value_update();
             }
@@ -1482,22 +3240,165 @@ impl NeteaseCloudMusicGtk4Application {
             Action::PageBack => {
                 window.page_back();
             }
-            Action::InitMpris(mpris) => {
-                window.init_mpris(mpris);
-            }
-            Action::UpdateTrayPlaying(playing) => {
-                window.update_tray_playing(playing);
-            }
-            Action::UpdateTraySongTitle(title, artist, album_id) => {
-                window.update_tray_song_title(title, artist, album_id);
+            Action::SearchSongs(text) => {
+                window.present();
+                window.search(text, SearchType::Song);
+            }
+            Action::ShareLink(link, title, position) => {
+                let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+                let label = gtk::Label::builder()
+                    .selectable(true)
+                    .wrap(true)
+                    .wrap_mode(gtk::pango::WrapMode::Char)
+                    .build();
+                content.append(&label);
+
+                let picture = gtk::Picture::builder()
+                    .can_shrink(false)
+                    .halign(gtk::Align::Center)
+                    .build();
+                let expander = gtk::Expander::builder()
+                    .label(gettext("QR Code"))
+                    .child(&picture)
+                    .build();
+
+                let url = Rc::new(RefCell::new(String::new()));
+                let update_url = {
+                    let url = url.clone();
+                    let label = label.clone();
+                    let picture = picture.clone();
+                    Rc::new(move |position: Option<u64>| {
+                        let new_url = link.url(position);
+                        label.set_label(&new_url);
+                        let image =
+                            NcmClient::create_qrcode_image(&new_url, "share-qrimage.png", 200);
+                        match image.and_then(|path| {
+                            gtk::gdk::Texture::from_filename(path).map_err(Into::into)
+                        }) {
+                            Ok(texture) => picture.set_paintable(Some(&texture)),
+                            Err(err) => error!("生成分享二维码失败: {:?}", err),
+                        }
+                        url.replace(new_url);
+                    })
+                };
+                update_url(None);
+
+                // 分享歌曲时可附带当前播放位置
+                if let Some(position) = position {
+                    let time = format!("{:0>2}:{:0>2}", position / 60, position % 60);
+                    let check = gtk::CheckButton::with_label(&gettext_f(
+                        "Include playback position ({time})",
+                        &[("time", &time)],
+                    ));
+                    let update_url = update_url.clone();
+                    check.connect_toggled(move |check| {
+                        update_url(check.is_active().then_some(position));
+                    });
+                    content.append(&check);
+                }
+                content.append(&expander);
+
+                let dialog = adw::AlertDialog::new(Some(&gettext("Share")), Some(&title));
+                dialog.set_extra_child(Some(&content));
+                dialog.add_responses(&[
+                    ("cancel", &gettext("Cancel")),
+                    ("copy", &gettext("Copy Link")),
+                ]);
+                dialog.set_response_appearance("copy", adw::ResponseAppearance::Suggested);
+                dialog.set_default_response(Some("copy"));
+                dialog.set_close_response("cancel");
+                let sender = imp.sender.clone();
+                let clipboard = window.clipboard();
+                dialog.connect_response(Some("copy"), move |_, _| {
+                    clipboard.set_text(&url.borrow());
+                    sender
+                        .send_blocking(Action::AddToast(gettext("Link copied to clipboard!")))
+                        .unwrap();
+                });
+                dialog.present(Some(&window));
             }
-            Action::ShowMainWindow => {
+            Action::OpenLink(link, position) => {
                 window.present();
+                let sender = imp.sender.clone();
+                match link {
+                    NeteaseLink::Song(id) => {
+                        let action = match position {
+                            // 从分享时的播放位置开始播放
+                            Some(position) => Action::PlayTrackAt(id, position),
+                            None => Action::AddTrackById(id, true),
+                        };
+                        sender.send_blocking(action).unwrap();
+                    }
+                    NeteaseLink::Playlist(id) => {
+                        MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                            match ncmapi.client.song_list_detail(id).await {
+                                Ok(detail) => {
+                                    debug!("获取链接歌单信息：{:?}", detail);
+                                    let songlist = SongList {
+                                        id: detail.id,
+                                        name: detail.name,
+                                        cover_img_url: detail.cover_img_url,
+                                        author: String::new(),
+                                        special_type: 0,
+                                    };
+                                    sender.send(Action::ToSongListPage(songlist)).await.unwrap();
+                                }
+                                Err(err) => {
+                                    error!("获取链接歌单信息失败！{:?}", err);
+                                    sender
+                                        .send(Action::AddToast(gettext(
+                                            "Request for interface failed, please try again!",
+                                        )))
+                                        .await
+                                        .unwrap();
+                                }
+                            }
+                        });
+                    }
+                    NeteaseLink::Album(id) => {
+                        MAINCONTEXT.spawn_local_with_priority(Priority::DEFAULT_IDLE, async move {
+                            match ncmapi.client.album(id).await {
+                                Ok(album) => {
+                                    debug!("获取链接专辑信息：{:?}", album);
+                                    let songlist = SongList {
+                                        id: album.id,
+                                        name: album.name,
+                                        cover_img_url: album.pic_url,
+                                        author: album.artist_name,
+                                        special_type: 0,
+                                    };
+                                    sender.send(Action::ToAlbumPage(songlist)).await.unwrap();
+                                }
+                                Err(err) => {
+                                    error!("获取链接专辑信息失败！{:?}", err);
+                                    sender
+                                        .send(Action::AddToast(gettext(
+                                            "Request for interface failed, please try again!",
+                                        )))
+                                        .await
+                                        .unwrap();
+                                }
+                            }
+                        });
+                    }
+                    NeteaseLink::Artist(id) => {
+                        // 链接中不含歌手名称，歌手页标题使用通用名称
+                        let singer = SingerInfo {
+                            id,
+                            name: gettext("Artist"),
+                            pic_url: String::new(),
+                        };
+                        sender
+                            .send_blocking(Action::ToSingerSongsPage(singer))
+                            .unwrap();
+                    }
+                }
             }
             Action::ShowPlayerBar => {
                 window.show_player_bar();
             }
-            Action::Quit => unreachable!("quit is handled before window lookup"),
+
+            _ => unreachable!("core actions are handled in process_action"),
         }
         glib::ControlFlow::Continue
     }
@@ -0,0 +1,350 @@
+//
+// library.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use gtk::glib;
+use log::*;
+use ncm_api::SongInfo;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, RwLock},
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    audio::{mp3_duration, read_tags},
+    path::DATA,
+    utils::empty_song_info,
+};
+
+// 本地歌曲 ID 标记位，避免与网易云歌曲 ID 冲突
+pub const LOCAL_SONG_ID_FLAG: u64 = 1 << 63;
+
+// 支持扫描的音频文件后缀
+const AUDIO_EXTENSIONS: [&str; 8] = ["mp3", "flac", "ogg", "opus", "m4a", "aac", "wav", "ape"];
+
+// 本地歌曲 ID 到网易云歌曲 ID 的映射
+static LOCAL_MATCHES: Lazy<RwLock<HashMap<u64, u64>>> = Lazy::new(|| {
+    let library = LocalLibrary::load_from_file();
+    RwLock::new(library.matches())
+});
+
+// 索引在后台线程中修改，读取-修改-保存需依次进行，避免互相覆盖
+static LIBRARY_LOCK: Mutex<()> = Mutex::new(());
+
+fn library_file_path() -> PathBuf {
+    DATA.join("local_library.json")
+}
+
+// 根据文件路径生成稳定的本地歌曲 ID
+pub fn local_song_id(path: &Path) -> u64 {
+    let checksum = glib::compute_checksum_for_string(
+        glib::ChecksumType::Md5,
+        path.to_string_lossy().as_ref(),
+    )
+    .map(|s| s.to_string())
+    .unwrap_or_default();
+    let id = u64::from_str_radix(checksum.get(..15).unwrap_or("0"), 16).unwrap_or(0);
+    id | LOCAL_SONG_ID_FLAG
+}
+
+pub fn is_local_song(si: &SongInfo) -> bool {
+    si.id & LOCAL_SONG_ID_FLAG != 0 && si.song_url.starts_with("file://")
+}
+
+// 歌曲对应的网易云 ID，未匹配的本地歌曲返回 None
+pub fn netease_song_id(si: &SongInfo) -> Option<u64> {
+    if si.id & LOCAL_SONG_ID_FLAG == 0 {
+        return Some(si.id);
+    }
+    LOCAL_MATCHES.read().unwrap().get(&si.id).copied()
+}
+
+// 本地歌曲对应的文件路径
+pub fn local_song_path(si: &SongInfo) -> Option<PathBuf> {
+    if is_local_song(si) {
+        glib::filename_from_uri(&si.song_url).ok().map(|(p, _)| p)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalTrack {
+    pub path: PathBuf,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    // 毫秒
+    pub duration: u64,
+    // 文件修改时间，用于增量扫描
+    pub mtime: u64,
+    // 匹配到的网易云歌曲 ID，0 表示未匹配
+    #[serde(default)]
+    pub netease_id: u64,
+    // 匹配到的歌曲名称，用于显示
+    #[serde(default)]
+    pub matched: String,
+    // 匹配是否已确认（来自文件标签或手动确认）
+    #[serde(default)]
+    pub confirmed: bool,
+}
+
+impl LocalTrack {
+    fn from_path(path: &Path, mtime: u64) -> Self {
+        let tags = read_tags(path).unwrap_or_default();
+        let title = if tags.title.is_empty() {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        } else {
+            tags.title
+        };
+        Self {
+            path: path.to_owned(),
+            title,
+            artist: tags.artists.join("/"),
+            album: tags.album,
+            // 部分 MP3 没有 TLEN 标签，从帧头计算时长
+            duration: tags.duration.or_else(|| mp3_duration(path)).unwrap_or(0),
+            mtime,
+            netease_id: tags.netease_id,
+            matched: String::new(),
+            confirmed: tags.netease_id != 0,
+        }
+    }
+
+    // 设置匹配结果，song 为 None 时清除匹配
+    pub fn set_match(&mut self, song: Option<&SongInfo>, confirmed: bool) {
+        self.netease_id = song.map(|s| s.id).unwrap_or(0);
+        self.matched = song
+            .map(|s| format!("{} - {}", s.name, s.singer))
+            .unwrap_or_default();
+        self.confirmed = confirmed;
+    }
+
+    pub fn id(&self) -> u64 {
+        local_song_id(&self.path)
+    }
+
+    pub fn to_song_info(&self) -> SongInfo {
+        let song_url = glib::filename_to_uri(&self.path, None)
+            .map(|u| u.to_string())
+            .unwrap_or_else(|_| format!("file://{}", self.path.to_string_lossy()));
+        SongInfo {
+            id: self.id(),
+            name: self.title.to_owned(),
+            singer: self.artist.to_owned(),
+            album: self.album.to_owned(),
+            duration: self.duration,
+            song_url,
+            ..empty_song_info()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocalLibrary {
+    pub tracks: Vec<LocalTrack>,
+}
+
+impl LocalLibrary {
+    pub fn load_from_file() -> Self {
+        let path = library_file_path();
+        if !path.exists() {
+            return Self::default();
+        }
+        match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|err| {
+                error!("解析本地音乐索引失败: {:?}", err);
+                Self::default()
+            }),
+            Err(err) => {
+                error!("读取本地音乐索引失败: {:?}", err);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save_to_file(&self) {
+        *LOCAL_MATCHES.write().unwrap() = self.matches();
+        match serde_json::to_string(self) {
+            Ok(json) => {
+                if let Err(err) = fs::write(library_file_path(), json) {
+                    error!("保存本地音乐索引失败: {:?}", err);
+                }
+            }
+            Err(err) => error!("序列化本地音乐索引失败: {:?}", err),
+        }
+    }
+
+    // 读取索引并修改后保存，会阻塞当前线程，需在后台线程调用
+    pub fn update<T>(f: impl FnOnce(&mut Self) -> T) -> T {
+        let _guard = LIBRARY_LOCK.lock().unwrap();
+        let mut library = Self::load_from_file();
+        let res = f(&mut library);
+        library.save_to_file();
+        res
+    }
+
+    // 扫描目录，未修改的文件沿用已有索引
+    pub fn scan(&self, folders: &[PathBuf]) -> Self {
+        let old: HashMap<&Path, &LocalTrack> =
+            self.tracks.iter().map(|t| (t.path.as_path(), t)).collect();
+        let mut tracks = Vec::new();
+        let mut visited = HashSet::new();
+        for folder in folders {
+            let mut files = Vec::new();
+            collect_audio_files(folder, &mut files, &mut visited);
+            for (path, mtime) in files {
+                match old.get(path.as_path()) {
+                    Some(track) if track.mtime == mtime => tracks.push((*track).to_owned()),
+                    Some(track) => {
+                        // 文件已修改，标签中无 ID 时保留原有匹配
+                        let mut new = LocalTrack::from_path(&path, mtime);
+                        if new.netease_id == 0 {
+                            new.netease_id = track.netease_id;
+                            new.matched = track.matched.to_owned();
+                            new.confirmed = track.confirmed;
+                        }
+                        tracks.push(new);
+                    }
+                    None => tracks.push(LocalTrack::from_path(&path, mtime)),
+                }
+            }
+        }
+        tracks.sort_by(|a, b| a.path.cmp(&b.path));
+        tracks.dedup_by(|a, b| a.path == b.path);
+        debug!("扫描本地音乐完成，共 {} 首", tracks.len());
+        Self { tracks }
+    }
+
+    // 沿用另一份索引中未修改文件的匹配结果
+    pub fn keep_matches(&mut self, other: &LocalLibrary) {
+        let old: HashMap<&Path, &LocalTrack> =
+            other.tracks.iter().map(|t| (t.path.as_path(), t)).collect();
+        for track in self.tracks.iter_mut() {
+            if let Some(old) = old.get(track.path.as_path()).filter(|t| t.mtime == track.mtime) {
+                track.netease_id = old.netease_id;
+                track.matched = old.matched.to_owned();
+                track.confirmed = old.confirmed;
+            }
+        }
+    }
+
+    pub fn song_infos(&self) -> Vec<SongInfo> {
+        self.tracks.iter().map(LocalTrack::to_song_info).collect()
+    }
+
+    pub fn matches(&self) -> HashMap<u64, u64> {
+        self.tracks
+            .iter()
+            .filter(|t| t.netease_id != 0)
+            .map(|t| (t.id(), t.netease_id))
+            .collect()
+    }
+
+    pub fn set_match(&mut self, local_id: u64, song: Option<&SongInfo>, confirmed: bool) {
+        if let Some(track) = self.tracks.iter_mut().find(|t| t.id() == local_id) {
+            track.set_match(song, confirmed);
+        }
+    }
+}
+
+// 音乐目录及其全部子目录，用于目录监视
+pub fn local_library_dirs(folders: &[PathBuf]) -> Vec<PathBuf> {
+    fn collect(dir: &Path, dirs: &mut Vec<PathBuf>, visited: &mut HashSet<PathBuf>) {
+        if !first_visit(dir, visited) {
+            return;
+        }
+        dirs.push(dir.to_owned());
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    collect(&path, dirs, visited);
+                }
+            }
+        }
+    }
+    let mut dirs = Vec::new();
+    let mut visited = HashSet::new();
+    for folder in folders {
+        collect(folder, &mut dirs, &mut visited);
+    }
+    dirs
+}
+
+// 记录已访问的目录，避免符号链接形成的循环导致无限递归
+pub fn first_visit(dir: &Path, visited: &mut HashSet<PathBuf>) -> bool {
+    match fs::canonicalize(dir) {
+        Ok(dir) => visited.insert(dir),
+        Err(_) => false,
+    }
+}
+
+fn collect_audio_files(
+    dir: &Path,
+    files: &mut Vec<(PathBuf, u64)>,
+    visited: &mut HashSet<PathBuf>,
+) {
+    if !first_visit(dir, visited) {
+        return;
+    }
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!("无法读取目录 {:?}: {:?}", dir, err);
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_audio_files(&path, files, visited);
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| AUDIO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+            .unwrap_or(false)
+        {
+            let mtime = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            files.push((path, mtime));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symlink_loop() {
+        let root = std::env::temp_dir().join(format!("ncm-library-{}", std::process::id()));
+        let sub = root.join("a");
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(sub.join("song.mp3"), b"ID3").unwrap();
+        std::os::unix::fs::symlink("..", sub.join("loop")).unwrap();
+
+        let dirs = local_library_dirs(std::slice::from_ref(&root));
+        let mut files = Vec::new();
+        collect_audio_files(&root, &mut files, &mut HashSet::new());
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(dirs, vec![root.to_owned(), sub.to_owned()]);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, sub.join("song.mp3"));
+    }
+}
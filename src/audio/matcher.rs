@@ -0,0 +1,242 @@
+//
+// matcher.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use ncm_api::SongInfo;
+
+use crate::{audio::split_artists, lyrics::match_key};
+
+// 自动匹配的最低得分
+pub const MATCH_THRESHOLD: f64 = 0.75;
+
+// 各项权重：标题、歌手、专辑、时长
+const TITLE_WEIGHT: f64 = 0.45;
+const ARTIST_WEIGHT: f64 = 0.3;
+const ALBUM_WEIGHT: f64 = 0.1;
+const DURATION_WEIGHT: f64 = 0.15;
+
+// 用于搜索网易云的关键字
+pub fn match_keyword(local: &SongInfo) -> String {
+    format!("{} {}", local.name, local.singer).trim().to_owned()
+}
+
+// 字符串相似度，忽略大小写、空白及标点差异，取值 0.0 ~ 1.0
+fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (match_key(a), match_key(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+    if a.contains(&b) || b.contains(&a) {
+        return 0.8;
+    }
+    // 二元组 Dice 系数
+    let bigrams = |s: &str| {
+        let chars: Vec<char> = s.chars().collect();
+        chars.windows(2).map(|w| (w[0], w[1])).collect::<Vec<_>>()
+    };
+    let (ba, mut bb) = (bigrams(&a), bigrams(&b));
+    if ba.is_empty() || bb.is_empty() {
+        return 0.0;
+    }
+    let total = (ba.len() + bb.len()) as f64;
+    let mut hits = 0;
+    for g in ba {
+        if let Some(pos) = bb.iter().position(|x| *x == g) {
+            bb.swap_remove(pos);
+            hits += 1;
+        }
+    }
+    2.0 * hits as f64 / total
+}
+
+fn artist_score(local: &str, candidate: &str) -> f64 {
+    let locals = split_artists(local);
+    let candidates = split_artists(candidate);
+    if locals.is_empty() || candidates.is_empty() {
+        return 0.0;
+    }
+    let sum: f64 = locals
+        .iter()
+        .map(|l| {
+            candidates
+                .iter()
+                .map(|c| similarity(l, c))
+                .fold(0.0, f64::max)
+        })
+        .sum();
+    sum / locals.len() as f64
+}
+
+fn duration_score(local: u64, candidate: u64) -> f64 {
+    if local == 0 || candidate == 0 {
+        // 无时长信息时不加分也不扣分
+        return 0.5;
+    }
+    match local.abs_diff(candidate) / 1000 {
+        0..=2 => 1.0,
+        3..=5 => 0.7,
+        6..=10 => 0.3,
+        _ => 0.0,
+    }
+}
+
+// 计算候选歌曲与本地歌曲的匹配得分，满分 1.0
+pub fn match_score(local: &SongInfo, candidate: &SongInfo) -> f64 {
+    let album = if local.album.is_empty() {
+        0.5
+    } else {
+        similarity(&local.album, &candidate.album)
+    };
+    similarity(&local.name, &candidate.name) * TITLE_WEIGHT
+        + artist_score(&local.singer, &candidate.singer) * ARTIST_WEIGHT
+        + album * ALBUM_WEIGHT
+        + duration_score(local.duration, candidate.duration) * DURATION_WEIGHT
+}
+
+// 选出得分最高的候选歌曲
+pub fn best_match<'a>(
+    local: &SongInfo,
+    candidates: &'a [SongInfo],
+) -> Option<(&'a SongInfo, f64)> {
+    candidates
+        .iter()
+        .map(|c| (c, match_score(local, c)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::empty_song_info;
+
+    fn song(name: &str, singer: &str, album: &str, duration: u64) -> SongInfo {
+        SongInfo {
+            name: name.to_owned(),
+            singer: singer.to_owned(),
+            album: album.to_owned(),
+            duration,
+            ..empty_song_info()
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64, case: &str) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{case}: {actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn similarities() {
+        let cases = [
+            ("Hello", "hello", 1.0),
+            ("Hello, World!", "hello world", 1.0),
+            ("晴天", "晴天", 1.0),
+            // 包含关系，如现场版
+            ("晴天 (Live)", "晴天", 0.8),
+            ("Love", "Love Story", 0.8),
+            // 二元组 Dice 系数
+            ("night", "nacht", 0.25),
+            ("abcd", "abce", 2.0 / 3.0),
+            ("七里香", "七里路", 0.5),
+            ("七里香", "稻香", 0.0),
+            ("a", "b", 0.0),
+            ("", "a", 0.0),
+            ("!!!", "abc", 0.0),
+        ];
+        for (a, b, expected) in cases {
+            assert_close(similarity(a, b), expected, &format!("{a:?} {b:?}"));
+            assert_close(similarity(b, a), expected, &format!("{b:?} {a:?}"));
+        }
+    }
+
+    #[test]
+    fn artists() {
+        let cases = [
+            ("周杰伦", "周杰伦", 1.0),
+            ("周杰伦/费玉清", "费玉清/周杰伦", 1.0),
+            ("周杰伦、费玉清", "周杰伦 / 费玉清", 1.0),
+            // 候选歌曲有更多歌手
+            ("周杰伦", "周杰伦/费玉清", 1.0),
+            // 只匹配到一半本地歌手
+            ("周杰伦/费玉清", "周杰伦", 0.5),
+            ("", "周杰伦", 0.0),
+            ("周杰伦", "", 0.0),
+        ];
+        for (local, candidate, expected) in cases {
+            assert_close(
+                artist_score(local, candidate),
+                expected,
+                &format!("{local:?} {candidate:?}"),
+            );
+        }
+    }
+
+    #[test]
+    fn durations() {
+        let cases = [
+            (0, 200_000, 0.5),
+            (200_000, 0, 0.5),
+            (200_000, 200_000, 1.0),
+            (200_000, 202_999, 1.0),
+            (200_000, 197_001, 1.0),
+            (200_000, 203_000, 0.7),
+            (200_000, 205_999, 0.7),
+            (200_000, 194_000, 0.3),
+            (200_000, 210_999, 0.3),
+            (200_000, 211_000, 0.0),
+        ];
+        for (local, candidate, expected) in cases {
+            assert_close(
+                duration_score(local, candidate),
+                expected,
+                &format!("{local} {candidate}"),
+            );
+        }
+    }
+
+    #[test]
+    fn scores() {
+        let candidate = song("晴天", "周杰伦", "叶惠美", 269_000);
+        let cases = [
+            (song("晴天", "周杰伦", "叶惠美", 269_000), 1.0),
+            (song("晴天", "周杰伦", "叶惠美", 270_500), 1.0),
+            // 标题包含关系
+            (song("晴天 (Live)", "周杰伦", "叶惠美", 269_000), 0.91),
+            // 缺少专辑及时长
+            (song("晴天", "周杰伦", "", 0), 0.875),
+            // 多歌手且缺少专辑及时长，低于阈值
+            (song("晴天", "周杰伦/费玉清", "", 0), 0.725),
+            // 专辑不同且时长相差较大，恰好达到阈值
+            (song("晴天", "周杰伦", "Jay Chou Live", 300_000), 0.75),
+            (song("七里香", "周杰伦", "七里香", 299_000), 0.3),
+        ];
+        for (local, expected) in cases {
+            let score = match_score(&local, &candidate);
+            assert_close(score, expected, &local.name);
+        }
+        let boundary = song("晴天", "周杰伦", "Jay Chou Live", 300_000);
+        assert!(match_score(&boundary, &candidate) >= MATCH_THRESHOLD);
+        let below = song("晴天", "周杰伦/费玉清", "", 0);
+        assert!(match_score(&below, &candidate) < MATCH_THRESHOLD);
+    }
+
+    #[test]
+    fn best() {
+        let local = song("晴天", "周杰伦", "叶惠美", 269_000);
+        let candidates = [
+            song("晴天", "Various Artists", "KTV", 180_000),
+            song("晴天 (Live)", "周杰伦", "2004 无与伦比演唱会", 300_000),
+            song("晴天", "周杰伦", "叶惠美", 269_500),
+        ];
+        let (best, score) = best_match(&local, &candidates).unwrap();
+        assert_eq!(best.album, "叶惠美");
+        assert_close(score, 1.0, "best");
+        assert!(best_match(&local, &[]).is_none());
+    }
+}
@@ -4,8 +4,22 @@
 // Distributed under terms of the GPL-3.0-or-later license.
 //
 
+mod library;
+mod matcher;
 mod mpris;
+mod mpris_extension;
+mod mpris_playlists;
+mod mpris_tracklist;
+mod playback;
 mod playlist;
+mod tagger;
 
+pub use library::*;
+pub use matcher::*;
 pub use mpris::*;
+pub use mpris_extension::*;
+pub use mpris_playlists::*;
+pub use mpris_tracklist::*;
+pub use playback::*;
 pub use playlist::*;
+pub use tagger::*;
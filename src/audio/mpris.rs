@@ -4,20 +4,108 @@
 // Distributed under terms of the GPL-3.0-or-later license.
 //
 
-use adw::prelude::GtkWindowExt;
+use async_channel::Sender;
 use glib::clone;
-use gtk::prelude::Cast;
 use gtk::glib;
+use gtk::prelude::ApplicationExt;
 use mpris_server::{zbus::Result, Time, TrackId, *};
 
 use ncm_api::SongInfo;
-use std::rc::Rc;
+use once_cell::sync::Lazy;
+use std::{collections::HashMap, rc::Rc, sync::Mutex};
 
-use crate::gui::PlayerControls;
-use crate::window::NeteaseCloudMusicGtk4Window;
-use crate::application::NeteaseCloudMusicGtk4Application;
+use crate::{
+    application::{Action, NeteaseCloudMusicGtk4Application},
+    links::NeteaseLink,
+};
 
-use super::LoopsState;
+use super::{
+    LoopsState, Playback, PlayerExtension, Playlists, TrackList, TrackTags, is_local_song,
+    split_artists,
+};
+
+// 当前歌词行的自定义元数据字段
+const LYRIC_KEY: &str = "ncmgtk:lyric";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+
+// 在线歌曲的曲目序号，SongInfo 中没有该字段，需另外从歌曲详情获取
+static TRACK_NUMBERS: Lazy<Mutex<HashMap<u64, u32>>> = Lazy::new(Default::default);
+
+// 本地歌曲的文件标签，在后台读取后缓存，生成元数据时不读取文件
+static LOCAL_TAGS: Lazy<Mutex<HashMap<u64, TrackTags>>> = Lazy::new(Default::default);
+
+pub fn cached_track_number(id: u64) -> Option<u32> {
+    TRACK_NUMBERS.lock().unwrap().get(&id).copied()
+}
+
+pub fn has_local_tags(id: u64) -> bool {
+    LOCAL_TAGS.lock().unwrap().contains_key(&id)
+}
+
+// 曲目 ID 由播放列表位置与歌曲 ID 组成，同一歌曲在列表中多次出现时也不重复
+pub fn track_id(index: usize, id: u64) -> TrackId {
+    TrackId::try_from(format!(
+        "/com/gitee/gmg137/NeteaseCloudMusicGtk4/{}_{}",
+        index, id
+    ))
+    .unwrap_or(TrackId::NO_TRACK)
+}
+
+// 曲目 ID 对应的歌曲 ID
+pub fn track_song_id(track_id: &TrackId) -> Option<u64> {
+    track_id.as_str().rsplit_once('_')?.1.parse().ok()
+}
+
+// 元数据是否属于指定歌曲
+fn is_current_song(metadata: &Metadata, id: u64) -> bool {
+    metadata
+        .trackid()
+        .is_some_and(|track_id| track_song_id(&track_id) == Some(id))
+}
+
+// 仅使用已缓存的封面，避免锁屏等组件直接请求网络
+pub fn cover_art_url(album_id: u64) -> Option<String> {
+    let path = crate::path::get_cover_cache_path(album_id);
+    if !path.exists() {
+        return None;
+    }
+    glib::filename_to_uri(&path, None)
+        .ok()
+        .map(|uri| uri.to_string())
+}
+
+// index 为歌曲在播放列表中的位置
+pub fn song_metadata(index: usize, si: &SongInfo) -> Metadata {
+    let mut metadata = Metadata::new();
+    metadata.set_artist(Some(split_artists(&si.singer)));
+    metadata.set_track_number(cached_track_number(si.id).map(|track| track as i32));
+    // 本地歌曲优先使用已缓存的文件标签
+    if let Some(tags) = LOCAL_TAGS.lock().unwrap().get(&si.id) {
+        apply_local_tags(&mut metadata, tags);
+    }
+    metadata.set_title(Some(si.name.clone()));
+    metadata.set_album(Some(si.album.clone()));
+    metadata.set_length(Some(Time::from_micros(si.duration as i64 * 1000)));
+    metadata.set_trackid(Some(track_id(index, si.id)));
+    // 在线歌曲的播放链接会过期，使用歌曲页面链接
+    let url = if is_local_song(si) {
+        si.song_url.to_owned()
+    } else {
+        NeteaseLink::Song(si.id).url(None)
+    };
+    metadata.set_url(Some(url));
+    metadata.set_art_url(cover_art_url(si.album_id));
+    metadata
+}
+
+// 网易云未提供专辑歌手，只使用文件标签中的值
+fn apply_local_tags(metadata: &mut Metadata, tags: &TrackTags) {
+    if !tags.artists.is_empty() {
+        metadata.set_artist(Some(tags.artists.to_owned()));
+    }
+    metadata.set_album_artist(tags.album_artist.to_owned().map(|artist| vec![artist]));
+    metadata.set_track_number(tags.track.map(|track| track as i32));
+}
 
 unsafe impl Send for MprisController {}
 unsafe impl Sync for MprisController {}
@@ -28,7 +116,7 @@ pub struct MprisController {
 }
 
 impl MprisController {
-    pub async fn new() -> Result<Self> {
+    pub async fn new(sender: Sender<Action>) -> Result<Self> {
         let mpris_player = Rc::new(
             Player::builder(crate::MPRIS_NAME)
                 .identity(gettextrs::gettext(crate::APP_NAME))
@@ -41,7 +129,8 @@ impl MprisController {
                 .can_go_next(true)
                 .can_go_previous(true)
                 .can_quit(true)
-                .can_seek(false)
+                .can_seek(true)
+                .has_track_list(true)
                 .build()
                 .await?,
         );
@@ -53,31 +142,122 @@ impl MprisController {
         metadata.set_trackid(Some(TrackId::NO_TRACK));
         mpris_player.set_metadata(metadata).await.ok();
 
+        // TrackList、Playlists 与自定义接口注册在同一对象路径下
+        let object_server = mpris_player.connection().object_server();
+        object_server
+            .at(MPRIS_PATH, TrackList::new(sender.clone()))
+            .await?;
+        object_server
+            .at(MPRIS_PATH, Playlists::new(sender.clone()))
+            .await?;
+        object_server
+            .at(MPRIS_PATH, PlayerExtension::new(sender))
+            .await?;
+
         Ok(Self { mpris_player })
     }
 
-    pub async fn update_metadata(&self, si: &SongInfo) -> Result<()> {
-        let mut metadata = Metadata::new();
-        metadata.set_artist(Some(vec![si.singer.clone()]));
-        metadata.set_title(Some(si.name.clone()));
-        metadata.set_album(Some(si.album.clone()));
-        metadata.set_length(Some(Time::from_micros(si.duration as i64 * 1000)));
-        metadata.set_trackid(
-            TrackId::try_from(format!("/com/gitee/gmg137/NeteaseCloudMusicGtk4/{}", si.id)).ok(),
-        );
-        // 取消从缓存获取专辑封面
-        //let mut path_cover = crate::path::CACHE.clone();
-        //path_cover.push(format!("{}-songlist.jpg", si.album_id));
-        //if path_cover.exists() {
-        //metadata.set_art_url(Some(format!("file://{}", path_cover.to_string_lossy())));
-        //} else {
-        //metadata.set_art_url(Some(si.pic_url.to_owned()));
-        //}
-        metadata.set_art_url(Some(si.pic_url.to_owned()));
+    pub async fn update_metadata(&self, index: usize, si: &SongInfo, like: bool) -> Result<()> {
+        let mut metadata = song_metadata(index, si);
+        metadata.set_user_rating(Some(if like { 1.0 } else { 0.0 }));
         self.mpris_player.set_metadata(metadata).await?;
+        self.set_liked(like).await
+    }
+
+    // 红心状态对应 xesam:userRating
+    pub async fn set_user_rating(&self, like: bool) -> Result<()> {
+        let mut metadata = self.mpris_player.metadata();
+        let rating = if like { 1.0 } else { 0.0 };
+        if metadata.user_rating() != Some(rating) {
+            metadata.set_user_rating(Some(rating));
+            self.mpris_player.set_metadata(metadata).await?;
+        }
+        self.set_liked(like).await
+    }
+
+    // 同步自定义接口的 Liked 属性
+    async fn set_liked(&self, like: bool) -> Result<()> {
+        let iface = self
+            .mpris_player
+            .connection()
+            .object_server()
+            .interface::<_, PlayerExtension>(MPRIS_PATH)
+            .await?;
+        let mut extension = iface.get_mut().await;
+        if extension.set_liked(like) {
+            extension.liked_changed(iface.signal_emitter()).await?;
+        }
         Ok(())
     }
 
+    // 封面下载完成后更新 mpris:artUrl
+    pub async fn set_cover(&self, album_id: u64) -> Result<()> {
+        let mut metadata = self.mpris_player.metadata();
+        let art_url = cover_art_url(album_id);
+        if art_url.is_none() || metadata.art_url() == art_url.as_deref() {
+            return Ok(());
+        }
+        metadata.set_art_url(art_url);
+        self.mpris_player.set_metadata(metadata).await
+    }
+
+    // 获取到在线歌曲的曲目序号后更新 xesam:trackNumber
+    pub async fn set_track_number(&self, id: u64, track: u32) -> Result<()> {
+        TRACK_NUMBERS.lock().unwrap().insert(id, track);
+        let mut metadata = self.mpris_player.metadata();
+        if !is_current_song(&metadata, id) {
+            return Ok(());
+        }
+        metadata.set_track_number(Some(track as i32));
+        self.mpris_player.set_metadata(metadata).await
+    }
+
+    // 后台读取到本地歌曲的文件标签后更新歌手、专辑歌手及曲目序号
+    pub async fn set_local_tags(&self, id: u64, tags: TrackTags) -> Result<()> {
+        let mut metadata = self.mpris_player.metadata();
+        let current = is_current_song(&metadata, id);
+        apply_local_tags(&mut metadata, &tags);
+        LOCAL_TAGS.lock().unwrap().insert(id, tags);
+        if !current {
+            return Ok(());
+        }
+        self.mpris_player.set_metadata(metadata).await
+    }
+
+    // 同步播放列表到 TrackList 接口，列表有变化时发送 TrackListReplaced
+    pub async fn update_tracklist(
+        &self,
+        songs: Vec<SongInfo>,
+        current: Option<usize>,
+    ) -> Result<()> {
+        let iface = self
+            .mpris_player
+            .connection()
+            .object_server()
+            .interface::<_, TrackList>(MPRIS_PATH)
+            .await?;
+        let mut tracklist = iface.get_mut().await;
+        if !tracklist.set_songs(songs) {
+            return Ok(());
+        }
+        let current = current
+            .and_then(|index| tracklist.track_id_at(index))
+            .unwrap_or(TrackId::NO_TRACK);
+        TrackList::track_list_replaced(iface.signal_emitter(), tracklist.track_ids(), current)
+            .await
+    }
+
+    // 当前歌词行，同时写入 xesam:asText 与自定义字段
+    pub async fn set_lyric(&self, line: &str) -> Result<()> {
+        let mut metadata = self.mpris_player.metadata();
+        if metadata.lyrics() == Some(line) {
+            return Ok(());
+        }
+        metadata.set_lyrics(Some(line));
+        metadata.set(LYRIC_KEY, Some(line.to_owned()));
+        self.mpris_player.set_metadata(metadata).await
+    }
+
     pub async fn set_volume(&self, volume: f64) -> Result<()> {
         if (self.mpris_player.volume() * 100.0).round() as i64 != (volume * 100.0).round() as i64 {
             self.mpris_player.set_volume(volume).await?;
@@ -135,22 +315,15 @@ impl MprisController {
         self.mpris_player.seeked(Time::from_micros(value)).await
     }
 
-    pub fn setup_signals(&self, player_controls: &PlayerControls) {
-        // mpris raise
+    pub fn setup_signals(&self, playback: &Playback) {
+        // mpris raise，无窗口模式下会创建窗口
         self.mpris_player.connect_raise(move |_| {
-            let window = NeteaseCloudMusicGtk4Window::default();
-            window.present();
+            NeteaseCloudMusicGtk4Application::default().activate();
         });
 
         // mpris quit
         self.mpris_player.connect_quit(move |_| {
-            let window = NeteaseCloudMusicGtk4Window::default();
-            if let Some(app) = window
-                .application()
-                .and_then(|app| app.downcast::<NeteaseCloudMusicGtk4Application>().ok())
-            {
-                app.graceful_quit();
-            }
+            NeteaseCloudMusicGtk4Application::default().graceful_quit();
         });
 
         // mpris play / pause
@@ -158,12 +331,12 @@ impl MprisController {
             #[weak(rename_to = mpris)]
             self.mpris_player,
             #[weak]
-            player_controls,
+            playback,
             move |_| {
                 match mpris.playback_status() {
-                    PlaybackStatus::Paused => player_controls.switch_play(),
-                    PlaybackStatus::Stopped => player_controls.switch_play(),
-                    _ => player_controls.switch_pause(),
+                    PlaybackStatus::Paused => playback.switch_play(),
+                    PlaybackStatus::Stopped => playback.switch_play(),
+                    _ => playback.switch_pause(),
                 };
             }
         ));
@@ -171,72 +344,98 @@ impl MprisController {
         // mpris play
         self.mpris_player.connect_play(clone!(
             #[weak]
-            player_controls,
+            playback,
             move |_| {
-                player_controls.switch_play();
+                playback.switch_play();
             }
         ));
 
         // mpris pause
         self.mpris_player.connect_pause(clone!(
             #[weak]
-            player_controls,
+            playback,
             move |_| {
-                player_controls.switch_pause();
+                playback.switch_pause();
             }
         ));
 
         // mpris stop
         self.mpris_player.connect_stop(clone!(
             #[weak]
-            player_controls,
+            playback,
             move |_| {
-                player_controls.switch_stop();
+                playback.switch_stop();
             }
         ));
 
         // mpris next
         self.mpris_player.connect_next(clone!(
             #[weak]
-            player_controls,
+            playback,
             move |_| {
-                player_controls.next_song();
+                playback.next_song();
             }
         ));
 
         // mpris prev
         self.mpris_player.connect_previous(clone!(
             #[weak]
-            player_controls,
+            playback,
             move |_| {
-                player_controls.prev_song();
+                playback.prev_song();
             }
         ));
 
         // mpris loop
         self.mpris_player.connect_set_loop_status(clone!(
             #[weak]
-            player_controls,
+            playback,
             move |_, status| {
-                player_controls.set_loops(LoopsState::from(status));
+                playback.set_loops(LoopsState::from(status));
             }
         ));
 
         // mpris shuffle
         self.mpris_player.connect_set_shuffle(clone!(
             #[weak]
-            player_controls,
+            playback,
             move |_, status| {
-                player_controls.set_shuffle(status);
+                playback.set_shuffle(status);
+            }
+        ));
+
+        // mpris seek，偏移量为相对当前位置的微秒数
+        self.mpris_player.connect_seek(clone!(
+            #[weak]
+            playback,
+            move |_, offset| {
+                let position = playback.get_position() as i64 * 1000;
+                playback.seek((position + offset.as_micros()).max(0) as u64);
+            }
+        ));
+
+        // mpris set position，曲目 ID 不匹配或位置越界时忽略
+        self.mpris_player.connect_set_position(clone!(
+            #[weak]
+            playback,
+            move |player, track_id, position| {
+                if player.metadata().trackid().as_ref() != Some(track_id) {
+                    return;
+                }
+                let length = player.metadata().length().unwrap_or_default();
+                if position.as_micros() < 0 || position > length {
+                    return;
+                }
+                playback.seek(position.as_micros() as u64);
             }
         ));
 
         // mpris volume
         self.mpris_player.connect_set_volume(clone!(
             #[weak]
-            player_controls,
+            playback,
             move |_, value| {
-                player_controls.set_volume(value);
+                playback.set_volume(value);
             }
         ));
     }
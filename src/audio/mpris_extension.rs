@@ -0,0 +1,69 @@
+//
+// mpris_extension.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use async_channel::Sender;
+use mpris_server::zbus::interface;
+
+use crate::application::Action;
+
+// 与 MPRIS 位于同一总线名称和对象路径（/org/mpris/MediaPlayer2）下的自定义接口
+// 方法：
+//   Like()       收藏当前歌曲
+//   Unlike()     取消收藏当前歌曲
+//   ToggleLike() 切换当前歌曲的收藏状态
+//   Trash()      将当前歌曲标记为不喜欢（私人 FM 垃圾桶）并跳到下一首
+// 属性：
+//   Liked (b)    当前歌曲是否已收藏
+pub struct PlayerExtension {
+    sender: Sender<Action>,
+    liked: bool,
+}
+
+impl PlayerExtension {
+    pub fn new(sender: Sender<Action>) -> Self {
+        Self {
+            sender,
+            liked: false,
+        }
+    }
+
+    // 更新收藏状态，有变化时返回 true
+    pub fn set_liked(&mut self, liked: bool) -> bool {
+        let changed = self.liked != liked;
+        self.liked = liked;
+        changed
+    }
+}
+
+#[interface(name = "com.gitee.gmg137.NeteaseCloudMusicGtk4.Player")]
+impl PlayerExtension {
+    async fn like(&self) {
+        self.sender
+            .send(Action::LikeCurrentSong(Some(true)))
+            .await
+            .ok();
+    }
+
+    async fn unlike(&self) {
+        self.sender
+            .send(Action::LikeCurrentSong(Some(false)))
+            .await
+            .ok();
+    }
+
+    async fn toggle_like(&self) {
+        self.sender.send(Action::LikeCurrentSong(None)).await.ok();
+    }
+
+    async fn trash(&self) {
+        self.sender.send(Action::TrashCurrentSong).await.ok();
+    }
+
+    #[zbus(property)]
+    async fn liked(&self) -> bool {
+        self.liked
+    }
+}
@@ -0,0 +1,134 @@
+//
+// mpris_playlists.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use async_channel::Sender;
+use mpris_server::zbus::{interface, zvariant::OwnedObjectPath};
+use ncm_api::SongList;
+use std::sync::Arc;
+
+use crate::{
+    application::Action,
+    model::{SearchResult, SearchType},
+};
+
+const PLAYLIST_PATH: &str = "/com/gitee/gmg137/NeteaseCloudMusicGtk4/playlist";
+
+// (对象路径, 名称, 图标)
+type Playlist = (OwnedObjectPath, String, String);
+
+fn playlist_path(id: u64) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(format!("{PLAYLIST_PATH}/{id}")).unwrap()
+}
+
+fn no_playlist() -> Playlist {
+    (
+        OwnedObjectPath::try_from("/").unwrap(),
+        String::new(),
+        String::new(),
+    )
+}
+
+// org.mpris.MediaPlayer2.Playlists，提供用户创建和收藏的网易云歌单
+pub struct Playlists {
+    sender: Sender<Action>,
+    count: u32,
+    active: Option<Playlist>,
+    // 最近一次获取的歌单，用于激活时查找名称
+    cached: Vec<SongList>,
+}
+
+impl Playlists {
+    pub fn new(sender: Sender<Action>) -> Self {
+        Self {
+            sender,
+            count: 0,
+            active: None,
+            cached: Vec::new(),
+        }
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Playlists")]
+impl Playlists {
+    async fn activate_playlist(&mut self, playlist_id: OwnedObjectPath) {
+        let Some(id) = playlist_id
+            .as_str()
+            .strip_prefix(PLAYLIST_PATH)
+            .and_then(|s| s.strip_prefix('/'))
+            .and_then(|s| s.parse::<u64>().ok())
+        else {
+            return;
+        };
+        let name = self
+            .cached
+            .iter()
+            .find(|sl| sl.id == id)
+            .map(|sl| (sl.name.to_owned(), sl.cover_img_url.to_owned()))
+            .unwrap_or_default();
+        self.active = Some((playlist_id, name.0, name.1));
+        self.sender.send(Action::PlaySongList(id)).await.ok();
+    }
+
+    // 歌单仅支持网易云的默认顺序
+    async fn get_playlists(
+        &mut self,
+        index: u32,
+        max_count: u32,
+        _order: String,
+        reverse_order: bool,
+    ) -> Vec<Playlist> {
+        let (tx, rx) = async_channel::bounded(1);
+        let action = Action::Search(
+            String::new(),
+            SearchType::LikeSongList,
+            index.min(u16::MAX as u32) as u16,
+            max_count.min(u16::MAX as u32) as u16,
+            Arc::new(move |res| {
+                tx.try_send(res).ok();
+            }),
+        );
+        if self.sender.send(action).await.is_err() {
+            return Vec::new();
+        }
+        let Ok(SearchResult::SongLists(sls)) = rx.recv().await else {
+            return Vec::new();
+        };
+        self.count = self.count.max(index + sls.len() as u32);
+        let mut playlists: Vec<Playlist> = sls
+            .iter()
+            .map(|sl| {
+                (
+                    playlist_path(sl.id),
+                    sl.name.to_owned(),
+                    sl.cover_img_url.to_owned(),
+                )
+            })
+            .collect();
+        if reverse_order {
+            playlists.reverse();
+        }
+        self.cached = sls;
+        playlists
+    }
+
+    #[zbus(property)]
+    async fn playlist_count(&self) -> u32 {
+        self.count
+    }
+
+    #[zbus(property)]
+    async fn orderings(&self) -> Vec<String> {
+        vec!["UserDefined".to_owned()]
+    }
+
+    #[zbus(property)]
+    async fn active_playlist(&self) -> (bool, Playlist) {
+        match &self.active {
+            Some(playlist) => (true, playlist.to_owned()),
+            None => (false, no_playlist()),
+        }
+    }
+}
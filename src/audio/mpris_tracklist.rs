@@ -0,0 +1,152 @@
+//
+// mpris_tracklist.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use async_channel::Sender;
+use mpris_server::{
+    Metadata, TrackId,
+    zbus::{self, interface, object_server::SignalEmitter},
+};
+use ncm_api::SongInfo;
+
+use crate::{
+    application::Action,
+    links::{NeteaseLink, parse_link},
+};
+
+use super::{song_metadata, track_id};
+
+// org.mpris.MediaPlayer2.TrackList，内容与播放列表同步
+pub struct TrackList {
+    sender: Sender<Action>,
+    songs: Vec<SongInfo>,
+}
+
+impl TrackList {
+    pub fn new(sender: Sender<Action>) -> Self {
+        Self {
+            sender,
+            songs: Vec::new(),
+        }
+    }
+
+    // 更新歌曲列表，内容有变化时返回 true
+    pub fn set_songs(&mut self, songs: Vec<SongInfo>) -> bool {
+        let same = self.songs.len() == songs.len()
+            && self.songs.iter().zip(&songs).all(|(a, b)| a.id == b.id);
+        self.songs = songs;
+        !same
+    }
+
+    pub fn track_ids(&self) -> Vec<TrackId> {
+        self.songs
+            .iter()
+            .enumerate()
+            .map(|(index, si)| track_id(index, si.id))
+            .collect()
+    }
+
+    pub fn track_id_at(&self, index: usize) -> Option<TrackId> {
+        self.songs.get(index).map(|si| track_id(index, si.id))
+    }
+
+    fn find_song(&self, id: &TrackId) -> Option<(usize, &SongInfo)> {
+        self.songs
+            .iter()
+            .enumerate()
+            .find(|(index, si)| track_id(*index, si.id) == *id)
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.TrackList")]
+impl TrackList {
+    async fn get_tracks_metadata(&self, track_ids: Vec<TrackId>) -> Vec<Metadata> {
+        track_ids
+            .iter()
+            .filter_map(|id| self.find_song(id))
+            .map(|(index, si)| song_metadata(index, si))
+            .collect()
+    }
+
+    // 仅支持网易云歌曲链接，添加位置由播放列表决定
+    async fn add_track(&self, uri: String, _after_track: TrackId, set_as_current: bool) {
+        if let Some(NeteaseLink::Song(id)) = parse_link(&uri) {
+            self.sender
+                .send(Action::AddTrackById(id, set_as_current))
+                .await
+                .ok();
+        }
+    }
+
+    async fn remove_track(&self, track_id: TrackId) {
+        if let Some((_, si)) = self.find_song(&track_id) {
+            self.sender
+                .send(Action::RemoveFromPlayList(si.to_owned()))
+                .await
+                .ok();
+        }
+    }
+
+    async fn go_to(&self, track_id: TrackId) {
+        if let Some((_, si)) = self.find_song(&track_id) {
+            self.sender.send(Action::AddPlay(si.to_owned())).await.ok();
+        }
+    }
+
+    #[zbus(property)]
+    async fn tracks(&self) -> Vec<TrackId> {
+        self.track_ids()
+    }
+
+    #[zbus(property)]
+    async fn can_edit_tracks(&self) -> bool {
+        true
+    }
+
+    #[zbus(signal)]
+    pub async fn track_list_replaced(
+        emitter: &SignalEmitter<'_>,
+        tracks: Vec<TrackId>,
+        current_track: TrackId,
+    ) -> zbus::Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{audio::track_song_id, utils::empty_song_info};
+
+    fn song(id: u64) -> SongInfo {
+        SongInfo {
+            id,
+            ..empty_song_info()
+        }
+    }
+
+    #[test]
+    fn duplicate_songs() {
+        let mut tracklist = TrackList::new(async_channel::unbounded().0);
+        assert!(tracklist.set_songs(vec![song(1), song(2), song(1)]));
+        assert!(!tracklist.set_songs(vec![song(1), song(2), song(1)]));
+
+        let ids = tracklist.track_ids();
+        assert_eq!(ids.len(), 3);
+        assert_ne!(ids[0], ids[2]);
+        assert_eq!(tracklist.track_id_at(2).as_ref(), Some(&ids[2]));
+        assert_eq!(tracklist.track_id_at(3), None);
+
+        let found: Vec<usize> = ids
+            .iter()
+            .filter_map(|id| tracklist.find_song(id).map(|(index, _)| index))
+            .collect();
+        assert_eq!(found, [0, 1, 2]);
+        assert!(tracklist.find_song(&TrackId::NO_TRACK).is_none());
+        assert_eq!(
+            ids.iter().map(track_song_id).collect::<Vec<_>>(),
+            [Some(1), Some(2), Some(1)]
+        );
+        assert_eq!(track_song_id(&TrackId::NO_TRACK), None);
+    }
+}
@@ -0,0 +1,1288 @@
+//
+// playback.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use async_channel::Sender;
+use gettextrs::gettext;
+use gio::Settings;
+use glib::{
+    ParamSpec, ParamSpecBoolean, ParamSpecDouble, ParamSpecEnum, ParamSpecString, ParamSpecUInt,
+    ParamSpecUInt64, Value, clone, source::Priority,
+};
+use gst::{ClockTime, prelude::ObjectExt};
+use gstreamer_play::{prelude::ElementExt, *};
+use gtk::{glib, prelude::*, subclass::prelude::*};
+use log::*;
+use mpris_server::PlaybackStatus;
+use ncm_api::SongInfo;
+use once_cell::sync::*;
+use std::{
+    cell::{Cell, RefCell},
+    fs,
+    path::PathBuf,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{
+    LoopsState, MprisController, PlayList, TrackTags, cached_track_number, has_local_tags,
+    is_local_song, local_song_path, read_tags,
+};
+use crate::{
+    application::{Action, HEARTBEAT_ACTIVE, HeartbeatExtendMode},
+    lyrics::Lyrics,
+    now_playing::{NowPlaying, NowPlayingWriter},
+    utils::*,
+};
+
+// 只有播放进度变化时，正在播放文件的最短写入间隔
+const NOW_PLAYING_INTERVAL: Duration = Duration::from_secs(5);
+
+// 播放核心：播放器、播放列表与 MPRIS，不依赖主窗口
+glib::wrapper! {
+    pub struct Playback(ObjectSubclass<imp::Playback>);
+}
+
+impl Playback {
+    pub fn new(sender: Sender<Action>) -> Self {
+        let playback: Playback = glib::Object::new();
+        playback.imp().sender.set(sender).unwrap();
+        playback.connect_gst_signals();
+        playback.setup_mpris();
+        playback.restore_playlist();
+        playback
+    }
+
+    fn setup_settings(&self) {
+        let settings = Settings::new(crate::APP_ID);
+        self.imp()
+            .settings
+            .set(settings)
+            .expect("Could not set `Settings`.");
+    }
+
+    pub fn settings(&self) -> &Settings {
+        self.imp().settings.get().expect("Could not get settings.")
+    }
+
+    fn load_settings(&self) {
+        let settings = self.settings();
+        let loop_state = settings.string("repeat-variant");
+        let loop_state = LoopsState::from_str(loop_state.as_str());
+
+        self.set_loops(loop_state);
+        self.set_volume(if settings.boolean("mute-start") {
+            0.0
+        } else {
+            settings.double("volume")
+        });
+
+        settings.bind("music-rate", self, "music-rate").build();
+    }
+
+    fn setup_mpris(&self) {
+        let imp = self.imp();
+        let sender = imp.sender.get().unwrap().clone();
+        crate::MAINCONTEXT.spawn_local_with_priority(Priority::LOW, async move {
+            if let Ok(mc) = MprisController::new(sender.clone()).await {
+                sender.send(Action::InitMpris(mc)).await.unwrap();
+            }
+        });
+    }
+
+    pub fn init_mpris(&self, mpris: MprisController) {
+        let imp = self.imp();
+        imp.mpris.set(Rc::new(mpris)).unwrap();
+
+        if let Some(mpris) = imp.mpris.get() {
+            let like = self.property::<bool>("like");
+            mpris.setup_signals(self);
+            self.update_mpris_tracklist();
+
+            // 如果有恢复的播放列表，同步状态到 MPRIS
+            if imp.restored.get() {
+                if let Some(song_info) = self.get_current_song() {
+                    let play_position = self.get_play_position();
+                    let index = self.track_index(&song_info);
+                    crate::MAINCONTEXT.spawn_local_with_priority(
+                        Priority::LOW,
+                        clone!(
+                            #[weak]
+                            mpris,
+                            async move {
+                                mpris.update_metadata(index, &song_info, like).await.ok();
+                                mpris
+                                    .set_playback_status(PlaybackStatus::Stopped)
+                                    .await
+                                    .ok();
+                                mpris.set_position(play_position as i64);
+                                mpris.seeked(play_position as i64).await.ok();
+                            }
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn setup_player(&self) {
+        let imp = self.imp();
+        let player = Play::new(None::<PlayVideoRenderer>);
+        let player_signal = PlaySignalAdapter::new(&player);
+        let mut config = player.config();
+        config.set_user_agent(
+            "User-Agent: Mozilla/5.0 (X11; Linux x86_64; rv:100.0) Gecko/20100101 Firefox/100.0",
+        );
+        config.set_position_update_interval(250);
+        config.set_seek_accurate(true);
+        player.set_config(config).unwrap();
+        player.set_volume(0.0);
+
+        let pipeline = player.pipeline();
+
+        let flags = pipeline.property_value("flags");
+        let flags_class = glib::FlagsClass::with_type(flags.type_()).unwrap();
+        let flags = flags_class
+            .builder_with_value(flags)
+            .unwrap()
+            .set_by_nick("download")
+            .build()
+            .unwrap();
+        pipeline.set_property_from_value("flags", &flags);
+
+        imp.player.set(player).unwrap();
+        imp.player_signal.set(player_signal).unwrap();
+    }
+
+    fn connect_gst_signals(&self) {
+        let imp = self.imp();
+        let sender_ = imp.sender.get().unwrap().clone();
+        let player = imp.player.get().unwrap();
+        let player_sig = imp.player_signal.get().unwrap();
+
+        let sender = sender_.clone();
+        // need gstplay's playbin bus
+        let bus = player.pipeline().bus().unwrap();
+        bus.connect_message(Some("element"), move |_, msg| {
+            use gst::MessageView;
+            if let MessageView::Element(ele) = msg.view() {
+                if let Some(stu) = ele.structure() {
+                    if "GstCacheDownloadComplete" == stu.name() {
+                        if let Ok(loc) = stu.get::<String>("location") {
+                            sender
+                                .send_blocking(Action::GstCacheDownloadComplete(loc))
+                                .unwrap();
+                        }
+                    }
+                }
+            }
+        });
+
+        let sender = sender_.clone();
+        let old_msec: Cell<u64> = Cell::new(0);
+        player_sig.connect_position_updated(move |_, clock| {
+            if let Some(clock) = clock {
+                // mseconds -> milliseconds
+                // useconds -> microseconds
+                let msec = clock.mseconds();
+                if old_msec.get() / 500 != msec / 500 {
+                    sender
+                        .send_blocking(Action::ScaleSeekUpdate(clock.useconds()))
+                        .unwrap();
+                    old_msec.replace(msec);
+                }
+            }
+        });
+
+        let sender = sender_.clone();
+        player_sig.connect_duration_changed(move |_, clock| {
+            if let Some(clock) = clock {
+                sender
+                    .send_blocking(Action::GstDurationChanged(clock.useconds()))
+                    .unwrap();
+            }
+        });
+
+        let sender = sender_.clone();
+        player_sig.connect_end_of_stream(move |_| {
+            sender.send_blocking(Action::PlayNextSong).unwrap();
+        });
+
+        let sender = sender_.clone();
+        player_sig.connect_error(move |_, e, _| {
+            sender
+                .send_blocking(Action::AddToast(gettext_f(
+                    "Playback error:{err}",
+                    &[("err", &e.to_string())],
+                )))
+                .unwrap();
+            sender.send_blocking(Action::PlayNextSong).unwrap();
+        });
+
+        let sender = sender_.clone();
+        player_sig.connect_state_changed(move |_, state| {
+            sender
+                .send_blocking(Action::GstStateChanged(state))
+                .unwrap();
+        });
+
+        let sender = sender_;
+        player_sig.connect_volume_changed(move |_, volume| {
+            sender
+                .send_blocking(Action::GstVolumeChanged(volume))
+                .unwrap();
+        });
+    }
+
+    // 恢复上次保存的播放列表，首次播放时重新获取链接并跳转到保存的进度
+    fn restore_playlist(&self) {
+        let imp = self.imp();
+        let Some(song_info) = self.get_current_song() else {
+            return;
+        };
+        imp.restored.set(true);
+        // 使用保存的 duration（毫秒）
+        if song_info.duration > 0 {
+            self.set_property("duration", song_info.duration / 1000);
+        }
+        imp.pending_seek_position.set(self.get_play_position());
+        self.load_cover(&song_info);
+    }
+
+    pub fn play(&self, song_info: SongInfo) {
+        let imp = self.imp();
+
+        let sender = imp.sender.get().unwrap();
+        sender
+            .send_blocking(Action::AddToast(gettext_f(
+                "Start playback [{name}] ...",
+                &[("name", &song_info.name)],
+            )))
+            .unwrap();
+
+        let player = imp.player.get().unwrap();
+        player.stop();
+        player.set_uri(Some(&song_info.song_url));
+        player.set_volume(self.property("volume"));
+        player.play();
+        imp.restored.set(false);
+
+        // 如果存在待执行的 seek 位置，在开始播放后立即定位
+        let pending = imp.pending_seek_position.get();
+        if pending > 0 {
+            imp.pending_seek_position.set(0);
+            player.seek(ClockTime::from_useconds(pending));
+        }
+
+        // 切换曲目时清空当前歌词行，等待新歌词加载
+        imp.playing_lyrics.take();
+        imp.lyric_line.take();
+        self.update_mpris_lyric(String::new());
+        self.write_now_playing(&NowPlaying::new(&song_info, true, 0));
+
+        self.load_cover(&song_info);
+        self.load_track_number(&song_info);
+        self.load_local_tags(&song_info);
+
+        let volume = self.property("volume");
+        let like = self.property::<bool>("like");
+        let index = self.track_index(&song_info);
+        if let Some(mpris) = imp.mpris.get() {
+            crate::MAINCONTEXT.spawn_local_with_priority(
+                Priority::LOW,
+                clone!(
+                    #[weak]
+                    mpris,
+                    async move {
+                        if let Err(err) = mpris.update_metadata(index, &song_info, like).await {
+                            warn!("设置 MPRIS metadata 失败: {err:?}");
+                        }
+                        if let Err(err) = mpris.set_playback_status(PlaybackStatus::Playing).await {
+                            warn!("设置 MPRIS 播放状态失败: {err:?}");
+                        }
+                        if let Err(err) = mpris.set_volume(volume).await {
+                            warn!("设置 MPRIS 音量失败: {err:?}");
+                        }
+                        mpris.set_position(0);
+                        mpris.seeked(0).await.ok();
+                    }
+                ),
+            );
+        }
+    }
+
+    // 加载歌曲封面，未缓存时先下载
+    fn load_cover(&self, song_info: &SongInfo) {
+        let path_cover = crate::path::get_cover_cache_path(song_info.album_id);
+        if path_cover.exists() {
+            self.set_property("cover", path_cover.to_string_lossy().to_string());
+            return;
+        }
+        self.set_property("cover", String::new());
+        if !song_info.pic_url.is_empty() {
+            // 下载完成后同时更新封面与 MPRIS artUrl
+            let playback = glib::SendWeakRef::from(self.downgrade());
+            let album_id = song_info.album_id;
+            let sender = self.imp().sender.get().unwrap();
+            sender
+                .send_blocking(Action::DownloadImage(
+                    song_info.pic_url.to_owned(),
+                    path_cover,
+                    140,
+                    140,
+                    Some(Arc::new(move |_| {
+                        if let Some(playback) = playback.upgrade() {
+                            playback.update_cover(album_id);
+                        }
+                    })),
+                ))
+                .unwrap();
+        }
+    }
+
+    // 封面下载完成，仍是当前歌曲时更新
+    fn update_cover(&self, album_id: u64) {
+        if self
+            .get_current_song()
+            .is_none_or(|si| si.album_id != album_id)
+        {
+            return;
+        }
+        let path_cover = crate::path::get_cover_cache_path(album_id);
+        self.set_property("cover", path_cover.to_string_lossy().to_string());
+        if let Some(mpris) = self.imp().mpris.get() {
+            crate::MAINCONTEXT.spawn_local_with_priority(
+                Priority::LOW,
+                clone!(
+                    #[weak]
+                    mpris,
+                    async move {
+                        if let Err(err) = mpris.set_cover(album_id).await {
+                            warn!("设置 MPRIS 封面失败: {err:?}");
+                        }
+                    }
+                ),
+            );
+        }
+    }
+
+    // 在线歌曲的曲目序号需从歌曲详情获取，获取后更新 MPRIS
+    fn load_track_number(&self, song_info: &SongInfo) {
+        if is_local_song(song_info)
+            || self.imp().mpris.get().is_none()
+            || cached_track_number(song_info.id).is_some()
+        {
+            return;
+        }
+        let playback = glib::SendWeakRef::from(self.downgrade());
+        let id = song_info.id;
+        let sender = self.imp().sender.get().unwrap();
+        sender
+            .send_blocking(Action::GetTrackNumber(
+                id,
+                Arc::new(move |track| {
+                    if let (Some(playback), Some(track)) = (playback.upgrade(), track) {
+                        playback.update_track_number(id, track);
+                    }
+                }),
+            ))
+            .unwrap();
+    }
+
+    // 在后台读取本地歌曲的文件标签，用于 MPRIS 元数据
+    fn load_local_tags(&self, song_info: &SongInfo) {
+        let Some(mpris) = self.imp().mpris.get().cloned() else {
+            return;
+        };
+        let Some(path) = local_song_path(song_info).filter(|_| !has_local_tags(song_info.id))
+        else {
+            return;
+        };
+        let id = song_info.id;
+        crate::MAINCONTEXT.spawn_local_with_priority(Priority::LOW, async move {
+            let tags = blocking(move || Ok(read_tags(&path).unwrap_or_default())).await;
+            // 只保留元数据需要的字段
+            let tags = TrackTags {
+                lyrics: None,
+                cover: None,
+                ..tags.unwrap_or_default()
+            };
+            if let Err(err) = mpris.set_local_tags(id, tags).await {
+                warn!("设置 MPRIS 本地歌曲标签失败: {err:?}");
+            }
+        });
+    }
+
+    fn update_track_number(&self, id: u64, track: u32) {
+        if let Some(mpris) = self.imp().mpris.get() {
+            crate::MAINCONTEXT.spawn_local_with_priority(
+                Priority::LOW,
+                clone!(
+                    #[weak]
+                    mpris,
+                    async move {
+                        if let Err(err) = mpris.set_track_number(id, track).await {
+                            warn!("设置 MPRIS 曲目序号失败: {err:?}");
+                        }
+                    }
+                ),
+            );
+        }
+    }
+
+    pub fn next_song(&self) {
+        let imp = self.imp();
+        let sender = imp.sender.get().unwrap().clone();
+        let (loops, pos_before, len) = if let Ok(playlist) = imp.playlist.lock() {
+            (
+                playlist.get_loops(),
+                playlist.get_position(),
+                playlist.len(),
+            )
+        } else {
+            (LoopsState::None, 0, 0)
+        };
+        // 心动模式 + 列表循环播到最后一首：不回绕，追加新的心动歌曲后继续播放
+        if *HEARTBEAT_ACTIVE.lock().unwrap()
+            && loops == LoopsState::Playlist
+            && len > 0
+            && pos_before + 1 == len
+        {
+            if let Some(si) = self.get_current_song() {
+                sender
+                    .send_blocking(Action::HeartbeatExtend(si, HeartbeatExtendMode::ListLoop))
+                    .unwrap();
+                return;
+            }
+        }
+        if let Ok(mut playlist) = imp.playlist.lock() {
+            if let Some(song_info) = playlist.next_song() {
+                let song_info = song_info.to_owned();
+                sender.send_blocking(Action::Play(song_info)).unwrap();
+                sender
+                    .send_blocking(Action::UpdatePlayListStatus(playlist.get_position()))
+                    .unwrap();
+                return;
+            }
+            if loops == LoopsState::None {
+                if let Some(si) = playlist.current_song().map(|s| s.to_owned()) {
+                    // 不循环模式播完：追加心动歌曲并自动续播
+                    sender
+                        .send_blocking(Action::HeartbeatExtend(si, HeartbeatExtendMode::ListEnd))
+                        .unwrap();
+                    return;
+                }
+            }
+        }
+        sender
+            .send_blocking(Action::AddToast(gettext("No more songs！")))
+            .unwrap();
+    }
+
+    pub fn prev_song(&self) {
+        let imp = self.imp();
+        let sender = imp.sender.get().unwrap().clone();
+        if let Ok(mut playlist) = imp.playlist.lock() {
+            if let Some(song_info) = playlist.prev_song() {
+                let song_info = song_info.to_owned();
+                sender.send_blocking(Action::Play(song_info)).unwrap();
+                sender
+                    .send_blocking(Action::UpdatePlayListStatus(playlist.get_position()))
+                    .unwrap();
+                return;
+            }
+        }
+        sender
+            .send_blocking(Action::AddToast(gettext("No more songs！")))
+            .unwrap();
+    }
+
+    pub fn toggle_play_pause(&self) {
+        if self.is_playing() {
+            self.switch_pause();
+        } else {
+            self.switch_play();
+        }
+    }
+
+    // 播放进度更新（微秒）
+    pub fn update_position(&self, usec: u64) {
+        let imp = self.imp();
+        let sec = usec / 10u64.pow(6);
+
+        // 定期保存播放进度（每60秒保存一次）
+        if sec > 0 && sec % 60 == 0 {
+            self.set_play_position(usec);
+            self.save_playlist();
+        }
+
+        // 仅同步位置，Seeked 信号只在用户跳转时发送
+        if let Some(mpris) = imp.mpris.get() {
+            mpris.set_position(usec as i64);
+        }
+
+        let sender = imp.sender.get().unwrap();
+        if let Some(si) = self.get_current_song() {
+            // 发送更新歌词
+            sender
+                .send_blocking(Action::UpdateLyrics(si, usec / 1000))
+                .unwrap();
+        }
+
+        // 当剩余5秒时提前获取下首歌曲的播放链接。
+        let duration: u64 = self.property("duration");
+        let secp = (usec / 10u64.pow(5)) % 10;
+        if duration - sec == 5 && secp >= 5 {
+            if let Some(si) = self.get_next_song() {
+                sender.send_blocking(Action::GetSongUrl(si)).unwrap();
+            }
+        }
+    }
+
+    // 跳转到指定位置并通知 MPRIS
+    // usec -> microseconds
+    pub fn seek(&self, usec: u64) {
+        let imp = self.imp();
+        let player = imp.player.get().unwrap();
+        let upper = player.duration().map(|d| d.useconds()).unwrap_or(0);
+        let usec = if upper > 0 { usec.min(upper) } else { usec };
+        self.gst_position_update(usec);
+        // 同步进度条与歌词
+        imp.sender
+            .get()
+            .unwrap()
+            .send_blocking(Action::ScaleSeekUpdate(usec))
+            .unwrap();
+
+        if let Some(mpris) = imp.mpris.get() {
+            crate::MAINCONTEXT.spawn_local_with_priority(
+                Priority::LOW,
+                clone!(
+                    #[weak]
+                    mpris,
+                    async move {
+                        mpris.seeked(usec as i64).await.ok();
+                    }
+                ),
+            );
+        }
+    }
+
+    // usec -> microseconds
+    pub fn gst_position_update(&self, usec: u64) {
+        let player = self.imp().player.get().unwrap();
+        player.seek(ClockTime::from_useconds(usec));
+    }
+
+    pub fn gst_duration_changed(&self, usec: u64) {
+        let imp = self.imp();
+        self.set_property("duration", usec / 10u64.pow(6));
+
+        if let Some(mpris) = imp.mpris.get() {
+            if let Some(mut si) = self.get_current_song() {
+                si.duration = usec / 1000;
+                let like = self.property::<bool>("like");
+                let index = self.track_index(&si);
+                crate::MAINCONTEXT.spawn_local_with_priority(
+                    Priority::LOW,
+                    clone!(
+                        #[weak]
+                        mpris,
+                        async move {
+                            mpris.update_metadata(index, &si, like).await.ok();
+                        }
+                    ),
+                );
+            }
+        }
+    }
+
+    pub fn gst_state_changed(&self, state: PlayState) {
+        let playing = match state {
+            PlayState::Stopped | PlayState::Paused => false,
+            PlayState::Playing => true,
+            _ => return,
+        };
+        // 同步播放状态，确保保存时反映实际播放情况
+        if let Ok(mut playlist) = self.imp().playlist.lock() {
+            playlist.set_play_state(playing);
+        }
+        self.set_property("playing", playing);
+        // 播放状态变化时立即更新正在播放文件
+        self.update_now_playing(self.get_position());
+    }
+
+    pub fn gst_volume_changed(&self, volume: f64) {
+        self.set_property("volume", volume);
+    }
+
+    pub fn gst_cache_download_complete(&self, loc: String) {
+        let duration: u64 = self.property("duration");
+        // 不缓存小于 30 秒时长的乐曲(vip试听)
+        if duration > 30 {
+            if let Some(si) = self.get_current_song().filter(|si| !is_local_song(si)) {
+                let rate = self.property::<u32>("music-rate");
+                let src = PathBuf::from(loc);
+                let dst = crate::path::get_music_cache_path(si.id, rate);
+                thread::spawn(|| {
+                    if let Err(err) = fs::copy(src, dst) {
+                        log::error!("{:?}", err);
+                    }
+                });
+            }
+        }
+    }
+
+    fn playlist_length(&self) -> usize {
+        if let Ok(playlist) = self.imp().playlist.lock() {
+            playlist.len()
+        } else {
+            0
+        }
+    }
+
+    pub fn get_list(&self) -> Vec<SongInfo> {
+        if let Ok(playlist) = self.imp().playlist.lock() {
+            playlist.get_list()
+        } else {
+            vec![]
+        }
+    }
+
+    pub fn add_song(&self, song: SongInfo) {
+        if let Ok(mut playlist) = self.imp().playlist.lock() {
+            playlist.add_song(song);
+        }
+        // 用户选择了新歌曲，清除恢复时保存的播放进度
+        self.imp().pending_seek_position.set(0);
+        self.save_playlist();
+        self.update_mpris_tracklist();
+    }
+
+    pub fn remove_song(&self, song: SongInfo) {
+        if let Some(songinfo) = self.get_current_song() {
+            if songinfo.id == song.id {
+                if self.playlist_length() > 1 {
+                    self.next_song();
+                } else {
+                    self.switch_stop();
+                }
+            }
+            if let Ok(mut playlist) = self.imp().playlist.lock() {
+                playlist.remove_song(song);
+                let sender = self.imp().sender.get().unwrap().clone();
+                if playlist.len() >= 1 {
+                    sender
+                        .send_blocking(Action::UpdatePlayListStatus(playlist.get_position()))
+                        .unwrap();
+                }
+            }
+            self.save_playlist();
+            self.update_mpris_tracklist();
+        }
+    }
+
+    pub fn add_list(&self, list: Vec<SongInfo>) {
+        let settings = self.settings();
+        let not_ignore_grey = settings.get("not-ignore-grey");
+        let list: Vec<SongInfo> = if not_ignore_grey {
+            list
+        } else {
+            list.into_iter()
+                .filter(|si| si.copyright.playable())
+                .collect()
+        };
+
+        if let Ok(mut playlist) = self.imp().playlist.lock() {
+            playlist.add_list(list);
+        }
+        // 用户选择了新歌单，清除恢复时保存的播放进度
+        self.imp().pending_seek_position.set(0);
+        self.save_playlist();
+        self.update_mpris_tracklist();
+    }
+
+    // 追加歌曲到播放列表末尾（过滤灰歌，去重），返回实际追加的歌曲
+    pub fn append_list(&self, list: &mut Vec<SongInfo>) -> Vec<SongInfo> {
+        let not_ignore_grey: bool = self.settings().get("not-ignore-grey");
+        if !not_ignore_grey {
+            list.retain(|si| si.copyright.playable());
+        }
+        let appending = std::mem::take(list);
+        let appended = if let Ok(mut playlist) = self.imp().playlist.lock() {
+            playlist.append_list(appending)
+        } else {
+            vec![]
+        };
+        self.save_playlist();
+        self.update_mpris_tracklist();
+        appended
+    }
+
+    // 根据 song_id 定位播放位置（心动模式追加续播前使用）
+    pub fn sync_playlist_position(&self, song_id: u64) {
+        if let Ok(mut playlist) = self.imp().playlist.lock() {
+            playlist.sync_position_with_song_id(song_id);
+        }
+    }
+
+    pub fn playlist_position(&self) -> usize {
+        if let Ok(playlist) = self.imp().playlist.lock() {
+            playlist.get_position()
+        } else {
+            0
+        }
+    }
+
+    // 从头播放列表第一首（心动模式列表循环追加失败时的回退）
+    pub fn playlist_first_restart(&self) -> Option<SongInfo> {
+        let first = if let Ok(playlist) = self.imp().playlist.lock() {
+            playlist.get_list().first().cloned()
+        } else {
+            None
+        };
+        if let Some(first) = first {
+            self.sync_playlist_position(first.id);
+            Some(first)
+        } else {
+            None
+        }
+    }
+
+    pub fn set_song_url(&self, si: SongInfo) {
+        if let Ok(mut playlist) = self.imp().playlist.lock() {
+            playlist.set_song_url(si);
+        }
+    }
+
+    pub fn save_playlist(&self) {
+        if let Ok(playlist) = self.imp().playlist.lock() {
+            playlist.save_to_file();
+        }
+    }
+
+    // 保存当前播放状态（包括播放进度）
+    pub fn save_current_state(&self) {
+        // 先更新播放进度（如果 player 可用）
+        if let Some(player) = self.imp().player.get() {
+            if let Some(pos) = player.position() {
+                self.set_play_position(pos.useconds());
+            }
+        }
+        self.save_playlist();
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.property("playing")
+    }
+
+    // 将播放列表同步到 MPRIS TrackList
+    pub fn update_mpris_tracklist(&self) {
+        if let Some(mpris) = self.imp().mpris.get() {
+            let songs = self.get_list();
+            let current = self.get_current_song().map(|si| self.track_index(&si));
+            crate::MAINCONTEXT.spawn_local_with_priority(
+                Priority::LOW,
+                clone!(
+                    #[weak]
+                    mpris,
+                    async move {
+                        if let Err(err) = mpris.update_tracklist(songs, current).await {
+                            warn!("更新 MPRIS TrackList 失败: {err:?}");
+                        }
+                    }
+                ),
+            );
+        }
+    }
+
+    // 将当前歌词行发布到 MPRIS
+    fn update_mpris_lyric(&self, line: String) {
+        if let Some(mpris) = self.imp().mpris.get() {
+            crate::MAINCONTEXT.spawn_local_with_priority(
+                Priority::DEFAULT_IDLE,
+                clone!(
+                    #[weak]
+                    mpris,
+                    async move {
+                        mpris.set_lyric(&line).await.ok();
+                    }
+                ),
+            );
+        }
+    }
+
+    // 更新正在播放歌曲的歌词
+    pub fn set_lyrics(&self, si: &SongInfo, lrc: &Lyrics) {
+        let imp = self.imp();
+        if self.get_current_song().is_some_and(|s| s.id == si.id) {
+            imp.playing_lyrics.replace(lrc.clone());
+            imp.lyric_line.take();
+        }
+    }
+
+    // 当前歌曲、播放进度（毫秒）与歌词行
+    fn now_playing_at(&self, time: u64) -> Option<NowPlaying> {
+        let si = self.get_current_song()?;
+        let (lyric, translation) = match self.imp().playing_lyrics.borrow().line_at(time) {
+            Some((line, translation)) => (
+                line.to_owned(),
+                translation.map(|t| t.to_owned()).unwrap_or_default(),
+            ),
+            None => Default::default(),
+        };
+        let volume = self.property::<f64>("volume");
+        Some(NowPlaying {
+            liked: self.property("like"),
+            volume: (volume * 100.0).round() as u32,
+            lyric,
+            translation,
+            ..NowPlaying::new(&si, self.is_playing(), time)
+        })
+    }
+
+    pub fn now_playing(&self) -> Option<NowPlaying> {
+        self.now_playing_at(self.get_position())
+    }
+
+    // 发布当前歌词行到 MPRIS 及正在播放文件
+    pub fn update_now_playing(&self, time: u64) -> Option<NowPlaying> {
+        let imp = self.imp();
+        let now_playing = self.now_playing_at(time)?;
+        if *imp.lyric_line.borrow() != now_playing.lyric {
+            imp.lyric_line.replace(now_playing.lyric.clone());
+            self.update_mpris_lyric(now_playing.lyric.clone());
+        }
+        self.write_now_playing(&now_playing);
+        Some(now_playing)
+    }
+
+    fn write_now_playing(&self, now_playing: &NowPlaying) {
+        let path = self.settings().string("now-playing-file");
+        if path.is_empty() {
+            return;
+        }
+        let path = PathBuf::from(path.as_str());
+        let content = now_playing.format(&path);
+        let imp = self.imp();
+        if let Some((last, text, time)) = &*imp.now_playing_written.borrow() {
+            // 内容未变化时不重复写入，只有进度变化时限制写入频率
+            let only_position = NowPlaying {
+                position: last.position,
+                ..now_playing.clone()
+            } == *last;
+            if *text == content || (only_position && time.elapsed() < NOW_PLAYING_INTERVAL) {
+                return;
+            }
+        }
+        imp.now_playing_writer
+            .get_or_init(NowPlayingWriter::spawn)
+            .write(path, content.clone());
+        imp.now_playing_written
+            .replace(Some((now_playing.clone(), content, Instant::now())));
+    }
+
+    // 当前播放位置（毫秒）
+    pub fn get_position(&self) -> u64 {
+        self.imp()
+            .player
+            .get()
+            .and_then(|player| player.position())
+            .map(|pos| pos.mseconds())
+            .unwrap_or(0)
+    }
+
+    // 进度条显示的位置（微秒），恢复的播放列表尚未播放时为保存的进度
+    pub fn progress(&self) -> u64 {
+        let imp = self.imp();
+        if imp.restored.get() {
+            imp.pending_seek_position.get()
+        } else {
+            self.get_position() * 1000
+        }
+    }
+
+    pub fn set_play_position(&self, position: u64) {
+        if let Ok(mut playlist) = self.imp().playlist.lock() {
+            playlist.set_play_position(position);
+        }
+    }
+
+    // 下次开始播放后定位到 usec（微秒），用于从分享链接的播放位置开始
+    pub fn seek_on_start(&self, usec: u64) {
+        self.imp().pending_seek_position.set(usec);
+    }
+
+    pub fn get_play_position(&self) -> u64 {
+        if let Ok(playlist) = self.imp().playlist.lock() {
+            playlist.get_play_position()
+        } else {
+            0
+        }
+    }
+
+    pub fn get_next_song(&self) -> Option<SongInfo> {
+        if let Ok(mut playlist) = self.imp().playlist.lock() {
+            return playlist.get_next_song().map(|s| s.to_owned());
+        }
+        None
+    }
+
+    // 歌曲在播放列表中的位置，用于生成 MPRIS 曲目 ID
+    fn track_index(&self, si: &SongInfo) -> usize {
+        self.imp()
+            .playlist
+            .lock()
+            .ok()
+            .and_then(|playlist| playlist.song_index(si.id))
+            .unwrap_or(0)
+    }
+
+    pub fn get_current_song(&self) -> Option<SongInfo> {
+        if let Ok(playlist) = self.imp().playlist.lock() {
+            return playlist.current_song().map(|s| s.to_owned());
+        }
+        None
+    }
+
+    // 尝试播放恢复的歌曲（URL已过期，需要重新获取）
+    // 返回 true 表示已处理恢复情况，调用方应直接返回
+    fn try_play_restored_song(&self) -> bool {
+        let imp = self.imp();
+        if imp.restored.replace(false) {
+            if let Some(sender) = imp.sender.get() {
+                if let Some(song_info) = self.get_current_song() {
+                    sender.send_blocking(Action::Play(song_info)).unwrap();
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn switch_play(&self) {
+        let imp = self.imp();
+
+        // 从文件恢复的播放列表，URL已过期，需要重新获取
+        if self.try_play_restored_song() {
+            return;
+        }
+
+        let player = imp.player.get().unwrap();
+        player.play();
+
+        if let Some(mpris) = imp.mpris.get() {
+            crate::MAINCONTEXT.spawn_local_with_priority(
+                Priority::LOW,
+                clone!(
+                    #[weak]
+                    mpris,
+                    async move {
+                        if let Err(err) = mpris.set_playback_status(PlaybackStatus::Playing).await {
+                            warn!("设置 MPRIS 播放状态失败: {err:?}");
+                        }
+                    }
+                ),
+            );
+        }
+    }
+
+    pub fn switch_pause(&self) {
+        let imp = self.imp();
+        let player = imp.player.get().unwrap();
+        player.pause();
+
+        if let Some(mpris) = imp.mpris.get() {
+            crate::MAINCONTEXT.spawn_local_with_priority(
+                Priority::LOW,
+                clone!(
+                    #[weak]
+                    mpris,
+                    async move {
+                        if let Err(err) = mpris.set_playback_status(PlaybackStatus::Paused).await {
+                            warn!("设置 MPRIS 播放状态失败: {err:?}");
+                        }
+                    }
+                ),
+            );
+        }
+    }
+
+    pub fn switch_stop(&self) {
+        let imp = self.imp();
+        let player = imp.player.get().unwrap();
+        player.stop();
+
+        if let Some(mpris) = imp.mpris.get() {
+            crate::MAINCONTEXT.spawn_local_with_priority(
+                Priority::LOW,
+                clone!(
+                    #[weak]
+                    mpris,
+                    async move {
+                        if let Err(err) = mpris.set_playback_status(PlaybackStatus::Stopped).await {
+                            warn!("设置 MPRIS 播放状态失败: {err:?}");
+                        }
+                    }
+                ),
+            );
+        }
+    }
+
+    // these set funcs will be called from mpris
+    pub fn set_loops(&self, state: LoopsState) {
+        if self.property::<LoopsState>("loops") != state {
+            self.set_property("loops", state);
+        }
+    }
+
+    pub fn set_shuffle(&self, shuffle: bool) {
+        let imp = self.imp();
+        match shuffle {
+            true => self.set_loops(LoopsState::Shuffle),
+            false => {
+                if let Ok(status) = imp.mpris.get().unwrap().get_loop_status() {
+                    self.set_loops(status);
+                };
+            }
+        }
+    }
+
+    pub fn set_volume(&self, value: f64) {
+        let old: f64 = self.property("volume");
+        if (old * 100.0).round() as i64 != (value * 100.0).round() as i64 {
+            self.set_property("volume", value);
+            let player = self.imp().player.get().unwrap();
+            player.set_volume(value);
+            let Some(sender) = self.imp().sender.get().cloned() else {
+                // if sender is not ready, just return
+                return;
+            };
+            self.imp().debounce.debounce(1, move || {
+                let _ = sender.send_blocking(Action::PersistVolume(value));
+            });
+        }
+    }
+
+    pub fn persist_volume(&self, value: f64) {
+        self.settings().set_double("volume", value).unwrap();
+    }
+
+    fn setup_notify_connect(&self) {
+        self.connect_notify(None, move |s, p| {
+            s.property_changed(p.name(), p);
+        });
+    }
+
+    fn property_changed(&self, name: &str, _: &ParamSpec) {
+        let imp = self.imp();
+        match name {
+            "volume" => {
+                let value = self.property::<f64>("volume");
+                if let Some(mpris) = imp.mpris.get() {
+                    crate::MAINCONTEXT.spawn_local_with_priority(
+                        Priority::LOW,
+                        clone!(
+                            #[weak]
+                            mpris,
+                            async move {
+                                if let Err(err) = mpris.set_volume(value).await {
+                                    warn!("设置 MPRIS 音量失败: {err:?}");
+                                }
+                            }
+                        ),
+                    );
+                }
+            }
+            "loops" => {
+                let value = self.property::<LoopsState>("loops");
+                if let Some(mpris) = imp.mpris.get() {
+                    crate::MAINCONTEXT.spawn_local_with_priority(
+                        Priority::LOW,
+                        clone!(
+                            #[weak]
+                            mpris,
+                            async move {
+                                if let Err(err) = mpris.set_loop_status(value).await {
+                                    warn!("设置 MPRIS 循环状态失败: {err:?}");
+                                }
+                            }
+                        ),
+                    );
+                }
+
+                if let Ok(mut playlist) = imp.playlist.lock() {
+                    playlist.set_loops(value);
+                }
+
+                if let Some(sender) = imp.sender.get() {
+                    sender
+                        .send_blocking(Action::UpdateTrayLoops(value))
+                        .unwrap();
+                }
+
+                self.settings()
+                    .set_string("repeat-variant", value.to_string().as_str())
+                    .unwrap();
+            }
+            "like" => {
+                let like = self.property::<bool>("like");
+                if let Some(sender) = imp.sender.get() {
+                    sender.send_blocking(Action::UpdateTrayLike(like)).unwrap();
+                }
+                if let Some(mpris) = imp.mpris.get() {
+                    crate::MAINCONTEXT.spawn_local_with_priority(
+                        Priority::LOW,
+                        clone!(
+                            #[weak]
+                            mpris,
+                            async move {
+                                if let Err(err) = mpris.set_user_rating(like).await {
+                                    warn!("设置 MPRIS 评分失败: {err:?}");
+                                }
+                            }
+                        ),
+                    );
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+mod imp {
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct Playback {
+        pub settings: OnceCell<Settings>,
+        pub sender: OnceCell<Sender<Action>>,
+        pub player: OnceCell<gstreamer_play::Play>,
+        pub player_signal: OnceCell<gstreamer_play::PlaySignalAdapter>,
+        pub playlist: Arc<Mutex<PlayList>>,
+        pub mpris: OnceCell<Rc<MprisController>>,
+        pub debounce: Debounce,
+
+        // 正在播放歌曲的歌词，用于发布当前歌词行
+        pub playing_lyrics: RefCell<Lyrics>,
+        pub lyric_line: RefCell<String>,
+        // 上次写入正在播放文件的信息、内容及时间
+        pub now_playing_written: RefCell<Option<(NowPlaying, String, Instant)>>,
+        pub now_playing_writer: OnceCell<NowPlayingWriter>,
+
+        volume: Cell<f64>,
+        loops: Cell<LoopsState>,
+        music_rate: Cell<u32>,
+        duration: Cell<u64>,
+        like: Cell<bool>,
+        playing: Cell<bool>,
+        // 当前歌曲封面的本地路径，未缓存时为空
+        cover: RefCell<String>,
+
+        // 是否从文件恢复播放列表（URL可能已过期），恢复后首次播放需重新获取URL
+        pub restored: Cell<bool>,
+
+        // 恢复时保存的播放位置（微秒），等播放开始后再执行 seek
+        pub pending_seek_position: Cell<u64>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Playback {
+        const NAME: &'static str = "Playback";
+        type Type = super::Playback;
+    }
+
+    impl ObjectImpl for Playback {
+        fn constructed(&self) {
+            let obj = self.obj();
+            self.parent_constructed();
+            // 尝试从文件加载保存的播放列表（若无文件则创建空列表）
+            *self.playlist.lock().unwrap() = PlayList::load_from_file();
+
+            obj.setup_player();
+            obj.setup_settings();
+
+            obj.setup_notify_connect();
+
+            obj.load_settings();
+        }
+
+        fn properties() -> &'static [ParamSpec] {
+            static PROPERTIES: Lazy<Vec<ParamSpec>> = Lazy::new(|| {
+                vec![
+                    ParamSpecDouble::builder("volume").build(),
+                    ParamSpecEnum::builder::<LoopsState>("loops").build(),
+                    ParamSpecUInt::builder("music-rate").build(),
+                    ParamSpecUInt64::builder("duration").build(),
+                    ParamSpecBoolean::builder("like").readwrite().build(),
+                    ParamSpecBoolean::builder("playing").build(),
+                    ParamSpecString::builder("cover").build(),
+                ]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &Value, pspec: &ParamSpec) {
+            match pspec.name() {
+                "volume" => {
+                    let input_number = value.get().expect("The value needs to be of type `f64`.");
+                    self.volume.replace(input_number);
+                }
+                "loops" => {
+                    let val = value.get().unwrap();
+                    self.loops.replace(val);
+                }
+                "music-rate" => {
+                    let val = value.get().unwrap();
+                    self.music_rate.replace(val);
+                }
+                "duration" => {
+                    let val = value.get().unwrap();
+                    self.duration.replace(val);
+                }
+                "like" => {
+                    let like = value.get().expect("The value needs to be of type `bool`.");
+                    self.like.replace(like);
+                }
+                "playing" => {
+                    let playing = value.get().expect("The value needs to be of type `bool`.");
+                    self.playing.replace(playing);
+                }
+                "cover" => {
+                    let cover = value
+                        .get()
+                        .expect("The value needs to be of type `String`.");
+                    self.cover.replace(cover);
+                }
+                n => unimplemented!("{}", n),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &ParamSpec) -> Value {
+            match pspec.name() {
+                "volume" => self.volume.get().to_value(),
+                "loops" => self.loops.get().to_value(),
+                "music-rate" => self.music_rate.get().to_value(),
+                "duration" => self.duration.get().to_value(),
+                "like" => self.like.get().to_value(),
+                "playing" => self.playing.get().to_value(),
+                "cover" => self.cover.borrow().to_value(),
+                n => unimplemented!("{}", n),
+            }
+        }
+
+        fn dispose(&self) {
+            let obj = self.obj();
+            obj.settings()
+                .set_double("volume", obj.property("volume"))
+                .unwrap();
+            // 程序退出时保存当前播放状态（包括播放进度）
+            obj.save_current_state();
+        }
+    }
+}
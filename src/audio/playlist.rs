@@ -13,7 +13,7 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use crate::path::CONFIG;
+use crate::{audio::is_local_song, path::CONFIG};
 
 // 播放列表持久化数据
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,12 +104,16 @@ impl PlayList {
         match fs::read_to_string(&path) {
             Ok(json) => match serde_json::from_str::<PlayListData>(&json) {
                 Ok(mut data) => {
-                    // 清除保存的URL（会在播放时重新获取）
+                    // 清除保存的URL（会在播放时重新获取），本地歌曲保留文件路径
                     for song in &mut data.list {
-                        song.song_url.clear();
+                        if !is_local_song(song) {
+                            song.song_url.clear();
+                        }
                     }
                     for song in &mut data.shuffle {
-                        song.song_url.clear();
+                        if !is_local_song(song) {
+                            song.song_url.clear();
+                        }
                     }
                     let loops = LoopsState::from_str(&data.loops);
                     let mut shuffle = data.shuffle;
@@ -360,6 +364,15 @@ impl PlayList {
         self.position
     }
 
+    // 歌曲在列表中的位置，当前歌曲优先返回当前播放位置
+    pub fn song_index(&self, id: u64) -> Option<usize> {
+        let position = self.get_position();
+        if self.list.get(position).is_some_and(|s| s.id == id) {
+            return Some(position);
+        }
+        self.list.iter().position(|s| s.id == id)
+    }
+
     pub fn set_position(&mut self, position: usize) {
         self.position = position;
     }
@@ -0,0 +1,544 @@
+//
+// tagger.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use anyhow::Result;
+use log::*;
+use std::{
+    fs,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+// 写入 NetEase 歌曲 ID 使用的自定义字段名
+const NETEASE_ID_KEY: &str = "NETEASE_ID";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    Mp3,
+    Flac,
+    // 以下格式只用于识别扩展名，不支持读写标签
+    M4a,
+    Ogg,
+    Wav,
+    Unknown,
+}
+
+impl AudioFormat {
+    // 根据文件头识别音频格式
+    pub fn from_bytes(data: &[u8]) -> Self {
+        if data.starts_with(b"fLaC") {
+            AudioFormat::Flac
+        } else if data.starts_with(b"ID3")
+            || (data.len() >= 2 && data[0] == 0xFF && data[1] & 0xE0 == 0xE0)
+        {
+            AudioFormat::Mp3
+        } else if data.len() >= 8 && &data[4..8] == b"ftyp" {
+            AudioFormat::M4a
+        } else if data.starts_with(b"OggS") {
+            AudioFormat::Ogg
+        } else if data.starts_with(b"RIFF") && data.len() >= 12 && &data[8..12] == b"WAVE" {
+            AudioFormat::Wav
+        } else {
+            AudioFormat::Unknown
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Self {
+        let mut head = [0u8; 12];
+        match fs::File::open(path).and_then(|mut f| f.read(&mut head)) {
+            Ok(len) => Self::from_bytes(&head[..len]),
+            Err(_) => AudioFormat::Unknown,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Flac => "flac",
+            AudioFormat::M4a => "m4a",
+            AudioFormat::Ogg => "ogg",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Unknown => "bin",
+        }
+    }
+
+    // 是否支持读写标签
+    pub fn taggable(&self) -> bool {
+        matches!(self, AudioFormat::Mp3 | AudioFormat::Flac)
+    }
+}
+
+// 需要写入音频文件的标签信息
+#[derive(Debug, Clone, Default)]
+pub struct TrackTags {
+    pub title: String,
+    pub artists: Vec<String>,
+    pub album: String,
+    // 专辑歌手，为 None 时不写入
+    pub album_artist: Option<String>,
+    pub track: Option<u32>,
+    pub netease_id: u64,
+    // 封面图片数据（jpeg/png）
+    pub cover: Option<Vec<u8>>,
+    // LRC 格式歌词
+    pub lyrics: Option<String>,
+    // 时长（毫秒），仅在读取标签时使用
+    pub duration: Option<u64>,
+}
+
+impl TrackTags {
+    // SongInfo 不含曲目序号，需另行从歌曲详情获取
+    pub fn from_song_info(si: &ncm_api::SongInfo, track: Option<u32>) -> Self {
+        Self {
+            title: si.name.to_owned(),
+            artists: split_artists(&si.singer),
+            album: si.album.to_owned(),
+            album_artist: None,
+            track,
+            netease_id: si.id,
+            cover: None,
+            lyrics: None,
+            duration: None,
+        }
+    }
+}
+
+// 拆分多歌手字符串，如 "A/B"、"A、B"
+pub fn split_artists(singer: &str) -> Vec<String> {
+    singer
+        .split(['/', '、'])
+        .map(|s| s.trim().to_owned())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn image_mime_type(data: &[u8]) -> &'static str {
+    if data.starts_with(&[0x89, b'P', b'N', b'G']) {
+        "image/png"
+    } else {
+        "image/jpeg"
+    }
+}
+
+// 根据文件格式写入标签，MP3 使用 ID3v2.4，FLAC 使用 Vorbis comments
+pub fn write_tags(path: &Path, tags: &TrackTags) -> Result<()> {
+    match AudioFormat::from_path(path) {
+        AudioFormat::Mp3 => write_id3(path, tags),
+        AudioFormat::Flac => write_flac(path, tags),
+        _ => {
+            warn!("不支持写入标签的文件格式: {:?}", path);
+            anyhow::bail!("Unsupported audio format!")
+        }
+    }
+}
+
+// 读取音频文件标签，不支持的格式返回 None
+pub fn read_tags(path: &Path) -> Option<TrackTags> {
+    let res = match AudioFormat::from_path(path) {
+        AudioFormat::Mp3 => read_id3(path),
+        AudioFormat::Flac => read_flac(path),
+        _ => return None,
+    };
+    res.map_err(|err| debug!("读取标签失败: {:?} {:?}", path, err)).ok()
+}
+
+// MPEG 帧头中与时长相关的信息
+struct FrameHeader {
+    // kbit/s
+    bitrate: u64,
+    sample_rate: u64,
+    // 每帧采样数
+    samples: u64,
+    // 帧头后的边信息长度，其后可能是 Xing/Info 头
+    side_info: usize,
+}
+
+impl FrameHeader {
+    // 只支持 Layer III
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 4 || data[0] != 0xFF || data[1] & 0xE0 != 0xE0 {
+            return None;
+        }
+        let version = (data[1] >> 3) & 0x03;
+        let layer = (data[1] >> 1) & 0x03;
+        let bitrate_index = (data[2] >> 4) as usize;
+        let rate_index = ((data[2] >> 2) & 0x03) as usize;
+        if version == 1 || layer != 1 || !(1..15).contains(&bitrate_index) || rate_index == 3 {
+            return None;
+        }
+        let mono = data[3] >> 6 == 3;
+        let mpeg1 = version == 3;
+        let bitrates: [u64; 15] = if mpeg1 {
+            [
+                0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+            ]
+        } else {
+            [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160]
+        };
+        let sample_rates: [u64; 3] = match version {
+            3 => [44100, 48000, 32000],
+            2 => [22050, 24000, 16000],
+            _ => [11025, 12000, 8000],
+        };
+        Some(Self {
+            bitrate: bitrates[bitrate_index],
+            sample_rate: sample_rates[rate_index],
+            samples: if mpeg1 { 1152 } else { 576 },
+            side_info: match (mpeg1, mono) {
+                (true, false) => 32,
+                (true, true) | (false, false) => 17,
+                (false, true) => 9,
+            },
+        })
+    }
+}
+
+// 没有 TLEN 标签时从帧头计算 MP3 时长（毫秒），VBR 文件使用 Xing/Info/VBRI 头中的帧数
+pub fn mp3_duration(path: &Path) -> Option<u64> {
+    if AudioFormat::from_path(path) != AudioFormat::Mp3 {
+        return None;
+    }
+    let mut file = fs::File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    // 跳过 ID3v2 标签
+    let mut head = [0u8; 10];
+    file.read_exact(&mut head).ok()?;
+    let mut offset = 0;
+    if head.starts_with(b"ID3") {
+        let size = head[6..10]
+            .iter()
+            .fold(0, |size, b| (size << 7) | u64::from(b & 0x7F));
+        offset = 10 + size + if head[5] & 0x10 != 0 { 10 } else { 0 };
+    }
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut data = vec![0; 16 * 1024];
+    let read = file.read(&mut data).ok()?;
+    data.truncate(read);
+    mpeg_duration(&data, len.saturating_sub(offset))
+}
+
+// data 为音频数据开头，audio_len 为音频数据总长度
+fn mpeg_duration(data: &[u8], audio_len: u64) -> Option<u64> {
+    let start = (0..data.len()).find(|&i| FrameHeader::parse(&data[i..]).is_some())?;
+    let frame = FrameHeader::parse(&data[start..])?;
+    let read_u32 = |pos: usize| -> Option<u64> {
+        let bytes = data.get(pos..pos + 4)?;
+        Some(u64::from(u32::from_be_bytes([
+            bytes[0], bytes[1], bytes[2], bytes[3],
+        ])))
+    };
+    let xing = start + 4 + frame.side_info;
+    let vbri = start + 4 + 32;
+    let frames = match data.get(xing..xing + 4) {
+        Some(b"Xing") | Some(b"Info") if read_u32(xing + 4)? & 0x01 != 0 => read_u32(xing + 8),
+        _ if data.get(vbri..vbri + 4) == Some(&b"VBRI"[..]) => read_u32(vbri + 14),
+        _ => None,
+    };
+    match frames {
+        Some(frames) => Some(frames * frame.samples * 1000 / frame.sample_rate),
+        // 固定码率：字节数 * 8 / (kbit/s) 即为毫秒数
+        None => Some(audio_len.saturating_sub(start as u64) * 8 / frame.bitrate),
+    }
+}
+
+fn read_id3(path: &Path) -> Result<TrackTags> {
+    use id3::{Tag, TagLike};
+
+    let tag = Tag::read_from_path(path)?;
+    let netease_id = tag
+        .extended_texts()
+        .find(|t| t.description == NETEASE_ID_KEY)
+        .and_then(|t| t.value.parse().ok())
+        .unwrap_or(0);
+    Ok(TrackTags {
+        title: tag.title().unwrap_or_default().to_owned(),
+        artists: tag
+            .artists()
+            .map(|a| a.into_iter().flat_map(split_artists).collect())
+            .unwrap_or_default(),
+        album: tag.album().unwrap_or_default().to_owned(),
+        album_artist: tag
+            .album_artist()
+            .filter(|a| !a.is_empty())
+            .map(str::to_owned),
+        track: tag.track(),
+        netease_id,
+        cover: None,
+        lyrics: tag.lyrics().next().map(|l| l.text.to_owned()),
+        duration: tag.duration().map(u64::from),
+    })
+}
+
+fn read_flac(path: &Path) -> Result<TrackTags> {
+    use metaflac::Tag;
+
+    let tag = Tag::read_from_path(path)?;
+    let duration = tag.get_streaminfo().and_then(|info| {
+        (info.sample_rate > 0).then(|| info.total_samples * 1000 / info.sample_rate as u64)
+    });
+    let mut tags = TrackTags {
+        duration,
+        ..TrackTags::default()
+    };
+    if let Some(comments) = tag.vorbis_comments() {
+        tags.title = comments
+            .title()
+            .and_then(|t| t.first())
+            .cloned()
+            .unwrap_or_default();
+        tags.artists = comments
+            .artist()
+            .map(|a| a.iter().flat_map(|a| split_artists(a)).collect())
+            .unwrap_or_default();
+        tags.album = comments
+            .album()
+            .and_then(|a| a.first())
+            .cloned()
+            .unwrap_or_default();
+        tags.album_artist = comments
+            .album_artist()
+            .and_then(|a| a.first())
+            .filter(|a| !a.is_empty())
+            .cloned();
+        tags.track = comments.track();
+        tags.netease_id = comments
+            .get(NETEASE_ID_KEY)
+            .and_then(|v| v.first())
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        tags.lyrics = comments.lyrics().and_then(|l| l.first()).cloned();
+    }
+    Ok(tags)
+}
+
+fn write_id3(path: &Path, tags: &TrackTags) -> Result<()> {
+    use id3::{
+        Tag, TagLike, Version,
+        frame::{ExtendedText, Lyrics, Picture, PictureType},
+    };
+
+    let mut tag = Tag::read_from_path(path).unwrap_or_else(|_| Tag::new());
+    tag.set_title(&tags.title);
+    // ID3v2.4 多值字段使用 \0 分隔
+    tag.set_artist(tags.artists.join("\0"));
+    tag.set_album(&tags.album);
+    if let Some(album_artist) = &tags.album_artist {
+        tag.set_album_artist(album_artist);
+    }
+    if let Some(track) = tags.track {
+        tag.set_track(track);
+    }
+    tag.remove_extended_text(Some(NETEASE_ID_KEY), None);
+    tag.add_frame(ExtendedText {
+        description: NETEASE_ID_KEY.to_owned(),
+        value: tags.netease_id.to_string(),
+    });
+    if let Some(cover) = &tags.cover {
+        tag.remove_picture_by_type(PictureType::CoverFront);
+        tag.add_frame(Picture {
+            mime_type: image_mime_type(cover).to_owned(),
+            picture_type: PictureType::CoverFront,
+            description: String::new(),
+            data: cover.to_owned(),
+        });
+    }
+    if let Some(lyrics) = &tags.lyrics {
+        tag.remove_all_lyrics();
+        tag.add_frame(Lyrics {
+            lang: "und".to_owned(),
+            description: String::new(),
+            text: lyrics.to_owned(),
+        });
+    }
+    tag.write_to_path(path, Version::Id3v24)?;
+    Ok(())
+}
+
+fn write_flac(path: &Path, tags: &TrackTags) -> Result<()> {
+    use metaflac::{Tag, block::PictureType};
+
+    let mut tag = Tag::read_from_path(path)?;
+    {
+        let comments = tag.vorbis_comments_mut();
+        comments.set_title(vec![tags.title.to_owned()]);
+        comments.set_artist(tags.artists.to_owned());
+        comments.set_album(vec![tags.album.to_owned()]);
+        if let Some(album_artist) = &tags.album_artist {
+            comments.set_album_artist(vec![album_artist.to_owned()]);
+        }
+        if let Some(track) = tags.track {
+            comments.set_track(track);
+        }
+        comments.set(NETEASE_ID_KEY, vec![tags.netease_id.to_string()]);
+        if let Some(lyrics) = &tags.lyrics {
+            comments.set_lyrics(vec![lyrics.to_owned()]);
+        }
+    }
+    if let Some(cover) = &tags.cover {
+        tag.remove_picture_type(PictureType::CoverFront);
+        tag.add_picture(image_mime_type(cover), PictureType::CoverFront, cover.to_owned());
+    }
+    tag.save()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // MPEG1 Layer III, 128 kbit/s, 44100 Hz, 立体声
+    const HEADER: [u8; 4] = [0xFF, 0xFB, 0x90, 0x00];
+
+    #[test]
+    fn frame_header() {
+        let frame = FrameHeader::parse(&HEADER).unwrap();
+        assert_eq!(frame.bitrate, 128);
+        assert_eq!(frame.sample_rate, 44100);
+        assert_eq!(frame.samples, 1152);
+        assert_eq!(frame.side_info, 32);
+
+        // MPEG2 单声道
+        let frame = FrameHeader::parse(&[0xFF, 0xF3, 0x40, 0xC0]).unwrap();
+        assert_eq!((frame.bitrate, frame.sample_rate), (32, 22050));
+        assert_eq!((frame.samples, frame.side_info), (576, 9));
+
+        // Layer I、保留的码率及采样率
+        assert!(FrameHeader::parse(&[0xFF, 0xFF, 0x90, 0x00]).is_none());
+        assert!(FrameHeader::parse(&[0xFF, 0xFB, 0xF0, 0x00]).is_none());
+        assert!(FrameHeader::parse(&[0xFF, 0xFB, 0x9C, 0x00]).is_none());
+        assert!(FrameHeader::parse(b"fLaC").is_none());
+    }
+
+    #[test]
+    fn cbr_duration() {
+        let mut data = vec![0; 3];
+        data.extend(HEADER);
+        data.resize(1024, 0);
+        // 128 kbit/s 下 16000 字节为 1 秒
+        assert_eq!(mpeg_duration(&data, 16_003), Some(1000));
+        assert_eq!(mpeg_duration(&[0; 64], 16_000), None);
+    }
+
+    #[test]
+    fn vbr_duration() {
+        let mut data = HEADER.to_vec();
+        data.resize(4 + 32, 0);
+        data.extend(b"Xing");
+        data.extend(1u32.to_be_bytes());
+        // 1000 帧 * 1152 / 44100
+        data.extend(1000u32.to_be_bytes());
+        data.resize(1024, 0);
+        assert_eq!(mpeg_duration(&data, 1_000_000), Some(26_122));
+
+        let mut data = HEADER.to_vec();
+        data.resize(4 + 32, 0);
+        data.extend(b"VBRI");
+        data.resize(4 + 32 + 14, 0);
+        data.extend(500u32.to_be_bytes());
+        data.resize(1024, 0);
+        assert_eq!(mpeg_duration(&data, 1_000_000), Some(13_061));
+    }
+
+    #[test]
+    fn mp3_file_duration() {
+        let path = std::env::temp_dir().join(format!("ncm-duration-{}.mp3", std::process::id()));
+        // 带填充的 ID3v2 标签，大小为同步安全整数 0x0101 = 129
+        let mut data = b"ID3\x04\x00\x00\x00\x00\x01\x01".to_vec();
+        data.resize(10 + 129, 0);
+        data.extend(HEADER);
+        data.resize(10 + 129 + 32_000, 0);
+        fs::write(&path, &data).unwrap();
+        let duration = mp3_duration(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(duration, Some(2000));
+    }
+
+    fn sample_tags() -> TrackTags {
+        TrackTags {
+            title: "晴天".to_owned(),
+            artists: vec!["周杰伦".to_owned(), "费玉清".to_owned()],
+            album: "叶惠美".to_owned(),
+            album_artist: Some("周杰伦".to_owned()),
+            track: Some(3),
+            netease_id: 186016,
+            cover: Some(vec![0x89, b'P', b'N', b'G', 1, 2, 3]),
+            lyrics: Some("[00:01.00]a".to_owned()),
+            duration: None,
+        }
+    }
+
+    fn assert_tags(read: &TrackTags, written: &TrackTags) {
+        assert_eq!(read.title, written.title);
+        assert_eq!(read.artists, written.artists);
+        assert_eq!(read.album, written.album);
+        assert_eq!(read.album_artist, written.album_artist);
+        assert_eq!(read.track, written.track);
+        assert_eq!(read.netease_id, written.netease_id);
+        assert_eq!(read.lyrics, written.lyrics);
+    }
+
+    #[test]
+    fn mp3_tags() {
+        let path = std::env::temp_dir().join(format!("ncm-tags-{}.mp3", std::process::id()));
+        let mut data = HEADER.to_vec();
+        data.resize(16_000, 0);
+        fs::write(&path, &data).unwrap();
+        let tags = sample_tags();
+        write_tags(&path, &tags).unwrap();
+        // 再次写入时替换已有的封面、歌词及歌曲 ID
+        write_tags(&path, &tags).unwrap();
+
+        let read = read_tags(&path).unwrap();
+        let tag = id3::Tag::read_from_path(&path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_tags(&read, &tags);
+        assert_eq!(tag.version(), id3::Version::Id3v24);
+        let pictures: Vec<_> = tag.pictures().collect();
+        assert_eq!(pictures.len(), 1);
+        assert_eq!(
+            pictures[0].picture_type,
+            id3::frame::PictureType::CoverFront
+        );
+        assert_eq!(pictures[0].mime_type, "image/png");
+        assert_eq!(Some(&pictures[0].data), tags.cover.as_ref());
+        assert_eq!(tag.extended_texts().count(), 1);
+        // 音频数据保持不变
+        assert!(bytes.ends_with(&data));
+    }
+
+    #[test]
+    fn flac_tags() {
+        let path = std::env::temp_dir().join(format!("ncm-tags-{}.flac", std::process::id()));
+        // 仅含 STREAMINFO 块：44100 Hz，双声道，16 bit，共 88200 个采样
+        let mut data = b"fLaC".to_vec();
+        data.extend([0x80, 0, 0, 34]);
+        data.extend([0x10, 0x00, 0x10, 0x00, 0, 0, 0, 0, 0, 0]);
+        let info: u64 = (44100 << 44) | (1 << 41) | (15 << 36) | 88200;
+        data.extend(info.to_be_bytes());
+        data.extend([0; 16]);
+        fs::write(&path, &data).unwrap();
+        let tags = sample_tags();
+        write_tags(&path, &tags).unwrap();
+        write_tags(&path, &tags).unwrap();
+
+        let read = read_tags(&path).unwrap();
+        let tag = metaflac::Tag::read_from_path(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_tags(&read, &tags);
+        assert_eq!(read.duration, Some(2000));
+        let pictures: Vec<_> = tag.pictures().collect();
+        assert_eq!(pictures.len(), 1);
+        assert_eq!(
+            pictures[0].picture_type,
+            metaflac::block::PictureType::CoverFront
+        );
+        assert_eq!(pictures[0].mime_type, "image/png");
+        assert_eq!(Some(&pictures[0].data), tags.cover.as_ref());
+    }
+}
@@ -0,0 +1,352 @@
+//
+// cloud_disk.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use anyhow::Result;
+use ncm_api::SongInfo;
+use serde_json::Value;
+use std::cmp::Reverse;
+
+use crate::{
+    cloud_upload::UploadHttp,
+    links::{NeteaseLink, parse_link},
+    utils::empty_song_info,
+};
+
+// 每次请求的云盘歌曲数量
+const PAGE_SIZE: u64 = 200;
+
+// 云盘歌曲及其文件信息
+#[derive(Debug, Clone)]
+pub struct CloudSong {
+    pub song: SongInfo,
+    pub file_name: String,
+    // 文件大小（字节）
+    pub file_size: u64,
+    // 上传时间（毫秒时间戳）
+    pub add_time: u64,
+    // 文件格式（小写扩展名）
+    pub format: String,
+}
+
+impl CloudSong {
+    fn from_json(value: &Value) -> Option<Self> {
+        let simple = &value["simpleSong"];
+        let id = simple["id"].as_u64().or_else(|| value["songId"].as_u64())?;
+        let file_name = value["fileName"].as_str().unwrap_or_default().to_owned();
+        let format = file_name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_lowercase())
+            .unwrap_or_default();
+        let singer = simple["ar"]
+            .as_array()
+            .map(|ar| {
+                ar.iter()
+                    .filter_map(|a| a["name"].as_str())
+                    .collect::<Vec<&str>>()
+                    .join("/")
+            })
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| value["artist"].as_str().unwrap_or_default().to_owned());
+        let album = simple["al"]["name"]
+            .as_str()
+            .filter(|s| !s.is_empty())
+            .or_else(|| value["album"].as_str())
+            .unwrap_or_default()
+            .to_owned();
+        let name = simple["name"]
+            .as_str()
+            .or_else(|| value["songName"].as_str())
+            .unwrap_or(&file_name)
+            .to_owned();
+        Some(Self {
+            song: SongInfo {
+                id,
+                name,
+                singer,
+                album,
+                album_id: simple["al"]["id"].as_u64().unwrap_or(0),
+                pic_url: simple["al"]["picUrl"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_owned(),
+                duration: simple["dt"].as_u64().unwrap_or(0),
+                ..empty_song_info()
+            },
+            file_name,
+            file_size: value["fileSize"].as_u64().unwrap_or(0),
+            add_time: value["addTime"].as_u64().unwrap_or(0),
+            format,
+        })
+    }
+}
+
+// 云盘排序方式，与云盘页面排序下拉框顺序一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloudSort {
+    // 最近上传在前
+    AddTime,
+    // 文件大的在前
+    Size,
+    // 按格式，其次按上传时间
+    Format,
+}
+
+impl CloudSort {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            1 => Self::Size,
+            2 => Self::Format,
+            _ => Self::AddTime,
+        }
+    }
+}
+
+// 排序并按格式过滤，format 为空时不过滤
+pub fn sort_cloud_songs(songs: &[CloudSong], sort: CloudSort, format: &str) -> Vec<CloudSong> {
+    let mut songs: Vec<CloudSong> = songs
+        .iter()
+        .filter(|s| format.is_empty() || s.format == format)
+        .cloned()
+        .collect();
+    match sort {
+        CloudSort::AddTime => songs.sort_by_key(|s| Reverse(s.add_time)),
+        CloudSort::Size => songs.sort_by_key(|s| Reverse(s.file_size)),
+        CloudSort::Format => {
+            songs.sort_by(|a, b| a.format.cmp(&b.format).then(b.add_time.cmp(&a.add_time)))
+        }
+    }
+    songs
+}
+
+// 云盘中出现的所有格式
+pub fn cloud_formats(songs: &[CloudSong]) -> Vec<String> {
+    let mut formats: Vec<String> = songs
+        .iter()
+        .map(|s| s.format.to_owned())
+        .filter(|f| !f.is_empty())
+        .collect();
+    formats.sort();
+    formats.dedup();
+    formats
+}
+
+pub struct CloudDisk<H: UploadHttp> {
+    http: H,
+}
+
+impl<H: UploadHttp> CloudDisk<H> {
+    pub fn new(http: H) -> Self {
+        Self { http }
+    }
+
+    // 获取全部云盘歌曲
+    pub async fn songs(&self) -> Result<Vec<CloudSong>> {
+        let mut songs = Vec::new();
+        let mut offset = 0;
+        loop {
+            let res = self
+                .http
+                .post_api(
+                    "v1/cloud/get",
+                    &[
+                        ("limit", PAGE_SIZE.to_string()),
+                        ("offset", offset.to_string()),
+                    ],
+                )
+                .await?;
+            let data = res["data"].as_array().cloned().unwrap_or_default();
+            offset += data.len() as u64;
+            songs.extend(data.iter().filter_map(CloudSong::from_json));
+            if data.is_empty() || !res["hasMore"].as_bool().unwrap_or(false) {
+                break;
+            }
+        }
+        Ok(songs)
+    }
+
+    // 从云盘删除歌曲
+    pub async fn delete(&self, ids: &[u64]) -> Result<()> {
+        let ids = serde_json::to_string(ids)?;
+        self.http.post_api("cloud/del", &[("songIds", ids)]).await?;
+        Ok(())
+    }
+
+    // 将云盘歌曲重新关联到曲库中的歌曲
+    pub async fn rematch(&self, uid: u64, song_id: u64, adjust_song_id: u64) -> Result<()> {
+        self.http
+            .post_api(
+                "cloud/user/song/match",
+                &[
+                    ("userId", uid.to_string()),
+                    ("songId", song_id.to_string()),
+                    ("adjustSongId", adjust_song_id.to_string()),
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+// 从歌曲 ID 或歌曲链接中解析 ID
+pub fn parse_song_id(text: &str) -> Option<u64> {
+    let text = text.trim();
+    if let Ok(id) = text.parse() {
+        return Some(id);
+    }
+    match parse_link(text)? {
+        NeteaseLink::Song(id) => Some(id),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cloud_song(id: u64, format: &str, file_size: u64, add_time: u64) -> CloudSong {
+        CloudSong {
+            song: SongInfo {
+                id,
+                ..empty_song_info()
+            },
+            file_name: format!("{id}.{format}"),
+            file_size,
+            add_time,
+            format: format.to_owned(),
+        }
+    }
+
+    fn ids(songs: &[CloudSong]) -> Vec<u64> {
+        songs.iter().map(|s| s.song.id).collect()
+    }
+
+    #[test]
+    fn sort_and_filter() {
+        let songs = [
+            cloud_song(1, "mp3", 300, 10),
+            cloud_song(2, "flac", 900, 30),
+            cloud_song(3, "mp3", 500, 20),
+            cloud_song(4, "", 100, 40),
+            cloud_song(5, "flac", 700, 50),
+        ];
+        let cases = [
+            (CloudSort::AddTime, "", vec![5, 4, 2, 3, 1]),
+            (CloudSort::Size, "", vec![2, 5, 3, 1, 4]),
+            // 格式相同时最近上传在前，无格式的排在最前
+            (CloudSort::Format, "", vec![4, 5, 2, 3, 1]),
+            (CloudSort::AddTime, "mp3", vec![3, 1]),
+            (CloudSort::Size, "flac", vec![2, 5]),
+            (CloudSort::Format, "flac", vec![5, 2]),
+            // 格式需完全一致
+            (CloudSort::AddTime, "mp", vec![]),
+            (CloudSort::AddTime, "wav", vec![]),
+        ];
+        for (sort, format, expected) in cases {
+            assert_eq!(
+                ids(&sort_cloud_songs(&songs, sort, format)),
+                expected,
+                "{sort:?} {format:?}"
+            );
+        }
+        assert_eq!(cloud_formats(&songs), ["flac", "mp3"]);
+        assert!(cloud_formats(&[]).is_empty());
+    }
+
+    #[test]
+    fn sort_indexes() {
+        let cases = [
+            (0, CloudSort::AddTime),
+            (1, CloudSort::Size),
+            (2, CloudSort::Format),
+            (9, CloudSort::AddTime),
+        ];
+        for (index, expected) in cases {
+            assert_eq!(CloudSort::from_index(index), expected);
+        }
+    }
+
+    #[test]
+    fn song_ids() {
+        let cases = [
+            ("186016", Some(186016)),
+            ("  186016\n", Some(186016)),
+            ("https://music.163.com/song?id=186016", Some(186016)),
+            (
+                "https://music.163.com/#/song?id=186016&userid=1",
+                Some(186016),
+            ),
+            (
+                "分享歌曲：晴天 https://music.163.com/song?id=186016 (来自@网易云音乐)",
+                Some(186016),
+            ),
+            ("orpheus://song/186016", Some(186016)),
+            // 非单曲链接
+            ("https://music.163.com/playlist?id=1", None),
+            ("https://music.163.com/album/1", None),
+            // 缺少或无效的 ID
+            ("", None),
+            ("   ", None),
+            ("-1", None),
+            ("abc", None),
+            ("18601a", None),
+            ("99999999999999999999", None),
+            ("https://music.163.com/song", None),
+            ("https://music.163.com/song?id=", None),
+            ("https://music.163.com/song?id=abc", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_song_id(text), expected, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn from_json() {
+        let song = CloudSong::from_json(&json!({
+            "simpleSong": {
+                "id": 1,
+                "name": "晴天",
+                "ar": [{"name": "周杰伦"}, {"name": "费玉清"}],
+                "al": {"id": 2, "name": "叶惠美", "picUrl": "http://p/1.jpg"},
+                "dt": 269000,
+            },
+            "fileName": "晴天.FLAC",
+            "fileSize": 1024,
+            "addTime": 1700000000000u64,
+        }))
+        .unwrap();
+        assert_eq!(song.song.id, 1);
+        assert_eq!(song.song.name, "晴天");
+        assert_eq!(song.song.singer, "周杰伦/费玉清");
+        assert_eq!(
+            (song.song.album.as_str(), song.song.album_id),
+            ("叶惠美", 2)
+        );
+        assert_eq!(song.song.duration, 269000);
+        assert_eq!(song.format, "flac");
+        assert_eq!((song.file_size, song.add_time), (1024, 1700000000000));
+
+        // 未关联曲库的歌曲使用上传时的文件信息
+        let song = CloudSong::from_json(&json!({
+            "simpleSong": {"name": null, "ar": [], "al": {"name": ""}},
+            "songId": 3,
+            "songName": "demo",
+            "artist": "me",
+            "album": "tape",
+            "fileName": "demo",
+        }))
+        .unwrap();
+        assert_eq!(song.song.id, 3);
+        assert_eq!(
+            (song.song.name.as_str(), song.song.singer.as_str()),
+            ("demo", "me")
+        );
+        assert_eq!(song.song.album, "tape");
+        assert_eq!(song.format, "");
+
+        assert!(CloudSong::from_json(&json!({"fileName": "a.mp3"})).is_none());
+    }
+}
@@ -0,0 +1,492 @@
+//
+// cloud_upload.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use anyhow::{Context, Result};
+use gtk::glib;
+use log::*;
+use serde_json::Value;
+use std::{
+    fs,
+    future::Future,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use crate::{
+    audio::{AudioFormat, TrackTags, read_tags},
+    utils::blocking,
+};
+
+pub const BUCKET: &str = "jd-musicrep-privatecloud-audio-public";
+// 分块上传大小
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+// 上传所需的 HTTP 接口，可替换为本地测试服务
+pub trait UploadHttp {
+    // 以表单方式请求网易云接口，path 相对于 API 根地址
+    fn post_api(
+        &self,
+        path: &str,
+        params: &[(&str, String)],
+    ) -> impl Future<Output = Result<Value>>;
+    // 获取上传节点
+    fn get_lbs(&self) -> impl Future<Output = Result<Value>>;
+    // 上传文件数据块
+    fn post_object(
+        &self,
+        url: &str,
+        headers: &[(&str, String)],
+        data: Vec<u8>,
+    ) -> impl Future<Output = Result<Value>>;
+}
+
+// 单个文件的上传结果
+#[derive(Debug, Clone)]
+pub struct UploadReport {
+    pub name: String,
+    pub song_id: u64,
+    // 云盘中已存在相同文件，跳过了数据上传
+    pub duplicate: bool,
+    // 云盘歌曲已关联到网易云曲库
+    pub matched: bool,
+}
+
+pub struct CloudUploader<H: UploadHttp> {
+    http: H,
+}
+
+impl<H: UploadHttp> CloudUploader<H> {
+    pub fn new(http: H) -> Self {
+        Self { http }
+    }
+
+    // 上传文件到云盘，progress 回调参数为 0.0 ~ 1.0
+    // 文件读取与校验在后台线程进行，数据按块读取上传
+    pub async fn upload(&self, path: &Path, progress: impl Fn(f64)) -> Result<UploadReport> {
+        let file = {
+            let path = path.to_owned();
+            blocking(move || UploadFile::inspect(&path)).await?
+        };
+        let UploadFile {
+            size,
+            md5,
+            filename,
+            ext,
+            tags,
+        } = file;
+        let song = if tags.title.is_empty() {
+            filename.to_owned()
+        } else {
+            tags.title
+        };
+        let artist = tags.artists.join("/");
+        progress(0.0);
+
+        // 按 md5 检查云盘是否已有相同文件
+        let check = self
+            .http
+            .post_api(
+                "cloud/upload/check",
+                &[
+                    ("bitrate", "999000".to_owned()),
+                    ("ext", String::new()),
+                    ("length", size.to_string()),
+                    ("md5", md5.to_owned()),
+                    ("songId", "0".to_owned()),
+                    ("version", "1".to_owned()),
+                ],
+            )
+            .await?;
+        let need_upload = check["needUpload"].as_bool().unwrap_or(true);
+        let song_id = json_string(&check["songId"]);
+        debug!("云盘上传检查: {} needUpload={}", filename, need_upload);
+
+        let token = self
+            .http
+            .post_api(
+                "nos/token/alloc",
+                &[
+                    ("bucket", BUCKET.to_owned()),
+                    ("ext", ext.to_owned()),
+                    ("filename", filename.to_owned()),
+                    ("local", "false".to_owned()),
+                    ("nos_product", "3".to_owned()),
+                    ("type", "audio".to_owned()),
+                    ("md5", md5.to_owned()),
+                ],
+            )
+            .await?;
+        let token = &token["result"];
+        let object_key = token["objectKey"].as_str().context("objectKey missing")?;
+        let resource_id = json_string(&token["resourceId"]);
+
+        if need_upload {
+            let lbs = self.http.get_lbs().await?;
+            let host = lbs["upload"][0].as_str().context("upload host missing")?;
+            let object_url = format!(
+                "{}/{}/{}",
+                host,
+                BUCKET,
+                glib::Uri::escape_string(object_key, Some("/"), false)
+            );
+            let mut context = String::new();
+            let total = size;
+            let mut offset = 0;
+            while offset < total || total == 0 {
+                let end = (offset + CHUNK_SIZE as u64).min(total);
+                let complete = end == total;
+                let mut url = format!(
+                    "{}?offset={}&complete={}&version=1.0",
+                    object_url, offset, complete
+                );
+                if !context.is_empty() {
+                    url.push_str(&format!("&context={}", context));
+                }
+                let (chunk, chunk_md5) = {
+                    let path = path.to_owned();
+                    blocking(move || read_chunk(&path, offset, end - offset)).await?
+                };
+                // Content-MD5 为本块数据的校验值，而非整个文件
+                let headers = [
+                    (
+                        "x-nos-token",
+                        token["token"].as_str().unwrap_or_default().to_owned(),
+                    ),
+                    ("Content-Type", content_type(&ext).to_owned()),
+                    ("Content-MD5", chunk_md5),
+                ];
+                let res = self.http.post_object(&url, &headers, chunk).await?;
+                if let Some(ctx) = res["context"].as_str() {
+                    context = ctx.to_owned();
+                }
+                offset = end;
+                progress(if total == 0 {
+                    1.0
+                } else {
+                    offset as f64 / total as f64
+                });
+                if complete {
+                    break;
+                }
+            }
+        }
+
+        let info = self
+            .http
+            .post_api(
+                "upload/cloud/info/v2",
+                &[
+                    ("md5", md5),
+                    ("songid", song_id),
+                    ("filename", filename),
+                    ("song", song.to_owned()),
+                    ("album", tags.album),
+                    ("artist", artist),
+                    ("bitrate", "999000".to_owned()),
+                    ("resourceId", resource_id),
+                ],
+            )
+            .await?;
+        let cloud_song_id = json_string(&info["songId"]);
+        let publish = self
+            .http
+            .post_api("cloud/pub/v2", &[("songid", cloud_song_id.to_owned())])
+            .await?;
+        progress(1.0);
+
+        // 未关联到曲库的云盘歌曲专辑 ID 为 0
+        let simple_song = &publish["privateCloud"]["simpleSong"];
+        let matched = simple_song["al"]["id"].as_u64().unwrap_or(0) != 0;
+        Ok(UploadReport {
+            name: song,
+            song_id: simple_song["id"]
+                .as_u64()
+                .or_else(|| cloud_song_id.parse().ok())
+                .unwrap_or(0),
+            duplicate: !need_upload,
+            matched,
+        })
+    }
+}
+
+// 上传前需要的文件信息
+struct UploadFile {
+    size: u64,
+    md5: String,
+    filename: String,
+    ext: String,
+    tags: TrackTags,
+}
+
+impl UploadFile {
+    // 分块计算 md5，避免将整个文件读入内存
+    fn inspect(path: &Path) -> Result<Self> {
+        let mut file = fs::File::open(path)?;
+        let size = file.metadata()?.len();
+        let mut checksum =
+            glib::Checksum::new(glib::ChecksumType::Md5).context("md5 checksum failed")?;
+        let mut buf = vec![0; CHUNK_SIZE];
+        loop {
+            let len = file.read(&mut buf)?;
+            if len == 0 {
+                break;
+            }
+            checksum.update(&buf[..len]);
+        }
+        let md5 = checksum
+            .string()
+            .context("md5 checksum failed")?
+            .to_string();
+        let filename = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let ext = match AudioFormat::from_path(path) {
+            AudioFormat::Unknown => path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default(),
+            format => format.extension().to_owned(),
+        };
+        Ok(Self {
+            size,
+            md5,
+            filename,
+            ext,
+            tags: read_tags(path).unwrap_or_default(),
+        })
+    }
+}
+
+// 读取一块数据，同时返回其 md5
+fn read_chunk(path: &Path, offset: u64, len: u64) -> Result<(Vec<u8>, String)> {
+    let mut file = fs::File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::with_capacity(len as usize);
+    file.take(len).read_to_end(&mut data)?;
+    let md5 = glib::compute_checksum_for_data(glib::ChecksumType::Md5, &data)
+        .context("md5 checksum failed")?;
+    Ok((data, md5.to_string()))
+}
+
+// 上传数据的 Content-Type，按扩展名确定
+fn content_type(ext: &str) -> &'static str {
+    match ext {
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "ogg" | "oga" | "opus" => "audio/ogg",
+        "m4a" | "mp4" => "audio/mp4",
+        "aac" => "audio/aac",
+        "wav" => "audio/wav",
+        "ape" => "audio/ape",
+        "wma" => "audio/x-ms-wma",
+        _ => "application/octet-stream",
+    }
+}
+
+// 接口返回的 ID 可能为数字或字符串
+fn json_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_owned(),
+        Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webapi::WebApi;
+    use serde_json::json;
+    use std::{cell::RefCell, thread};
+
+    // 本地测试服务收到的请求
+    #[derive(Debug)]
+    struct Received {
+        method: String,
+        url: String,
+        content_type: Option<String>,
+        content_md5: Option<String>,
+        body: Vec<u8>,
+    }
+
+    // 模拟网易云接口、上传节点与对象存储，收到 cloud/pub/v2 后结束
+    fn stand_in_server(need_upload: bool) -> (String, thread::JoinHandle<Vec<Received>>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let host = base.to_owned();
+        let handle = thread::spawn(move || {
+            let mut received = Vec::new();
+            for mut request in server.incoming_requests() {
+                let mut body = Vec::new();
+                request.as_reader().read_to_end(&mut body).unwrap();
+                let url = request.url().to_owned();
+                let path = url.split('?').next().unwrap_or_default().to_owned();
+                let json = match path.as_str() {
+                    "/weapi/cloud/upload/check" => {
+                        json!({"code": 200, "needUpload": need_upload, "songId": "0"})
+                    }
+                    "/weapi/nos/token/alloc" => json!({
+                        "code": 200,
+                        "result": {"objectKey": "obj/a b.flac", "resourceId": 123, "token": "tok"},
+                    }),
+                    "/lbs" => json!({"upload": [host]}),
+                    "/weapi/upload/cloud/info/v2" => json!({"code": 200, "songId": "456"}),
+                    "/weapi/cloud/pub/v2" => json!({
+                        "code": 200,
+                        "privateCloud": {"simpleSong": {"id": 789, "al": {"id": 1}}},
+                    }),
+                    _ => json!({"context": "ctx"}),
+                };
+                let header = |name: &str| {
+                    request
+                        .headers()
+                        .iter()
+                        .find(|h| h.field.equiv(name))
+                        .map(|h| h.value.to_string())
+                };
+                received.push(Received {
+                    method: request.method().to_string(),
+                    url,
+                    content_type: header("Content-Type"),
+                    content_md5: header("Content-MD5"),
+                    body,
+                });
+                request
+                    .respond(tiny_http::Response::from_string(json.to_string()))
+                    .unwrap();
+                if path == "/weapi/cloud/pub/v2" {
+                    break;
+                }
+            }
+            received
+        });
+        (base, handle)
+    }
+
+    fn upload(need_upload: bool, data: &[u8]) -> (UploadReport, Vec<f64>, Vec<Received>) {
+        let dir = std::env::temp_dir().join(format!("ncm-upload-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.flac", need_upload));
+        fs::write(&path, data).unwrap();
+
+        let (base, server) = stand_in_server(need_upload);
+        let http = WebApi::new(None, None)
+            .unwrap()
+            .with_base_urls(&format!("{}/weapi/", base), &format!("{}/lbs", base));
+        let uploader = CloudUploader::new(http);
+        let progress = RefCell::new(Vec::new());
+        let report = glib::MainContext::new()
+            .block_on(uploader.upload(&path, |f| progress.borrow_mut().push(f)))
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+        (report, progress.into_inner(), server.join().unwrap())
+    }
+
+    #[test]
+    fn upload_in_chunks() {
+        let mut data = b"fLaC".to_vec();
+        data.resize(CHUNK_SIZE * 2 + CHUNK_SIZE / 2, 7);
+        let (report, progress, received) = upload(true, &data);
+
+        assert_eq!(report.song_id, 789);
+        assert!(report.matched);
+        assert!(!report.duplicate);
+
+        let paths: Vec<&str> = received
+            .iter()
+            .map(|r| r.url.split('?').next().unwrap())
+            .collect();
+        let object = format!("/{}/obj/a%20b.flac", BUCKET);
+        assert_eq!(
+            paths,
+            [
+                "/weapi/cloud/upload/check",
+                "/weapi/nos/token/alloc",
+                "/lbs",
+                object.as_str(),
+                object.as_str(),
+                object.as_str(),
+                "/weapi/upload/cloud/info/v2",
+                "/weapi/cloud/pub/v2",
+            ]
+        );
+
+        // 接口请求以 weapi 方式加密
+        let check = String::from_utf8_lossy(&received[0].body);
+        assert!(check.starts_with("params=") && check.contains("&encSecKey="));
+
+        // 数据按块上传，第二块起携带上一块返回的 context
+        let chunks = &received[3..6];
+        assert!(chunks.iter().all(|r| r.method == "POST"));
+        assert!(
+            chunks
+                .iter()
+                .all(|r| r.content_type.as_deref() == Some("audio/flac"))
+        );
+        assert!(chunks[0].url.contains("offset=0&complete=false"));
+        assert!(!chunks[0].url.contains("context="));
+        assert!(chunks[1].url.contains(&format!("offset={}", CHUNK_SIZE)));
+        assert!(chunks[1].url.contains("context=ctx"));
+        assert!(chunks[2].url.contains("complete=true"));
+        let uploaded: Vec<u8> = chunks.iter().flat_map(|r| r.body.to_owned()).collect();
+        assert_eq!(uploaded, data);
+
+        // 每块携带各自数据的 Content-MD5
+        for (chunk, expected) in chunks.iter().zip(data.chunks(CHUNK_SIZE)) {
+            let md5 = glib::compute_checksum_for_data(glib::ChecksumType::Md5, expected).unwrap();
+            assert_eq!(chunk.body.len(), expected.len());
+            assert_eq!(chunk.content_md5.as_deref(), Some(md5.as_str()));
+        }
+        assert_ne!(chunks[0].content_md5, chunks[2].content_md5);
+
+        assert_eq!(progress.first(), Some(&0.0));
+        assert_eq!(progress.last(), Some(&1.0));
+        assert!(progress.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn skip_duplicate() {
+        let (report, _, received) = upload(false, b"fLaC duplicate");
+        assert!(report.duplicate);
+        assert!(
+            received
+                .iter()
+                .all(|r| r.url.starts_with("/weapi/") && r.method == "POST")
+        );
+        assert_eq!(received.len(), 4);
+    }
+
+    #[test]
+    fn inspect_file() {
+        let path = std::env::temp_dir().join(format!("ncm-inspect-{}.FLAC", std::process::id()));
+        let mut data = b"fLaC".to_vec();
+        data.resize(CHUNK_SIZE + 3, 1);
+        fs::write(&path, &data).unwrap();
+        let file = UploadFile::inspect(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let md5 = glib::compute_checksum_for_data(glib::ChecksumType::Md5, &data).unwrap();
+        assert_eq!(file.size, data.len() as u64);
+        assert_eq!(file.md5, md5.as_str());
+        assert_eq!(file.ext, "flac");
+    }
+
+    #[test]
+    fn content_types() {
+        let cases = [
+            ("mp3", "audio/mpeg"),
+            ("flac", "audio/flac"),
+            ("ogg", "audio/ogg"),
+            ("m4a", "audio/mp4"),
+            ("wav", "audio/wav"),
+            ("xyz", "application/octet-stream"),
+        ];
+        for (ext, expected) in cases {
+            assert_eq!(content_type(ext), expected);
+        }
+    }
+}
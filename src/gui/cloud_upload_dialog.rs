@@ -0,0 +1,141 @@
+//
+// cloud_upload_dialog.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use adw::prelude::*;
+use gettextrs::gettext;
+use gtk::{CompositeTemplate, glib, subclass::prelude::*};
+use std::{cell::RefCell, path::PathBuf};
+
+use crate::{cloud_upload::UploadReport, utils::*};
+
+glib::wrapper! {
+    pub struct CloudUploadDialog(ObjectSubclass<imp::CloudUploadDialog>)
+        @extends adw::Dialog, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::ShortcutManager;
+}
+
+impl Default for CloudUploadDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CloudUploadDialog {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    pub fn set_files(&self, paths: &[PathBuf]) {
+        let imp = self.imp();
+        let listbox = imp.listbox.get();
+        listbox.remove_all();
+        let mut rows = imp.rows.borrow_mut();
+        rows.clear();
+        for path in paths {
+            let name = path
+                .file_name()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let progress = gtk::ProgressBar::builder()
+                .valign(gtk::Align::Center)
+                .width_request(120)
+                .build();
+            let row = adw::ActionRow::builder()
+                .title(glib::markup_escape_text(&name))
+                .subtitle(gettext("Waiting"))
+                .build();
+            row.add_suffix(&progress);
+            listbox.append(&row);
+            rows.push((row, progress));
+        }
+        imp.summary_label.set_label(&gettext_f(
+            "Uploading {num} files…",
+            &[("num", &paths.len().to_string())],
+        ));
+    }
+
+    pub fn set_progress(&self, index: usize, fraction: f64) {
+        if let Some((row, progress)) = self.imp().rows.borrow().get(index) {
+            row.set_subtitle(&gettext("Uploading"));
+            progress.set_fraction(fraction);
+        }
+    }
+
+    pub fn set_result(&self, index: usize, result: &Result<UploadReport, String>) {
+        if let Some((row, progress)) = self.imp().rows.borrow().get(index) {
+            progress.set_visible(false);
+            let subtitle = match result {
+                Ok(report) => {
+                    let mut status = if report.matched {
+                        gettext("Matched")
+                    } else {
+                        gettext("Not matched")
+                    };
+                    if report.duplicate {
+                        status = gettext_f(
+                            "{status}, already in cloud",
+                            &[("status", &status)],
+                        );
+                    }
+                    status
+                }
+                Err(err) => gettext_f("Failed: {err}", &[("err", err)]),
+            };
+            row.set_subtitle(&glib::markup_escape_text(&subtitle));
+        }
+    }
+
+    pub fn finish(&self, reports: &[UploadReport], failed: usize) {
+        let matched = reports.iter().filter(|r| r.matched).count();
+        let summary = gettext_f(
+            "Uploaded {num}: {matched} matched, {unmatched} not matched, {failed} failed",
+            &[
+                ("num", &reports.len().to_string()),
+                ("matched", &matched.to_string()),
+                ("unmatched", &(reports.len() - matched).to_string()),
+                ("failed", &failed.to_string()),
+            ],
+        );
+        self.imp().summary_label.set_label(&summary);
+    }
+}
+
+mod imp {
+
+    use adw::subclass::prelude::*;
+
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/gitee/gmg137/NeteaseCloudMusicGtk4/gtk/cloud-upload-dialog.ui")]
+    pub struct CloudUploadDialog {
+        #[template_child]
+        pub summary_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub listbox: TemplateChild<gtk::ListBox>,
+
+        pub rows: RefCell<Vec<(adw::ActionRow, gtk::ProgressBar)>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for CloudUploadDialog {
+        const NAME: &'static str = "CloudUploadDialog";
+        type Type = super::CloudUploadDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for CloudUploadDialog {}
+    impl WidgetImpl for CloudUploadDialog {}
+    impl AdwDialogImpl for CloudUploadDialog {}
+}
@@ -0,0 +1,288 @@
+//
+// desktop_lyrics.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use gio::Settings;
+use glib::translate::IntoGlib;
+use gtk::{CompositeTemplate, cairo, gdk, gio, glib, pango, prelude::*, subclass::prelude::*};
+#[cfg(target_os = "linux")]
+use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
+use once_cell::sync::OnceCell;
+use std::cell::{Cell, RefCell};
+
+use crate::lyrics::Lyrics;
+
+glib::wrapper! {
+    pub struct DesktopLyricsWindow(ObjectSubclass<imp::DesktopLyricsWindow>)
+        @extends gtk::Widget, gtk::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl Default for DesktopLyricsWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 桌面歌词窗口
+impl DesktopLyricsWindow {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    // 保持窗口在其他窗口之上，需在窗口实现前调用
+    // GTK4 没有提供置顶接口：Wayland 下混成器支持 layer-shell 时改为屏幕底部的覆盖层，
+    // 位置由混成器决定，无法拖动；X11 下窗口映射后请求窗口管理器置顶
+    #[cfg(target_os = "linux")]
+    fn setup_keep_above(&self) {
+        if gtk4_layer_shell::is_supported() {
+            self.init_layer_shell();
+            self.set_namespace(Some("desktop-lyrics"));
+            self.set_layer(Layer::Overlay);
+            self.set_anchor(Edge::Bottom, true);
+            self.set_margin(Edge::Bottom, 48);
+            self.set_keyboard_mode(KeyboardMode::None);
+            return;
+        }
+        self.connect_realize(|s| {
+            let Some(surface) = s.surface().and_downcast::<gdk4_x11::X11Surface>() else {
+                return;
+            };
+            surface.connect_mapped_notify(|surface| {
+                if !surface.is_mapped() {
+                    return;
+                }
+                if let Err(err) = x11_keep_above(surface.xid() as u32) {
+                    log::warn!("桌面歌词窗口置顶失败: {:?}", err);
+                }
+            });
+        });
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn setup_keep_above(&self) {}
+
+    fn settings(&self) -> &Settings {
+        self.imp().settings.get().unwrap()
+    }
+
+    fn setup_settings(&self) {
+        let settings = Settings::new(crate::APP_ID);
+        for key in [
+            "desktop-lyrics-font",
+            "desktop-lyrics-color",
+            "desktop-lyrics-next-color",
+            "desktop-lyrics-outline-color",
+            "desktop-lyrics-outline-width",
+        ] {
+            settings.connect_changed(
+                Some(key),
+                glib::clone!(
+                    #[weak(rename_to = s)]
+                    self,
+                    move |_, _| s.load_style()
+                ),
+            );
+        }
+        settings.connect_changed(
+            Some("desktop-lyrics-locked"),
+            glib::clone!(
+                #[weak(rename_to = s)]
+                self,
+                move |settings, key| s.set_locked(settings.boolean(key))
+            ),
+        );
+        settings.connect_changed(
+            Some("desktop-lyrics"),
+            glib::clone!(
+                #[weak(rename_to = s)]
+                self,
+                move |settings, key| s.set_visible(settings.boolean(key))
+            ),
+        );
+        self.imp().settings.set(settings).unwrap();
+    }
+
+    fn load_style(&self) {
+        let settings = self.settings();
+        let font = pango::FontDescription::from_string(&settings.string("desktop-lyrics-font"));
+        let outline = settings.uint("desktop-lyrics-outline-width") as i32;
+        let outline_color = settings.string("desktop-lyrics-outline-color");
+        // 用八个方向的文字阴影模拟描边
+        let shadow = if outline == 0 {
+            "none".to_owned()
+        } else {
+            [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)]
+                .iter()
+                .map(|(x, y)| format!("{}px {}px {}", x * outline, y * outline, outline_color))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        let css = format!(
+            "window.desktop-lyrics {{ font-family: \"{}\"; font-size: {}pt; font-weight: {}; }}\n\
+             window.desktop-lyrics label {{ text-shadow: {}; }}\n\
+             window.desktop-lyrics .current-line {{ color: {}; }}\n\
+             window.desktop-lyrics .next-line {{ color: {}; }}",
+            font.family().unwrap_or_else(|| "Sans".into()),
+            (font.size() / pango::SCALE).max(8),
+            font.weight().into_glib(),
+            shadow,
+            settings.string("desktop-lyrics-color"),
+            settings.string("desktop-lyrics-next-color"),
+        );
+        self.imp().provider.load_from_string(&css);
+    }
+
+    // 锁定后窗口不再响应鼠标，点击会穿透到下方窗口
+    pub fn set_locked(&self, locked: bool) {
+        if locked {
+            self.remove_css_class("unlocked");
+        } else {
+            self.add_css_class("unlocked");
+        }
+        if let Some(surface) = self.surface() {
+            let region = if locked {
+                cairo::Region::create()
+            } else {
+                cairo::Region::create_rectangle(&cairo::RectangleInt::new(0, 0, 1 << 15, 1 << 15))
+            };
+            surface.set_input_region(&region);
+        }
+    }
+
+    pub fn set_lyrics(&self, lyrics: &Lyrics) {
+        let imp = self.imp();
+        imp.lyrics.replace(lyrics.clone());
+        imp.index.set(None);
+        imp.current_label.set_label("");
+        imp.translation_label.set_visible(false);
+        imp.next_label.set_label(lyrics.original.lines.first().map_or("", |l| &l.text));
+    }
+
+    // 显示当前行（含翻译）与下一行
+    pub fn update_time(&self, time: u64) {
+        let imp = self.imp();
+        let lyrics = imp.lyrics.borrow();
+        let lines = &lyrics.original.lines;
+        let Some(index) = lines.iter().rposition(|l| l.time <= time) else {
+            return;
+        };
+        if imp.index.replace(Some(index)) == Some(index) {
+            return;
+        }
+        let line = &lines[index];
+        imp.current_label.set_label(&line.text);
+        let translation = lyrics
+            .translation
+            .lines
+            .iter()
+            .find(|l| l.time == line.time && !l.text.is_empty());
+        imp.translation_label.set_label(translation.map_or("", |l| &l.text));
+        imp.translation_label.set_visible(translation.is_some());
+        imp.next_label.set_label(lines.get(index + 1).map_or("", |l| &l.text));
+    }
+}
+
+// 通过 EWMH 客户端消息请求窗口管理器为窗口添加 _NET_WM_STATE_ABOVE 状态
+#[cfg(target_os = "linux")]
+fn x11_keep_above(window: u32) -> anyhow::Result<()> {
+    use x11rb::{
+        connection::Connection,
+        protocol::xproto::{ClientMessageEvent, ConnectionExt, EventMask},
+    };
+
+    let (conn, screen) = x11rb::connect(None)?;
+    let root = conn.setup().roots[screen].root;
+    let state = conn.intern_atom(false, b"_NET_WM_STATE")?.reply()?.atom;
+    let above = conn
+        .intern_atom(false, b"_NET_WM_STATE_ABOVE")?
+        .reply()?
+        .atom;
+    // 数据依次为：操作（1 为添加）、状态、第二个状态、请求来源（1 为普通应用）
+    let event = ClientMessageEvent::new(32, window, state, [1, above, 0, 1, 0]);
+    conn.send_event(
+        false,
+        root,
+        EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+        event,
+    )?;
+    conn.flush()?;
+    Ok(())
+}
+
+mod imp {
+
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/gitee/gmg137/NeteaseCloudMusicGtk4/gtk/desktop-lyrics-window.ui")]
+    pub struct DesktopLyricsWindow {
+        #[template_child]
+        pub current_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub translation_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub next_label: TemplateChild<gtk::Label>,
+
+        pub settings: OnceCell<Settings>,
+        // 基础样式及根据设置生成的样式，添加到整个显示上，窗口销毁时移除
+        pub base_provider: gtk::CssProvider,
+        pub provider: gtk::CssProvider,
+        pub lyrics: RefCell<Lyrics>,
+        pub index: Cell<Option<usize>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for DesktopLyricsWindow {
+        const NAME: &'static str = "DesktopLyricsWindow";
+        type Type = super::DesktopLyricsWindow;
+        type ParentType = gtk::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for DesktopLyricsWindow {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+
+            let display = gdk::Display::default().expect("Could not connect to a display.");
+            self.base_provider.load_from_resource(
+                "/com/gitee/gmg137/NeteaseCloudMusicGtk4/themes/desktop-lyrics.css",
+            );
+            gtk::style_context_add_provider_for_display(
+                &display,
+                &self.base_provider,
+                gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+            );
+            gtk::style_context_add_provider_for_display(
+                &display,
+                &self.provider,
+                gtk::STYLE_PROVIDER_PRIORITY_USER,
+            );
+
+            obj.setup_settings();
+            obj.load_style();
+            obj.setup_keep_above();
+            // 输入区域需在窗口表面创建后设置
+            obj.connect_realize(|s| s.set_locked(s.settings().boolean("desktop-lyrics-locked")));
+        }
+
+        fn dispose(&self) {
+            if let Some(display) = gdk::Display::default() {
+                gtk::style_context_remove_provider_for_display(&display, &self.base_provider);
+                gtk::style_context_remove_provider_for_display(&display, &self.provider);
+            }
+        }
+    }
+    impl WidgetImpl for DesktopLyricsWindow {}
+    impl WindowImpl for DesktopLyricsWindow {}
+}
@@ -0,0 +1,236 @@
+//
+// local_match_dialog.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use adw::prelude::*;
+use async_channel::Sender;
+use gettextrs::gettext;
+use gtk::{CompositeTemplate, glib, subclass::prelude::*};
+use ncm_api::SongInfo;
+use once_cell::sync::OnceCell;
+use std::{cell::Cell, sync::Arc};
+
+use crate::{
+    application::Action,
+    audio::{LocalTrack, match_keyword, match_score},
+    model::{SearchResult, SearchType},
+    utils::*,
+};
+
+glib::wrapper! {
+    pub struct LocalMatchDialog(ObjectSubclass<imp::LocalMatchDialog>)
+        @extends adw::Dialog, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::ShortcutManager;
+}
+
+impl LocalMatchDialog {
+    pub fn new(sender: Sender<Action>) -> Self {
+        let obj: Self = glib::Object::new();
+        obj.imp().sender.set(sender).unwrap();
+        obj
+    }
+
+    pub fn update_tracks(&self, tracks: &[LocalTrack]) {
+        let listbox = self.imp().listbox.get();
+        listbox.remove_all();
+        for track in tracks {
+            listbox.append(&self.create_row(track));
+        }
+    }
+
+    fn create_row(&self, track: &LocalTrack) -> adw::ExpanderRow {
+        let sender = self.imp().sender.get().unwrap().clone();
+        let si = track.to_song_info();
+        let row = adw::ExpanderRow::builder()
+            .title(glib::markup_escape_text(&format!("{} - {}", si.name, si.singer)))
+            .subtitle(glib::markup_escape_text(&match_subtitle(track)))
+            .build();
+
+        // 首次展开时搜索候选歌曲
+        let loaded = Cell::new(false);
+        row.connect_expanded_notify(move |row| {
+            if !row.is_expanded() || loaded.replace(true) {
+                return;
+            }
+            let row_weak = glib::SendWeakRef::from(row.downgrade());
+            let si = si.clone();
+            let local = si.clone();
+            let sender_cb = sender.clone();
+            sender
+                .send_blocking(Action::Search(
+                    match_keyword(&si),
+                    SearchType::Song,
+                    0,
+                    10,
+                    Arc::new(move |res| {
+                        if let (Some(row), SearchResult::Songs(sis, _)) = (row_weak.upgrade(), res)
+                        {
+                            add_candidates(&row, &local, sis, &sender_cb);
+                        }
+                    }),
+                ))
+                .unwrap();
+        });
+        row
+    }
+}
+
+fn match_subtitle(track: &LocalTrack) -> String {
+    if track.netease_id == 0 {
+        if track.confirmed {
+            gettext("No match")
+        } else {
+            gettext("Not matched")
+        }
+    } else {
+        let name = if track.matched.is_empty() {
+            track.netease_id.to_string()
+        } else {
+            track.matched.to_owned()
+        };
+        if track.confirmed {
+            gettext_f("Confirmed: {name}", &[("name", &name)])
+        } else {
+            gettext_f("Matched: {name}", &[("name", &name)])
+        }
+    }
+}
+
+fn add_candidates(
+    row: &adw::ExpanderRow,
+    local: &SongInfo,
+    candidates: Vec<SongInfo>,
+    sender: &Sender<Action>,
+) {
+    let mut candidates: Vec<(SongInfo, f64)> = candidates
+        .into_iter()
+        .map(|c| {
+            let score = match_score(local, &c);
+            (c, score)
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    // 第一项为“无匹配”
+    let none_check = gtk::CheckButton::new();
+    let none_row = adw::ActionRow::builder()
+        .title(gettext("No match"))
+        .activatable_widget(&none_check)
+        .build();
+    none_row.add_prefix(&none_check);
+    let local_id = local.id;
+    none_check.connect_toggled(glib::clone!(
+        #[strong]
+        sender,
+        #[weak]
+        row,
+        move |check| {
+            if check.is_active() {
+                row.set_subtitle(&gettext("No match"));
+                sender
+                    .send_blocking(Action::ConfirmLocalMatch(local_id, None))
+                    .unwrap();
+            }
+        }
+    ));
+    row.add_row(&none_row);
+
+    for (candidate, score) in candidates {
+        let check = gtk::CheckButton::new();
+        check.set_group(Some(&none_check));
+        let subtitle = format!(
+            "{} · {} · {:0>2}:{:0>2} · {:.0}%",
+            candidate.singer,
+            candidate.album,
+            candidate.duration / 1000 / 60,
+            candidate.duration / 1000 % 60,
+            score * 100.0
+        );
+        let candidate_row = adw::ActionRow::builder()
+            .title(glib::markup_escape_text(&candidate.name))
+            .subtitle(glib::markup_escape_text(&subtitle))
+            .activatable_widget(&check)
+            .build();
+        candidate_row.add_prefix(&check);
+        check.connect_toggled(glib::clone!(
+            #[strong]
+            sender,
+            #[weak]
+            row,
+            move |check| {
+                if check.is_active() {
+                    let name = format!("{} - {}", candidate.name, candidate.singer);
+                    row.set_subtitle(&glib::markup_escape_text(&gettext_f(
+                        "Confirmed: {name}",
+                        &[("name", &name)],
+                    )));
+                    sender
+                        .send_blocking(Action::ConfirmLocalMatch(
+                            local_id,
+                            Some(candidate.clone()),
+                        ))
+                        .unwrap();
+                }
+            }
+        ));
+        row.add_row(&candidate_row);
+    }
+}
+
+#[gtk::template_callbacks]
+impl LocalMatchDialog {
+    #[template_callback]
+    fn match_button_clicked_cb(&self) {
+        let sender = self.imp().sender.get().unwrap();
+        self.imp().match_button.set_sensitive(false);
+        let s = glib::SendWeakRef::from(self.downgrade());
+        sender
+            .send_blocking(Action::MatchLocalMusic(Some(Arc::new(move |tracks| {
+                if let Some(s) = s.upgrade() {
+                    s.imp().match_button.set_sensitive(true);
+                    s.update_tracks(&tracks);
+                }
+            }))))
+            .unwrap();
+    }
+}
+
+mod imp {
+
+    use adw::subclass::prelude::*;
+
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/gitee/gmg137/NeteaseCloudMusicGtk4/gtk/local-match-dialog.ui")]
+    pub struct LocalMatchDialog {
+        #[template_child]
+        pub match_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub listbox: TemplateChild<gtk::ListBox>,
+
+        pub sender: OnceCell<Sender<Action>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for LocalMatchDialog {
+        const NAME: &'static str = "LocalMatchDialog";
+        type Type = super::LocalMatchDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for LocalMatchDialog {}
+    impl WidgetImpl for LocalMatchDialog {}
+    impl AdwDialogImpl for LocalMatchDialog {}
+}
@@ -0,0 +1,227 @@
+//
+// lyrics_sync_dialog.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use adw::prelude::*;
+use async_channel::Sender;
+use gtk::{CompositeTemplate, gdk, glib, subclass::prelude::*};
+use ncm_api::SongInfo;
+use once_cell::sync::OnceCell;
+use std::{
+    cell::{Cell, RefCell},
+    sync::Arc,
+};
+
+use crate::{
+    application::Action,
+    lyrics::{Lyrics, format_timestamp},
+};
+
+glib::wrapper! {
+    pub struct LyricsSyncDialog(ObjectSubclass<imp::LyricsSyncDialog>)
+        @extends adw::Dialog, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::ShortcutManager;
+}
+
+// 逐行打点校准歌词：播放时在每行开始处点击，记录当前播放位置
+impl LyricsSyncDialog {
+    pub fn new(sender: Sender<Action>, si: SongInfo, lyrics: Lyrics) -> Self {
+        let obj: Self = glib::Object::new();
+        let imp = obj.imp();
+        imp.sender.set(sender).unwrap();
+        imp.song_info.set(si).unwrap();
+        imp.times
+            .replace(lyrics.original.lines.iter().map(|l| l.time).collect());
+        for line in lyrics.original.lines.iter() {
+            let row = adw::ActionRow::builder()
+                .title(glib::markup_escape_text(&line.text))
+                .subtitle(format_timestamp(line.time))
+                .activatable(true)
+                .build();
+            imp.listbox.append(&row);
+        }
+        imp.lyrics.replace(lyrics);
+        obj.select_line(0);
+        obj
+    }
+
+    fn select_line(&self, index: usize) {
+        let imp = self.imp();
+        imp.index.set(index);
+        let row = imp.listbox.row_at_index(index as i32);
+        imp.listbox.select_row(row.as_ref());
+        imp.tap_button.set_sensitive(row.is_some());
+        if let Some(row) = row {
+            // 保持当前行可见
+            if let Some(bounds) = row.compute_bounds(&*imp.listbox) {
+                let adj = imp.scrolled_window.vadjustment();
+                let y = bounds.y() as f64 - adj.page_size() / 2.0;
+                adj.set_value(y.max(0.0));
+            }
+        }
+    }
+
+    fn set_line_time(&self, index: usize, time: u64) {
+        let imp = self.imp();
+        if let Some(t) = imp.times.borrow_mut().get_mut(index) {
+            *t = time;
+        }
+        if let Some(row) = imp
+            .listbox
+            .row_at_index(index as i32)
+            .and_downcast::<adw::ActionRow>()
+        {
+            row.set_subtitle(&format_timestamp(time));
+        }
+    }
+
+    fn tap(&self) {
+        let imp = self.imp();
+        let index = imp.index.get();
+        if index >= imp.times.borrow().len() {
+            return;
+        }
+        self.select_line(index + 1);
+        let sender = imp.sender.get().unwrap();
+        let s = glib::SendWeakRef::from(self.downgrade());
+        sender
+            .send_blocking(Action::GetPlayPosition(Arc::new(move |time| {
+                if let Some(s) = s.upgrade() {
+                    let imp = s.imp();
+                    let old = imp.times.borrow()[index];
+                    imp.history.borrow_mut().push((index, old));
+                    s.set_line_time(index, time);
+                    imp.undo_button.set_sensitive(true);
+                    imp.save_button.set_sensitive(true);
+                }
+            })))
+            .unwrap();
+    }
+}
+
+#[gtk::template_callbacks]
+impl LyricsSyncDialog {
+    #[template_callback]
+    fn tap_button_clicked_cb(&self) {
+        self.tap();
+    }
+
+    #[template_callback]
+    fn undo_button_clicked_cb(&self) {
+        let imp = self.imp();
+        let last = imp.history.borrow_mut().pop();
+        if let Some((index, time)) = last {
+            self.set_line_time(index, time);
+            self.select_line(index);
+        }
+        let empty = imp.history.borrow().is_empty();
+        imp.undo_button.set_sensitive(!empty);
+        imp.save_button.set_sensitive(!empty);
+    }
+
+    #[template_callback]
+    fn save_button_clicked_cb(&self) {
+        let imp = self.imp();
+        let moved = imp
+            .lyrics
+            .borrow()
+            .original
+            .lines
+            .iter()
+            .zip(imp.times.borrow().iter())
+            .filter(|(l, t)| l.time != **t)
+            .map(|(l, t)| (l.time, *t))
+            .collect();
+        let si = imp.song_info.get().unwrap().to_owned();
+        imp.sender
+            .get()
+            .unwrap()
+            .send_blocking(Action::SaveSyncedLyrics(si, moved))
+            .unwrap();
+        self.close();
+    }
+
+    // 从选中的行重新开始打点
+    #[template_callback]
+    fn listbox_row_activated_cb(&self, row: &gtk::ListBoxRow) {
+        self.select_line(row.index() as usize);
+    }
+}
+
+mod imp {
+
+    use adw::subclass::prelude::*;
+
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/gitee/gmg137/NeteaseCloudMusicGtk4/gtk/lyrics-sync-dialog.ui")]
+    pub struct LyricsSyncDialog {
+        #[template_child]
+        pub undo_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub save_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub tap_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub scrolled_window: TemplateChild<gtk::ScrolledWindow>,
+        #[template_child]
+        pub listbox: TemplateChild<gtk::ListBox>,
+
+        pub sender: OnceCell<Sender<Action>>,
+        pub song_info: OnceCell<SongInfo>,
+        pub lyrics: RefCell<Lyrics>,
+        // 每行的新时间，未打点的行保持原时间
+        pub times: RefCell<Vec<u64>>,
+        // 当前等待打点的行
+        pub index: Cell<usize>,
+        // (行, 打点前的时间)，用于撤销
+        pub history: RefCell<Vec<(usize, u64)>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for LyricsSyncDialog {
+        const NAME: &'static str = "LyricsSyncDialog";
+        type Type = super::LyricsSyncDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for LyricsSyncDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+
+            // 在捕获阶段处理空格，避免被获得焦点的行或按钮消费
+            let controller = gtk::EventControllerKey::new();
+            controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+            controller.connect_key_pressed(glib::clone!(
+                #[weak]
+                obj,
+                #[upgrade_or]
+                glib::Propagation::Proceed,
+                move |_, key, _, _| {
+                    if key == gdk::Key::space {
+                        obj.tap();
+                        glib::Propagation::Stop
+                    } else {
+                        glib::Propagation::Proceed
+                    }
+                }
+            ));
+            obj.add_controller(controller);
+        }
+    }
+    impl WidgetImpl for LyricsSyncDialog {}
+    impl AdwDialogImpl for LyricsSyncDialog {}
+}
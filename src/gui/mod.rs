@@ -4,7 +4,11 @@
 // Distributed under terms of the GPL-3.0-or-later license.
 //
 
+mod cloud_upload_dialog;
+mod desktop_lyrics;
 mod discover;
+mod local_match_dialog;
+mod lyrics_sync_dialog;
 mod my_page;
 mod player_controls;
 mod playlist_lyrics;
@@ -21,7 +25,11 @@ mod theme_selector;
 mod toplist;
 mod user_menus;
 
+pub use cloud_upload_dialog::*;
+pub use desktop_lyrics::*;
 pub use discover::*;
+pub use local_match_dialog::*;
+pub use lyrics_sync_dialog::*;
 pub use my_page::*;
 pub use player_controls::*;
 pub use playlist_lyrics::*;
@@ -132,6 +132,12 @@ mod imp {
             let sender = self.sender.get().unwrap();
             sender.send_blocking(Action::ToMyPageSonglist).unwrap();
         }
+
+        #[template_callback]
+        fn local_music_cb(&self) {
+            let sender = self.sender.get().unwrap();
+            sender.send_blocking(Action::ToMyPageLocalMusic).unwrap();
+        }
     }
 
     impl ObjectImpl for MyPage {
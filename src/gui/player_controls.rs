@@ -5,33 +5,18 @@
 //
 use async_channel::Sender;
 use gettextrs::gettext;
-use gio::Settings;
-use glib::{
-    ParamSpec, ParamSpecBoolean, ParamSpecDouble, ParamSpecEnum, ParamSpecUInt, ParamSpecUInt64,
-    Value, clone, source::Priority,
-};
-use gst::{ClockTime, prelude::ObjectExt};
-use gstreamer_play::{prelude::ElementExt, *};
+use glib::{ParamSpec, ParamSpecDouble, SignalHandlerId, Value, clone};
 use gtk::{CompositeTemplate, GestureClick, glib, prelude::*, subclass::prelude::*, *};
-use log::*;
-use mpris_server::PlaybackStatus;
 use ncm_api::{SongInfo, SongList};
 use once_cell::sync::*;
 
 use crate::{
-    application::{Action, HEARTBEAT_ACTIVE, HeartbeatExtendMode},
+    application::{Action, HEARTBEAT_ACTIVE},
     audio::*,
-    model::ImageDownloadImpl,
-    path::CACHE,
+    links::NeteaseLink,
     utils::*,
 };
-use std::{
-    cell::Cell,
-    fs, path,
-    rc::Rc,
-    sync::{Arc, Mutex},
-    thread,
-};
+use std::cell::{Cell, RefCell};
 
 glib::wrapper! {
     pub struct PlayerControls(ObjectSubclass<imp::PlayerControls>)
@@ -45,186 +30,94 @@ impl PlayerControls {
         player_controls
     }
 
-    pub fn set_sender(&self, sender: Sender<Action>) {
-        self.imp().sender.set(sender).unwrap();
-        self.connect_gst_signals();
+    pub fn set_playback(&self, sender: Sender<Action>, playback: Playback) {
+        let imp = self.imp();
+        imp.sender.set(sender).unwrap();
         self.bind_click();
-        self.setup_mpris();
-    }
-
-    fn setup_settings(&self) {
-        let settings = Settings::new(crate::APP_ID);
-
-        self.imp()
-            .volume_button
-            .set_icons(&[
-                "audio-volume-muted-symbolic",
-                "audio-volume-high-symbolic",
-                "audio-volume-low-symbolic",
-                "audio-volume-medium-symbolic",
-            ]);
-
-        self.imp()
-            .settings
-            .set(settings)
-            .expect("Could not set `Settings`.");
+        self.bind_playback(&playback);
+        imp.playback.set(playback).unwrap();
     }
 
-    fn settings(&self) -> &Settings {
-        self.imp().settings.get().expect("Could not get settings.")
+    fn playback(&self) -> &Playback {
+        self.imp().playback.get().expect("Could not get playback.")
     }
 
-    fn load_settings(&self) {
-        let settings = self.settings();
-        let loop_state = settings.string("repeat-variant");
-        let loop_state = LoopsState::from_str(loop_state.as_str());
-
-        self.set_loops(loop_state);
-        self.set_volume(if settings.boolean("mute-start") {
-            0.0
-        } else {
-            settings.double("volume")
-        });
-
-        settings.bind("music-rate", self, "music-rate").build();
-    }
-
-    pub fn setup_mpris(&self) {
+    // 同步播放核心的状态到播放栏
+    fn bind_playback(&self, playback: &Playback) {
         let imp = self.imp();
-        let sender = imp.sender.get().unwrap().clone();
-        crate::MAINCONTEXT.spawn_local_with_priority(Priority::LOW, async move {
-            if let Ok(mc) = MprisController::new().await {
-                sender.send(Action::InitMpris(mc)).await.unwrap();
-            }
-        });
-    }
 
-    pub fn init_mpris(&self, mpris: MprisController) {
-        let imp = self.imp();
-        imp.mpris.set(Rc::new(mpris)).unwrap();
-
-        if let Some(mpris) = imp.mpris.get() {
-            mpris.setup_signals(self);
-
-            // 如果有恢复的播放列表，同步状态到 MPRIS
-            if imp.restored.get() {
-                if let Some(song_info) = self.get_current_song() {
-                    let play_position = self.get_play_position();
-                    crate::MAINCONTEXT.spawn_local_with_priority(
-                        Priority::LOW,
-                        clone!(
-                            #[weak]
-                            mpris,
-                            async move {
-                                mpris.update_metadata(&song_info).await.ok();
-                                mpris
-                                    .set_playback_status(PlaybackStatus::Stopped)
-                                    .await
-                                    .ok();
-                                mpris.set_position(play_position as i64);
-                                mpris.seeked(play_position as i64).await.ok();
-                            }
-                        ),
-                    );
+        playback
+            .bind_property("volume", &imp.volume_button.get(), "value")
+            .sync_create()
+            .build();
+
+        playback
+            .bind_property("like", &imp.like_button.get(), "icon_name")
+            .transform_to(|_, v: bool| {
+                Some(
+                    (if v {
+                        "starred-symbolic"
+                    } else {
+                        "non-starred-symbolic"
+                    })
+                    .to_string(),
+                )
+            })
+            .sync_create()
+            .build();
+
+        playback
+            .bind_property("playing", &imp.play_button.get(), "icon_name")
+            .transform_to(|_, v: bool| {
+                Some(
+                    (if v {
+                        "media-playback-pause-symbolic"
+                    } else {
+                        "media-playback-start-symbolic"
+                    })
+                    .to_string(),
+                )
+            })
+            .sync_create()
+            .build();
+
+        let loops_handler = playback.connect_notify_local(
+            Some("loops"),
+            clone!(
+                #[weak(rename_to = s)]
+                self,
+                move |playback, _| {
+                    s.update_loops(playback.property("loops"));
                 }
-            }
-        }
-    }
-
-    pub fn setup_debounce(&self) {
-        let imp = self.imp();
-        let debounce = Debounce::new();
-        imp.debounce.set(debounce).unwrap();
-    }
-
-    pub fn setup_player(&self) {
-        let imp = self.imp();
-        let player = Play::new(None::<PlayVideoRenderer>);
-        let player_signal = PlaySignalAdapter::new(&player);
-        let mut config = player.config();
-        config.set_user_agent(
-            "User-Agent: Mozilla/5.0 (X11; Linux x86_64; rv:100.0) Gecko/20100101 Firefox/100.0",
+            ),
         );
-        config.set_position_update_interval(250);
-        config.set_seek_accurate(true);
-        player.set_config(config).unwrap();
-        player.set_volume(0.0);
-
-        let pipeline = player.pipeline();
-
-        let flags = pipeline.property_value("flags");
-        let flags_class = glib::FlagsClass::with_type(flags.type_()).unwrap();
-        let flags = flags_class
-            .builder_with_value(flags)
-            .unwrap()
-            .set_by_nick("download")
-            .build()
-            .unwrap();
-        pipeline.set_property_from_value("flags", &flags);
-
-        imp.player.set(player).unwrap();
-        imp.player_signal.set(player_signal).unwrap();
-    }
-
-    pub fn play(&self, song_info: SongInfo) {
-        let imp = self.imp();
+        self.update_loops(playback.property("loops"));
+
+        let cover_handler = playback.connect_notify_local(
+            Some("cover"),
+            clone!(
+                #[weak(rename_to = s)]
+                self,
+                move |playback, _| {
+                    s.update_cover(&playback.property::<String>("cover"));
+                }
+            ),
+        );
+        self.update_cover(&playback.property::<String>("cover"));
 
-        let sender = imp.sender.get().unwrap();
-        sender
-            .send_blocking(Action::AddToast(gettext_f(
-                "Start playback [{name}] ...",
-                &[("name", &song_info.name)],
-            )))
-            .unwrap();
+        imp.playback_handlers
+            .replace(vec![loops_handler, cover_handler]);
 
-        let player = imp.player.get().unwrap();
-        player.stop();
-        player.set_uri(Some(&song_info.song_url));
-        player.set_volume(self.property("volume"));
-        player.play();
-
-        // 如果存在待执行的 seek 位置，在开始播放后立即定位
-        let pending = imp.pending_seek_position.get();
-        if pending > 0 {
-            imp.pending_seek_position.set(0);
-            player.seek(ClockTime::from_useconds(pending));
+        // 恢复的播放列表或窗口重新打开时显示当前歌曲
+        if let Some(song_info) = playback.get_current_song() {
+            self.init_play_info(&song_info);
+            self.update_duration(playback.property::<u64>("duration") * 10u64.pow(6));
+            self.update_position(playback.progress());
         }
-
-        self.init_play_info(song_info);
-    }
-
-    pub fn next_song(&self) {
-        let next_button = self.imp().next_button.get();
-        next_button.emit_clicked();
-    }
-
-    pub fn prev_song(&self) {
-        let prev_button = self.imp().prev_button.get();
-        prev_button.emit_clicked();
     }
 
-    pub fn toggle_play_pause(&self) {
-        let play_button = self.imp().play_button.get();
-        play_button.emit_clicked();
-    }
-
-    pub fn init_play_info(&self, song_info: SongInfo) {
+    pub fn init_play_info(&self, song_info: &SongInfo) {
         let imp = self.imp();
-        let cover_image = imp.cover_image.get();
-        let mut path_cover = CACHE.clone();
-        path_cover.push(format!("{}-songlist.jpg", song_info.album_id));
-        if path_cover.exists() {
-            cover_image.set_from_file(Some(&path_cover));
-        } else {
-            cover_image.set_icon_name(Some("image-missing-symbolic"));
-            let sender = imp.sender.get().unwrap().clone();
-            cover_image.set_from_net(
-                song_info.pic_url.to_owned(),
-                path_cover.to_owned(),
-                (140, 140),
-                &sender,
-            );
-        }
 
         let title_label = imp.title_label.get();
         title_label.set_label(&song_info.name);
@@ -232,264 +125,56 @@ impl PlayerControls {
 
         let artist_label = imp.artist_label.get();
         artist_label.set_label(&song_info.singer);
+    }
 
-        let volume = self.property("volume");
-        if let Some(mpris) = imp.mpris.get() {
-            crate::MAINCONTEXT.spawn_local_with_priority(
-                Priority::LOW,
-                clone!(
-                    #[weak]
-                    mpris,
-                    async move {
-                        if let Err(err) = mpris.update_metadata(&song_info).await {
-                            warn!("设置 MPRIS metadata 失败: {err:?}");
-                        }
-                        if let Err(err) = mpris.set_playback_status(PlaybackStatus::Playing).await {
-                            warn!("设置 MPRIS 播放状态失败: {err:?}");
-                        }
-                        if let Err(err) = mpris.set_volume(volume).await {
-                            warn!("设置 MPRIS 音量失败: {err:?}");
-                        }
-                        mpris.set_position(0);
-                        mpris.seeked(0).await.ok();
-                    }
-                ),
-            );
+    fn update_cover(&self, path: &str) {
+        let cover_image = self.imp().cover_image.get();
+        if path.is_empty() {
+            cover_image.set_icon_name(Some("image-missing-symbolic"));
+        } else {
+            cover_image.set_from_file(Some(path));
         }
     }
 
-    pub fn connect_gst_signals(&self) {
+    fn update_loops(&self, state: LoopsState) {
         let imp = self.imp();
-        let sender_ = imp.sender.get().unwrap().clone();
-        let player = imp.player.get().unwrap();
-        let player_sig = imp.player_signal.get().unwrap();
-
-        let sender = sender_.clone();
-        // need gstplay's playbin bus
-        let bus = player.pipeline().bus().unwrap();
-        bus.connect_message(Some("element"), move |_, msg| {
-            use gst::MessageView;
-            if let MessageView::Element(ele) = msg.view() {
-                if let Some(stu) = ele.structure() {
-                    if "GstCacheDownloadComplete" == stu.name() {
-                        if let Ok(loc) = stu.get::<String>("location") {
-                            sender
-                                .send_blocking(Action::GstCacheDownloadComplete(loc))
-                                .unwrap();
-                        }
-                    }
-                }
-            }
-        });
-
-        let sender = sender_.clone();
-        let old_msec: Cell<u64> = Cell::new(0);
-        player_sig.connect_position_updated(move |_, clock| {
-            if let Some(clock) = clock {
-                // mseconds -> milliseconds
-                // useconds -> microseconds
-                let msec = clock.mseconds();
-                if old_msec.get() / 500 != msec / 500 {
-                    sender
-                        .send_blocking(Action::ScaleSeekUpdate(clock.useconds()))
-                        .unwrap();
-                    old_msec.replace(msec);
-                }
-            }
-        });
-
-        let sender = sender_.clone();
-        player_sig.connect_duration_changed(move |_, clock| {
-            if let Some(clock) = clock {
-                sender
-                    .send_blocking(Action::GstDurationChanged(clock.useconds()))
-                    .unwrap();
-            }
-        });
-
-        let sender = sender_.clone();
-        player_sig.connect_end_of_stream(move |_| {
-            sender.send_blocking(Action::PlayNextSong).unwrap();
-        });
-
-        let sender = sender_.clone();
-        player_sig.connect_error(move |_, e, _| {
-            sender
-                .send_blocking(Action::AddToast(gettext_f(
-                    "Playback error:{err}",
-                    &[("err", &e.to_string())],
-                )))
-                .unwrap();
-            sender.send_blocking(Action::PlayNextSong).unwrap();
-        });
-
-        let sender = sender_.clone();
-        player_sig.connect_state_changed(move |_, state| {
-            sender
-                .send_blocking(Action::GstStateChanged(state))
-                .unwrap();
-        });
-
-        let sender = sender_.clone();
-        player_sig.connect_volume_changed(move |_, volume| {
-            sender
-                .send_blocking(Action::GstVolumeChanged(volume))
-                .unwrap();
-        });
-
-        // let sender = sender_.clone();
-        // player_sig.connect_buffering(move |_, percent| {});
+        let switch: gtk::CheckButton = match state {
+            LoopsState::Shuffle => imp.shuffle_button.get(),
+            LoopsState::None => imp.none_button.get(),
+            LoopsState::Track => imp.one_button.get(),
+            LoopsState::Playlist => imp.loop_button.get(),
+        };
+        if !switch.is_active() {
+            switch.set_active(true);
+        }
     }
 
-    // msec -> microseconds
-    pub fn scale_seek_update(&self, msec: u64) {
+    // usec -> microseconds
+    pub fn update_position(&self, usec: u64) {
         let imp = self.imp();
 
         let seek_scale = imp.seek_scale.get();
-        seek_scale.set_value(msec as f64);
+        seek_scale.set_value(usec as f64);
 
-        let sec = msec / 10u64.pow(6);
+        let sec = usec / 10u64.pow(6);
         let duration = format!("{:0>2}:{:0>2}", sec / 60, sec % 60);
         imp.progress_time_label.get().set_label(&duration);
-
-        // 定期保存播放进度（每60秒保存一次）
-        if sec > 0 && sec % 60 == 0 {
-            self.set_play_position(msec);
-            self.save_playlist();
-        }
-
-        if let Some(mpris) = self.imp().mpris.get() {
-            mpris.set_position(msec as i64);
-            crate::MAINCONTEXT.spawn_local_with_priority(
-                Priority::LOW,
-                clone!(
-                    #[weak]
-                    mpris,
-                    async move {
-                        mpris.seeked(msec as i64).await.ok();
-                    }
-                ),
-            );
-        }
-
-        let sender = imp.sender.get().unwrap();
-        if let Some(si) = self.get_current_song() {
-            // 发送更新歌词
-            sender
-                .send_blocking(Action::UpdateLyrics(si, msec / 1000))
-                .unwrap();
-        }
-
-        // 当剩余5秒时提前获取下首歌曲的播放链接。
-        let duration: u64 = self.property("duration");
-        let secp = (msec / 10u64.pow(5)) % 10;
-        if duration - sec == 5 && secp >= 5 {
-            if let Some(si) = self.get_next_song() {
-                sender.send_blocking(Action::GetSongUrl(si)).unwrap();
-            }
-        }
     }
 
-    pub fn scale_value_update(&self) {
-        let value: f64 = self.property("scale-value");
-        self.gst_position_update(value as u64);
-
-        if let Some(mpris) = self.imp().mpris.get() {
-            mpris.set_position(value as i64);
-            crate::MAINCONTEXT.spawn_local_with_priority(
-                Priority::LOW,
-                clone!(
-                    #[weak]
-                    mpris,
-                    async move {
-                        mpris.seeked(value as i64).await.ok();
-                    }
-                ),
-            );
-        }
-    }
-
-    // msec -> microseconds
-    pub fn gst_position_update(&self, msec: u64) {
+    // usec -> microseconds
+    pub fn update_duration(&self, usec: u64) {
         let imp = self.imp();
-        let player = imp.player.get().unwrap();
-        player.seek(ClockTime::from_useconds(msec));
-    }
-
-    pub fn gst_duration_changed(&self, msec: u64) {
-        let imp = self.imp();
-        let sec = msec / 10u64.pow(6);
+        let sec = usec / 10u64.pow(6);
 
         let duration = format!("{:0>2}:{:0>2}", sec / 60, sec % 60);
 
-        imp.seek_scale.set_range(0.0, msec as f64);
+        imp.seek_scale.set_range(0.0, usec as f64);
         imp.duration_label.get().set_label(&duration);
-
-        self.set_property("duration", sec);
-
-        if let Some(mpris) = imp.mpris.get() {
-            if let Some(mut si) = self.get_current_song() {
-                si.duration = msec / 1000;
-                crate::MAINCONTEXT.spawn_local_with_priority(
-                    Priority::LOW,
-                    clone!(
-                        #[weak]
-                        mpris,
-                        async move {
-                            mpris.update_metadata(&si).await.ok();
-                        }
-                    ),
-                );
-            }
-        }
     }
 
-    pub fn gst_state_changed(&self, state: PlayState) {
-        let imp = self.imp();
-        let play_button = imp.play_button.get();
-        match state {
-            PlayState::Stopped => {
-                play_button.set_icon_name("media-playback-start-symbolic");
-                // 同步播放状态，确保保存时反映实际播放情况
-                if let Ok(mut playlist) = imp.playlist.lock() {
-                    playlist.set_play_state(false);
-                }
-            }
-            PlayState::Paused => {
-                play_button.set_icon_name("media-playback-start-symbolic");
-                if let Ok(mut playlist) = imp.playlist.lock() {
-                    playlist.set_play_state(false);
-                }
-            }
-            PlayState::Playing => {
-                play_button.set_icon_name("media-playback-pause-symbolic");
-                if let Ok(mut playlist) = imp.playlist.lock() {
-                    playlist.set_play_state(true);
-                }
-            }
-            _ => (),
-        }
-    }
-
-    pub fn gst_volume_changed(&self, volume: f64) {
-        self.set_property("volume", volume);
-    }
-
-    pub fn gst_cache_download_complete(&self, loc: String) {
-        let duration: u64 = self.property("duration");
-        // 不缓存小于 30 秒时长的乐曲(vip试听)
-        if duration > 30 {
-            if let Some(si) = self.get_current_song() {
-                let rate = self.property::<u32>("music-rate");
-                let src = path::PathBuf::from(loc);
-                let dst = crate::path::get_music_cache_path(si.id, rate);
-                thread::spawn(|| {
-                    if let Err(err) = fs::copy(src, dst) {
-                        log::error!("{:?}", err);
-                    }
-                });
-            }
-        }
+    pub fn scale_value_update(&self) {
+        let value: f64 = self.property("scale-value");
+        self.playback().seek(value as u64);
     }
 
     pub fn bind_shortcut(&self) {
@@ -544,72 +229,6 @@ impl PlayerControls {
         });
     }
 
-    fn playlist_length(&self) -> usize {
-        if let Ok(playlist) = self.imp().playlist.lock() {
-            playlist.len()
-        } else {
-            0
-        }
-    }
-
-    pub fn get_list(&self) -> Vec<SongInfo> {
-        if let Ok(playlist) = self.imp().playlist.lock() {
-            playlist.get_list()
-        } else {
-            vec![]
-        }
-    }
-
-    pub fn add_song(&self, song: SongInfo) {
-        if let Ok(mut playlist) = self.imp().playlist.lock() {
-            playlist.add_song(song);
-        }
-        // 用户选择了新歌曲，清除恢复时保存的播放进度
-        self.imp().pending_seek_position.set(0);
-        self.save_playlist();
-    }
-
-    pub fn remove_song(&self, song: SongInfo) {
-        if let Some(songinfo) = self.get_current_song() {
-            if songinfo.id == song.id {
-                if self.playlist_length() > 1 {
-                    self.next_song();
-                } else {
-                    self.switch_stop();
-                }
-            }
-            if let Ok(mut playlist) = self.imp().playlist.lock() {
-                playlist.remove_song(song);
-                let sender = self.imp().sender.get().unwrap().clone();
-                if playlist.len() >= 1 {
-                    sender
-                        .send_blocking(Action::UpdatePlayListStatus(playlist.get_position()))
-                        .unwrap();
-                }
-            }
-            self.save_playlist();
-        }
-    }
-
-    pub fn add_list(&self, list: Vec<SongInfo>) {
-        let settings = self.settings();
-        let not_ignore_grey = settings.get("not-ignore-grey");
-        let list: Vec<SongInfo> = if not_ignore_grey {
-            list
-        } else {
-            list.into_iter()
-                .filter(|si| si.copyright.playable())
-                .collect()
-        };
-
-        if let Ok(mut playlist) = self.imp().playlist.lock() {
-            playlist.add_list(list);
-        }
-        // 用户选择了新歌单，清除恢复时保存的播放进度
-        self.imp().pending_seek_position.set(0);
-        self.save_playlist();
-    }
-
     // 心动模式开启/关闭时更新按钮状态（开启红心高亮）
     pub fn set_heartbeat_active(&self, active: bool) {
         if active {
@@ -621,348 +240,6 @@ impl PlayerControls {
                 .remove_css_class("heartbeat-active");
         }
     }
-
-    // 追加歌曲到播放列表末尾（过滤灰歌，去重），返回实际追加的歌曲
-    pub fn append_list(&self, list: &mut Vec<SongInfo>) -> Vec<SongInfo> {
-        let not_ignore_grey: bool = self.settings().get("not-ignore-grey");
-        if !not_ignore_grey {
-            list.retain(|si| si.copyright.playable());
-        }
-        let appending = std::mem::take(list);
-        let appended = if let Ok(mut playlist) = self.imp().playlist.lock() {
-            playlist.append_list(appending)
-        } else {
-            vec![]
-        };
-        self.save_playlist();
-        appended
-    }
-
-    // 根据 song_id 定位播放位置（心动模式追加续播前使用）
-    pub fn sync_playlist_position(&self, song_id: u64) {
-        if let Ok(mut playlist) = self.imp().playlist.lock() {
-            playlist.sync_position_with_song_id(song_id);
-        }
-    }
-
-    pub fn playlist_position(&self) -> usize {
-        if let Ok(playlist) = self.imp().playlist.lock() {
-            playlist.get_position()
-        } else {
-            0
-        }
-    }
-
-    // 从头播放列表第一首（心动模式列表循环追加失败时的回退）
-    pub fn playlist_first_restart(&self) -> Option<SongInfo> {
-        let first = if let Ok(playlist) = self.imp().playlist.lock() {
-            playlist.get_list().first().cloned()
-        } else {
-            None
-        };
-        if let Some(first) = first {
-            self.sync_playlist_position(first.id);
-            Some(first)
-        } else {
-            None
-        }
-    }
-
-    pub fn set_song_url(&self, si: SongInfo) {
-        if let Ok(mut playlist) = self.imp().playlist.lock() {
-            playlist.set_song_url(si);
-        }
-    }
-
-    pub fn save_playlist(&self) {
-        if let Ok(playlist) = self.imp().playlist.lock() {
-            playlist.save_to_file();
-        }
-    }
-
-    // 保存当前播放状态（包括播放进度）
-    pub fn save_current_state(&self) {
-        // 先更新播放进度（如果 player 可用）
-        if let Some(player) = self.imp().player.get() {
-            if let Some(pos) = player.position() {
-                self.set_play_position(pos.useconds());
-            }
-        }
-        self.save_playlist();
-    }
-
-    pub fn set_play_position(&self, position: u64) {
-        if let Ok(mut playlist) = self.imp().playlist.lock() {
-            playlist.set_play_position(position);
-        }
-    }
-
-    pub fn get_play_position(&self) -> u64 {
-        if let Ok(playlist) = self.imp().playlist.lock() {
-            playlist.get_play_position()
-        } else {
-            0
-        }
-    }
-
-    pub fn get_next_song(&self) -> Option<SongInfo> {
-        if let Ok(mut playlist) = self.imp().playlist.lock() {
-            return playlist.get_next_song().map(|s| s.to_owned());
-        }
-        None
-    }
-
-    pub fn get_prev_song(&self) -> Option<SongInfo> {
-        if let Ok(mut playlist) = self.imp().playlist.lock() {
-            return playlist.prev_song().map(|s| s.to_owned());
-        }
-        None
-    }
-
-    pub fn get_current_song(&self) -> Option<SongInfo> {
-        if let Ok(playlist) = self.imp().playlist.lock() {
-            return playlist.current_song().map(|s| s.to_owned());
-        }
-        None
-    }
-
-    pub fn has_playlist(&self) -> bool {
-        if let Ok(playlist) = self.imp().playlist.lock() {
-            return !playlist.is_empty();
-        }
-        false
-    }
-
-    pub fn restore_playlist_ui(&self) -> bool {
-        if let Some(song_info) = self.get_current_song() {
-            self.imp().restored.set(true);
-            self.init_play_info(song_info.clone());
-
-            // 设置进度条范围和时长标签（使用保存的 duration，毫秒→微秒）
-            if song_info.duration > 0 {
-                let duration_usec = song_info.duration * 1000;
-                self.imp().seek_scale.set_range(0.0, duration_usec as f64);
-                let sec = duration_usec / 10u64.pow(6);
-                let duration = format!("{:0>2}:{:0>2}", sec / 60, sec % 60);
-                self.imp().duration_label.get().set_label(&duration);
-                self.set_property("duration", sec);
-            }
-
-            let play_position = self.get_play_position();
-            if play_position > 0 {
-                // 直接更新UI，不调用 scale_seek_update（避免触发歌词更新、MPRIS通知等副作用）
-                self.imp().seek_scale.set_value(play_position as f64);
-                let sec = play_position / 10u64.pow(6);
-                let duration = format!("{:0>2}:{:0>2}", sec / 60, sec % 60);
-                self.imp().progress_time_label.get().set_label(&duration);
-
-                // 保存待执行的seek位置（播放时再执行）
-                self.imp().pending_seek_position.set(play_position);
-            }
-
-            return true;
-        }
-        false
-    }
-
-    // 尝试播放恢复的歌曲（URL已过期，需要重新获取）
-    // 返回 true 表示已处理恢复情况，调用方应直接返回
-    pub fn try_play_restored_song(&self) -> bool {
-        let imp = self.imp();
-        if imp.restored.replace(false) {
-            if let Some(sender) = imp.sender.get() {
-                if let Ok(playlist) = imp.playlist.lock() {
-                    if let Some(song_info) = playlist.current_song() {
-                        imp.play_button
-                            .get()
-                            .set_icon_name("media-playback-pause-symbolic");
-                        sender
-                            .send_blocking(Action::Play(song_info.to_owned()))
-                            .unwrap();
-                        return true;
-                    }
-                }
-            }
-        }
-        false
-    }
-
-    pub fn switch_play(&self) {
-        let imp = self.imp();
-
-        // 从文件恢复的播放列表，URL已过期，需要重新获取
-        if self.try_play_restored_song() {
-            return;
-        }
-
-        let player = imp.player.get().unwrap();
-        player.play();
-
-        if let Some(mpris) = imp.mpris.get() {
-            crate::MAINCONTEXT.spawn_local_with_priority(
-                Priority::LOW,
-                clone!(
-                    #[weak]
-                    mpris,
-                    async move {
-                        if let Err(err) = mpris.set_playback_status(PlaybackStatus::Playing).await {
-                            warn!("设置 MPRIS 播放状态失败: {err:?}");
-                        }
-                    }
-                ),
-            );
-        }
-    }
-
-    pub fn switch_pause(&self) {
-        let imp = self.imp();
-        let player = imp.player.get().unwrap();
-        player.pause();
-
-        if let Some(mpris) = imp.mpris.get() {
-            crate::MAINCONTEXT.spawn_local_with_priority(
-                Priority::LOW,
-                clone!(
-                    #[weak]
-                    mpris,
-                    async move {
-                        if let Err(err) = mpris.set_playback_status(PlaybackStatus::Paused).await {
-                            warn!("设置 MPRIS 播放状态失败: {err:?}");
-                        }
-                    }
-                ),
-            );
-        }
-    }
-
-    pub fn switch_stop(&self) {
-        let imp = self.imp();
-        let player = imp.player.get().unwrap();
-        player.stop();
-
-        if let Some(mpris) = imp.mpris.get() {
-            crate::MAINCONTEXT.spawn_local_with_priority(
-                Priority::LOW,
-                clone!(
-                    #[weak]
-                    mpris,
-                    async move {
-                        if let Err(err) = mpris.set_playback_status(PlaybackStatus::Stopped).await {
-                            warn!("设置 MPRIS 播放状态失败: {err:?}");
-                        }
-                    }
-                ),
-            );
-        }
-    }
-
-    // these set funcs will be called from mpris
-    pub fn set_loops(&self, state: LoopsState) {
-        if self.property::<LoopsState>("loops") != state {
-            self.set_property("loops", state);
-        }
-    }
-
-    pub fn set_shuffle(&self, shuffle: bool) {
-        let imp = self.imp();
-        match shuffle {
-            true => self.set_loops(LoopsState::Shuffle),
-            false => {
-                if let Ok(status) = imp.mpris.get().unwrap().get_loop_status() {
-                    self.set_loops(status);
-                };
-            }
-        }
-    }
-
-    pub fn set_volume(&self, value: f64) {
-        let old: f64 = self.property("volume");
-        if (old * 100.0).round() as i64 != (value * 100.0).round() as i64 {
-            self.set_property("volume", value);
-            let player = self.imp().player.get().unwrap();
-            player.set_volume(value);
-            if self.imp().sender.get().is_none() {
-                // if sender is not ready, just return
-                return;
-            }
-            let debounce = self.imp().debounce.get().unwrap();
-            let sender = self.imp().sender.get().unwrap().clone();
-            debounce.debounce(1, move || {
-                let _ = sender.send_blocking(Action::PersistVolume(value));
-            });
-        }
-    }
-
-    pub fn persist_volume(&self, value: f64) {
-        let settings = self.imp().settings.get().unwrap();
-        settings.set_double("volume", value).unwrap();
-    }
-
-    pub fn setup_notify_connect(&self) {
-        self.connect_notify(None, move |s, p| {
-            s.property_changed(p.name(), p);
-        });
-    }
-
-    fn property_changed(&self, name: &str, _: &ParamSpec) {
-        let imp = self.imp();
-        match name {
-            "volume" => {
-                let value = self.property::<f64>("volume");
-                self.imp().volume_button.get().set_value(value);
-                if let Some(mpris) = imp.mpris.get() {
-                    crate::MAINCONTEXT.spawn_local_with_priority(
-                        Priority::LOW,
-                        clone!(
-                            #[weak]
-                            mpris,
-                            async move {
-                                if let Err(err) = mpris.set_volume(value).await {
-                                    warn!("设置 MPRIS 音量失败: {err:?}");
-                                }
-                            }
-                        ),
-                    );
-                }
-            }
-            "loops" => {
-                let value = self.property::<LoopsState>("loops");
-                let switch: gtk::CheckButton = match value {
-                    LoopsState::Shuffle => imp.shuffle_button.get(),
-                    LoopsState::None => imp.none_button.get(),
-                    LoopsState::Track => imp.one_button.get(),
-                    LoopsState::Playlist => imp.loop_button.get(),
-                };
-                if !switch.is_active() {
-                    switch.set_active(true);
-                }
-
-                if let Some(mpris) = imp.mpris.get() {
-                    crate::MAINCONTEXT.spawn_local_with_priority(
-                        Priority::LOW,
-                        clone!(
-                            #[weak]
-                            mpris,
-                            async move {
-                                if let Err(err) = mpris.set_loop_status(value).await {
-                                    warn!("设置 MPRIS 循环状态失败: {err:?}");
-                                }
-                            }
-                        ),
-                    );
-                }
-
-                if let Ok(mut playlist) = imp.playlist.lock() {
-                    playlist.set_loops(value);
-                }
-
-                self.settings()
-                    .set_string("repeat-variant", value.to_string().as_str())
-                    .unwrap();
-            }
-            _ => (),
-        }
-    }
 }
 
 impl Default for PlayerControls {
@@ -975,13 +252,13 @@ impl Default for PlayerControls {
 impl PlayerControls {
     #[template_callback]
     fn volume_cb(&self, adj: Adjustment) {
-        self.set_volume(adj.value());
+        self.playback().set_volume(adj.value());
     }
 
     #[template_callback]
     fn cover_clicked_cb(&self) {
         let sender = self.imp().sender.get().unwrap().clone();
-        if let Some(songinfo) = self.get_current_song() {
+        if let Some(songinfo) = self.playback().get_current_song() {
             if songinfo.album_id != 0 {
                 let songlist = SongList {
                     id: songinfo.album_id,
@@ -1001,24 +278,33 @@ impl PlayerControls {
 
     #[template_callback]
     fn title_clicked_cb(&self) {
-        if let Some(songinfo) = self.get_current_song() {
-            let sender = self.imp().sender.get().unwrap().clone();
-            let clipboard = self.clipboard();
-            let share = gettext_f(
-                "https://music.163.com/song?id={id}\nsong:{name}\nsinger:{singer}",
-                &[
-                    ("id", &songinfo.id.to_string()),
-                    ("name", &songinfo.name),
-                    ("singer", &songinfo.singer),
-                ],
-            );
-            clipboard.set_text(&share);
+        self.share_current_song();
+    }
+
+    // 分享当前歌曲及播放位置，本地歌曲需已匹配网易云歌曲
+    fn share_current_song(&self) {
+        let sender = self.imp().sender.get().unwrap().clone();
+        let playback = self.playback().clone();
+        let Some(song_info) = playback.get_current_song() else {
+            return;
+        };
+        let Some(id) = netease_song_id(&song_info) else {
             sender
                 .send_blocking(Action::AddToast(gettext(
-                    "Copied song information to the clipboard!",
+                    "This local song is not matched yet!",
                 )))
                 .unwrap();
-        }
+            return;
+        };
+        let title = format!("{} - {}", song_info.name, song_info.singer);
+        let position = playback.get_position() / 1000;
+        sender
+            .send_blocking(Action::ShareLink(
+                NeteaseLink::Song(id),
+                title,
+                Some(position),
+            ))
+            .unwrap();
     }
 }
 
@@ -1026,8 +312,6 @@ mod imp {
 
     use gst::glib::Propagation;
 
-    use crate::utils::Debounce;
-
     use super::*;
 
     #[derive(Debug, Default, CompositeTemplate)]
@@ -1071,29 +355,13 @@ mod imp {
         #[template_child(id = "like_button")]
         pub like_button: TemplateChild<Button>,
 
-        pub settings: OnceCell<Settings>,
         pub sender: OnceCell<Sender<Action>>,
-        pub player: OnceCell<gstreamer_play::Play>,
-        pub player_signal: OnceCell<gstreamer_play::PlaySignalAdapter>,
-        pub playlist: Arc<Mutex<PlayList>>,
-        pub mpris: OnceCell<Rc<MprisController>>,
-        pub debounce: OnceCell<Debounce>,
-
-        volume: Cell<f64>,
-        loops: Cell<LoopsState>,
-        music_rate: Cell<u32>,
-        duration: Cell<u64>,
-
-        like: Cell<bool>,
+        pub playback: OnceCell<Playback>,
+        // 连接到播放核心的信号，销毁时断开
+        pub playback_handlers: RefCell<Vec<SignalHandlerId>>,
 
         // 播放条拖动结束时的值
         scale_value: Cell<f64>,
-
-        // 是否从文件恢复播放列表（URL可能已过期），恢复后首次播放需重新获取URL
-        pub restored: Cell<bool>,
-
-        // 恢复时保存的播放位置（微秒），等播放开始后再执行 seek
-        pub pending_seek_position: Cell<u64>,
     }
 
     #[glib::object_subclass]
@@ -1117,128 +385,17 @@ mod imp {
     impl PlayerControls {
         #[template_callback]
         fn prev_button_clicked_cb(&self) {
-            let sender = self.sender.get().unwrap().clone();
-            if let Ok(mut playlist) = self.playlist.lock() {
-                if let Some(song_info) = playlist.prev_song() {
-                    let song_info = song_info.to_owned();
-                    sender
-                        .send_blocking(Action::Play(song_info.to_owned()))
-                        .unwrap();
-                    sender
-                        .send_blocking(Action::UpdatePlayListStatus(playlist.get_position()))
-                        .unwrap();
-                    return;
-                }
-            }
-            sender
-                .send_blocking(Action::AddToast(gettext("No more songs！")))
-                .unwrap();
+            self.obj().playback().prev_song();
         }
 
         #[template_callback]
-        fn play_button_clicked_cb(&self, button: Button) {
-            let player = self.player.get().unwrap();
-            if button
-                .icon_name()
-                .unwrap()
-                .eq("media-playback-start-symbolic")
-            {
-                // 从文件恢复的播放列表，URL已过期，需要重新获取
-                if self.obj().try_play_restored_song() {
-                    return;
-                }
-                player.play();
-                button.set_icon_name("media-playback-pause-symbolic");
-                if let Some(mpris) = self.mpris.get() {
-                    crate::MAINCONTEXT.spawn_local_with_priority(
-                        Priority::LOW,
-                        clone!(
-                            #[weak]
-                            mpris,
-                            async move {
-                                if let Err(err) =
-                                    mpris.set_playback_status(PlaybackStatus::Playing).await
-                                {
-                                    warn!("设置 MPRIS 播放状态失败: {err:?}");
-                                }
-                            }
-                        ),
-                    );
-                }
-            } else {
-                player.pause();
-                button.set_icon_name("media-playback-start-symbolic");
-                if let Some(mpris) = self.mpris.get() {
-                    crate::MAINCONTEXT.spawn_local_with_priority(
-                        Priority::LOW,
-                        clone!(
-                            #[weak]
-                            mpris,
-                            async move {
-                                if let Err(err) =
-                                    mpris.set_playback_status(PlaybackStatus::Paused).await
-                                {
-                                    warn!("设置 MPRIS 播放状态失败: {err:?}");
-                                }
-                            }
-                        ),
-                    );
-                }
-            }
+        fn play_button_clicked_cb(&self) {
+            self.obj().playback().toggle_play_pause();
         }
 
         #[template_callback]
         fn next_button_clicked_cb(&self) {
-            let sender = self.sender.get().unwrap().clone();
-            let (loops, pos_before, len) = if let Ok(playlist) = self.playlist.lock() {
-                (playlist.get_loops(), playlist.get_position(), playlist.len())
-            } else {
-                (LoopsState::None, 0, 0)
-            };
-            // 心动模式 + 列表循环播到最后一首：不回绕，追加新的心动歌曲后继续播放
-            if *HEARTBEAT_ACTIVE.lock().unwrap()
-                && loops == LoopsState::Playlist
-                && len > 0
-                && pos_before + 1 == len
-            {
-                let si = {
-                    if let Ok(playlist) = self.playlist.lock() {
-                        playlist.current_song().map(|s| s.to_owned())
-                    } else {
-                        None
-                    }
-                };
-                if let Some(si) = si {
-                    sender
-                        .send_blocking(Action::HeartbeatExtend(si, HeartbeatExtendMode::ListLoop))
-                        .unwrap();
-                    return;
-                }
-            }
-            if let Ok(mut playlist) = self.playlist.lock() {
-                if let Some(song_info) = playlist.next_song() {
-                    let song_info = song_info.to_owned();
-                    sender
-                        .send_blocking(Action::Play(song_info.to_owned()))
-                        .unwrap();
-                    sender
-                        .send_blocking(Action::UpdatePlayListStatus(playlist.get_position()))
-                        .unwrap();
-                    return;
-                }
-                if loops == LoopsState::None {
-                    if let Some(si) = playlist.current_song().map(|s| s.to_owned()) {
-                        // 不循环模式播完：追加心动歌曲并自动续播
-                        sender
-                            .send_blocking(Action::HeartbeatExtend(si, HeartbeatExtendMode::ListEnd))
-                            .unwrap();
-                        return;
-                    }
-                }
-            }
-            sender
-                .send_blocking(Action::AddToast(gettext("No more songs！")))
-                .unwrap();
+            self.obj().playback().next_song();
         }
 
         #[template_callback]
@@ -1255,13 +412,9 @@ mod imp {
         #[template_callback]
         fn moved_button_cb(&self) {
             let sender = self.sender.get().unwrap().clone();
-            if let Ok(playlist) = self.playlist.lock() {
-                if let Some(song_info) = playlist.current_song() {
-                    sender
-                        .send_blocking(Action::Moved(song_info.clone()))
-                        .unwrap();
-                    return;
-                }
+            if let Some(song_info) = self.obj().playback().get_current_song() {
+                sender.send_blocking(Action::Moved(song_info)).unwrap();
+                return;
             }
             sender
                 .send_blocking(Action::AddToast(gettext("Intelligent mode failure！")))
@@ -1271,25 +424,39 @@ mod imp {
         #[template_callback]
         fn like_button_cb(&self) {
             let sender = self.sender.get().unwrap().clone();
-            if let Ok(playlist) = self.playlist.lock() {
-                if let Some(song_info) = playlist.current_song() {
-                    sender
-                        .send_blocking(Action::LikeSong(song_info.id, !self.like.get(), None))
-                        .unwrap();
-                    return;
+            let playback = self.obj().playback().clone();
+            if let Some(song_info) = playback.get_current_song() {
+                match netease_song_id(&song_info) {
+                    Some(id) => {
+                        let like = playback.property::<bool>("like");
+                        sender
+                            .send_blocking(Action::LikeSong(id, !like, None))
+                            .unwrap()
+                    }
+                    None => sender
+                        .send_blocking(Action::AddToast(gettext(
+                            "This local song is not matched yet!",
+                        )))
+                        .unwrap(),
                 }
+                return;
             }
             sender
                 .send_blocking(Action::AddToast(gettext("Collection failure！")))
                 .unwrap();
         }
 
+        #[template_callback]
+        fn share_button_cb(&self) {
+            self.obj().share_current_song();
+        }
+
         #[template_callback]
         fn repeat_none_cb(&self) {
             self.repeat_image
                 .set_icon_name(Some("media-playlist-consecutive-symbolic"));
 
-            self.obj().set_loops(LoopsState::None);
+            self.obj().playback().set_loops(LoopsState::None);
         }
 
         #[template_callback]
@@ -1297,7 +464,7 @@ mod imp {
             self.repeat_image
                 .set_icon_name(Some("media-playlist-repeat-song-symbolic"));
 
-            self.obj().set_loops(LoopsState::Track);
+            self.obj().playback().set_loops(LoopsState::Track);
         }
 
         #[template_callback]
@@ -1305,7 +472,7 @@ mod imp {
             self.repeat_image
                 .set_icon_name(Some("media-playlist-repeat-symbolic"));
 
-            self.obj().set_loops(LoopsState::Playlist);
+            self.obj().playback().set_loops(LoopsState::Playlist);
         }
 
         #[template_callback]
@@ -1313,24 +480,22 @@ mod imp {
             self.repeat_image
                 .set_icon_name(Some("media-playlist-shuffle-symbolic"));
 
-            self.obj().set_loops(LoopsState::Shuffle);
+            self.obj().playback().set_loops(LoopsState::Shuffle);
         }
 
         #[template_callback]
         fn playlist_lyrics_cb(&self) {
-            if let Ok(playlist) = self.playlist.lock() {
-                let current_song = playlist
-                    .current_song()
-                    .unwrap_or(&crate::utils::empty_song_info())
-                    .to_owned();
-                let sender = self.sender.get().unwrap().clone();
-                sender
-                    .send_blocking(Action::ToPlayListLyricsPage(
-                        playlist.get_list(),
-                        current_song,
-                    ))
-                    .unwrap();
-            }
+            let playback = self.obj().playback().clone();
+            let current_song = playback
+                .get_current_song()
+                .unwrap_or_else(crate::utils::empty_song_info);
+            let sender = self.sender.get().unwrap().clone();
+            sender
+                .send_blocking(Action::ToPlayListLyricsPage(
+                    playback.get_list(),
+                    current_song,
+                ))
+                .unwrap();
         }
     }
 
@@ -1338,73 +503,29 @@ mod imp {
         fn constructed(&self) {
             let obj = self.obj();
             self.parent_constructed();
-            // 尝试从文件加载保存的播放列表（若无文件则创建空列表）
-            *self.playlist.lock().unwrap() = PlayList::load_from_file();
 
-            obj.setup_player();
-            obj.setup_settings();
-
-            obj.setup_notify_connect();
-
-            obj.setup_debounce();
+            self.volume_button.get().set_icons(&[
+                "audio-volume-muted-symbolic",
+                "audio-volume-high-symbolic",
+                "audio-volume-low-symbolic",
+                "audio-volume-medium-symbolic",
+            ]);
 
-            obj.load_settings();
             obj.bind_shortcut();
 
             load_css();
             // 恢复心动模式按钮状态
             obj.set_heartbeat_active(*HEARTBEAT_ACTIVE.lock().unwrap());
-
-            obj.bind_property("like", &self.like_button.get(), "icon_name")
-                .transform_to(|_, v: bool| {
-                    Some(
-                        (if v {
-                            "starred-symbolic"
-                        } else {
-                            "non-starred-symbolic"
-                        })
-                        .to_string(),
-                    )
-                })
-                .build();
         }
 
         fn properties() -> &'static [ParamSpec] {
-            static PROPERTIES: Lazy<Vec<ParamSpec>> = Lazy::new(|| {
-                vec![
-                    ParamSpecDouble::builder("volume").build(),
-                    ParamSpecEnum::builder::<LoopsState>("loops").build(),
-                    ParamSpecUInt::builder("music-rate").build(),
-                    ParamSpecUInt64::builder("duration").build(),
-                    ParamSpecBoolean::builder("like").readwrite().build(),
-                    ParamSpecDouble::builder("scale-value").readwrite().build(),
-                ]
-            });
+            static PROPERTIES: Lazy<Vec<ParamSpec>> =
+                Lazy::new(|| vec![ParamSpecDouble::builder("scale-value").readwrite().build()]);
             PROPERTIES.as_ref()
         }
 
         fn set_property(&self, _id: usize, value: &Value, pspec: &ParamSpec) {
             match pspec.name() {
-                "volume" => {
-                    let input_number = value.get().expect("The value needs to be of type `f64`.");
-                    self.volume.replace(input_number);
-                }
-                "loops" => {
-                    let val = value.get().unwrap();
-                    self.loops.replace(val);
-                }
-                "music-rate" => {
-                    let val = value.get().unwrap();
-                    self.music_rate.replace(val);
-                }
-                "duration" => {
-                    let val = value.get().unwrap();
-                    self.duration.replace(val);
-                }
-                "like" => {
-                    let like = value.get().expect("The value needs to be of type `bool`.");
-                    self.like.replace(like);
-                }
                 "scale-value" => {
                     let scale_value = value.get().expect("The value needs to be of type `f64`.");
                     self.scale_value.replace(scale_value);
@@ -1415,23 +536,18 @@ mod imp {
 
         fn property(&self, _id: usize, pspec: &ParamSpec) -> Value {
             match pspec.name() {
-                "volume" => self.volume.get().to_value(),
-                "loops" => self.loops.get().to_value(),
-                "music-rate" => self.music_rate.get().to_value(),
-                "duration" => self.duration.get().to_value(),
-                "like" => self.like.get().to_value(),
                 "scale-value" => self.scale_value.get().to_value(),
                 n => unimplemented!("{}", n),
             }
         }
 
         fn dispose(&self) {
-            let obj = self.obj();
-            obj.settings()
-                .set_double("volume", obj.property("volume"))
-                .unwrap();
-            // 程序退出时保存当前播放状态（包括播放进度）
-            obj.save_current_state();
+            // 播放核心比窗口存活更久，断开信号避免残留
+            if let Some(playback) = self.playback.get() {
+                for handler in self.playback_handlers.take() {
+                    playback.disconnect(handler);
+                }
+            }
         }
     }
     impl WidgetImpl for PlayerControls {}
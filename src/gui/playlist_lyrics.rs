@@ -5,20 +5,27 @@
 //
 use adw::subclass::prelude::BinImpl;
 use async_channel::Sender;
-use glib::{closure_local, ParamSpec, Value};
-use gtk::{glib, prelude::*, subclass::prelude::*, CompositeTemplate, *};
-use log::warn;
+use gettextrs::gettext;
+use gio::{Settings, SettingsBindFlags};
+use glib::{clone, closure_local, ParamSpec, Value};
+use gtk::{gio, glib, prelude::*, subclass::prelude::*, CompositeTemplate, *};
 use ncm_api::SongInfo;
 use once_cell::sync::Lazy;
 use once_cell::sync::OnceCell;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use crate::{
     application::Action,
     gui::{songlist_row::SonglistRow, songlist_view::SongListView},
+    lyrics::{Lyrics, WordLine},
 };
 
+// 播放时两次进度更新之间最多推算的时间（毫秒）
+const MAX_EXTRAPOLATE: u64 = 500;
+// 每次调整歌词偏移的步长（毫秒）
+const OFFSET_STEP: i64 = 500;
+
 glib::wrapper! {
     pub struct PlayListLyricsPage(ObjectSubclass<imp::PlayListLyricsPage>)
         @extends adw::Bin, Widget, Paned,
@@ -101,8 +108,25 @@ impl PlayListLyricsPage {
         buffer.set_text(text);
     }
 
-    pub fn update_lyrics(&self, lyrics: Vec<(u64, String)>) {
-        let buffer = self.imp().buffer.get();
+    pub fn update_lyrics(&self, lyrics: Lyrics) {
+        let imp = self.imp();
+        imp.word_lines.replace(lyrics.words.clone());
+        self.set_offset(lyrics.original.offset);
+        imp.lyrics.replace(lyrics);
+        self.render_lyrics();
+    }
+
+    // 按设置显示原文、翻译与罗马音
+    fn render_lyrics(&self) {
+        let imp = self.imp();
+        let settings = imp.settings.get().unwrap();
+        imp.current_line.set(-1);
+        let lyrics = imp.lyrics.borrow().to_lines(
+            settings.boolean("lyrics-show-original"),
+            settings.boolean("lyrics-show-translation"),
+            settings.boolean("lyrics-show-romanization"),
+        );
+        let buffer = imp.buffer.get();
         buffer.set_text(
             &lyrics
                 .iter()
@@ -114,6 +138,43 @@ impl PlayListLyricsPage {
         *current_lyrics = lyrics;
     }
 
+    fn setup_settings(&self) {
+        let imp = self.imp();
+        let settings = Settings::new(crate::APP_ID);
+        for (key, button) in [
+            ("lyrics-show-original", imp.original_button.get()),
+            ("lyrics-show-translation", imp.translation_button.get()),
+            ("lyrics-show-romanization", imp.romanization_button.get()),
+        ] {
+            settings
+                .bind(key, &button, "active")
+                .flags(SettingsBindFlags::DEFAULT)
+                .build();
+            settings.connect_changed(
+                Some(key),
+                clone!(
+                    #[weak(rename_to = s)]
+                    self,
+                    move |_, _| {
+                        s.render_lyrics();
+                        s.update_lyrics_highlight(s.imp().last_time.get());
+                    }
+                ),
+            );
+        }
+        imp.settings.set(settings).unwrap();
+    }
+
+    // 正值表示歌词提前显示
+    fn set_offset(&self, offset: i64) {
+        let label = if offset == 0 {
+            gettext("Offset")
+        } else {
+            format!("{:+.1}s", offset as f64 / 1000.0)
+        };
+        self.imp().offset_label.set_label(&label);
+    }
+
     pub fn update_lyrics_highlight(&self, time: u64) {
         let lyrics_text_view = self.imp().lyrics_text_view.get();
         let lyrics = self.imp().current_lyrics.read().unwrap().clone();
@@ -125,6 +186,19 @@ impl PlayListLyricsPage {
         let (start, end) = playing_indexes.unwrap();
         let center_mark = self.set_lyrics_highlight(start as i32, end as i32);
 
+        let imp = self.imp();
+        let buffer = imp.buffer.get();
+        buffer.remove_tag(
+            &imp.unfilled_text_tag.get(),
+            &buffer.start_iter(),
+            &buffer.end_iter(),
+        );
+        imp.current_line.set(start as i32);
+        imp.filled.set(None);
+        imp.last_time.set(time);
+        imp.last_tick.set(glib::monotonic_time());
+        self.update_word_fill(time);
+
         if let Some(mark) = center_mark {
             if *(self.imp().scrolled.lock().unwrap()) == 0 {
                 lyrics_text_view.scroll_to_mark(&mark, 0.0, true, 0.0, 0.5);
@@ -180,11 +254,108 @@ impl PlayListLyricsPage {
         mark_to_return
     }
 
+    // 逐字歌词：当前行中未唱到的部分使用 unfilled_text_tag
+    fn update_word_fill(&self, time: u64) {
+        let imp = self.imp();
+        let line = imp.current_line.get();
+        if line < 0 {
+            return;
+        }
+        let word_lines = imp.word_lines.borrow();
+        let Some(word_line) = find_word_line(&word_lines, time) else {
+            return;
+        };
+        let filled = word_line.filled_chars(time) as i32;
+        if imp.filled.get() == Some((line, filled)) {
+            return;
+        }
+        imp.filled.set(Some((line, filled)));
+
+        let buffer = imp.buffer.get();
+        let Some(start) = buffer.iter_at_line(line) else {
+            return;
+        };
+        let mut end = start;
+        if !start.ends_line() {
+            end.forward_to_line_end();
+        }
+        // 行文字与逐字歌词不一致时仅保留整行高亮
+        if buffer.text(&start, &end, false).trim() != word_line.text().trim() {
+            return;
+        }
+        let unfilled_text_tag = imp.unfilled_text_tag.get();
+        buffer.remove_tag(&unfilled_text_tag, &buffer.start_iter(), &buffer.end_iter());
+        let mut fill_end = start;
+        fill_end.forward_chars(filled);
+        if fill_end.line() == line && fill_end.offset() < end.offset() {
+            buffer.apply_tag(&unfilled_text_tag, &fill_end, &end);
+        }
+    }
+
+    // 播放时添加逐帧回调以平滑逐字填充，暂停或停止时移除并回到实际进度
+    pub fn set_playing(&self, playing: bool) {
+        let imp = self.imp();
+        if !playing {
+            if let Some(id) = imp.word_tick.take() {
+                id.remove();
+            }
+            imp.last_tick.set(glib::monotonic_time());
+            self.update_word_fill(imp.last_time.get());
+            return;
+        }
+        if imp.word_tick.borrow().is_some() {
+            return;
+        }
+        // 进度更新之间的时间从恢复播放时起算
+        imp.last_tick.set(glib::monotonic_time());
+        let id = imp.lyrics_text_view.add_tick_callback(clone!(
+            #[weak(rename_to = s)]
+            self,
+            #[upgrade_or]
+            glib::ControlFlow::Break,
+            move |_, _| {
+                let imp = s.imp();
+                if imp.current_line.get() >= 0 && !imp.word_lines.borrow().is_empty() {
+                    let elapsed = (glib::monotonic_time() - imp.last_tick.get()) / 1000;
+                    let elapsed = (elapsed.max(0) as u64).min(MAX_EXTRAPOLATE);
+                    s.update_word_fill(imp.last_time.get() + elapsed);
+                }
+                glib::ControlFlow::Continue
+            }
+        ));
+        imp.word_tick.replace(Some(id));
+    }
+
     pub fn switch_row(&self, index: i32) {
         self.imp().songs_list.mark_new_row_playing(index, false);
     }
 }
 
+#[gtk::template_callbacks]
+impl PlayListLyricsPage {
+    #[template_callback]
+    fn offset_earlier_clicked_cb(&self) {
+        let sender = self.imp().sender.get().unwrap();
+        sender
+            .send_blocking(Action::AdjustLyricsOffset(OFFSET_STEP))
+            .unwrap();
+    }
+
+    #[template_callback]
+    fn offset_later_clicked_cb(&self) {
+        let sender = self.imp().sender.get().unwrap();
+        sender
+            .send_blocking(Action::AdjustLyricsOffset(-OFFSET_STEP))
+            .unwrap();
+    }
+
+    #[template_callback]
+    fn sync_clicked_cb(&self) {
+        let sender = self.imp().sender.get().unwrap();
+        sender.send_blocking(Action::ShowLyricsSyncDialog).unwrap();
+    }
+}
+
 impl Default for PlayListLyricsPage {
     fn default() -> Self {
         Self::new()
@@ -212,10 +383,30 @@ mod imp {
         pub highlight_text_tag: TemplateChild<TextTag>,
         #[template_child]
         pub uttered_text_tag: TemplateChild<TextTag>,
+        #[template_child]
+        pub unfilled_text_tag: TemplateChild<TextTag>,
+        #[template_child]
+        pub offset_label: TemplateChild<Label>,
+        #[template_child]
+        pub original_button: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub translation_button: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub romanization_button: TemplateChild<ToggleButton>,
+        pub settings: OnceCell<Settings>,
+        pub lyrics: RefCell<Lyrics>,
         pub(crate) scrolled: Arc<Mutex<usize>>,
         pub playlist: Rc<RefCell<Vec<SongInfo>>>,
         pub sender: OnceCell<Sender<Action>>,
         pub current_lyrics: Arc<RwLock<Vec<(u64, String)>>>,
+        // 逐字歌词及其填充状态
+        pub word_lines: RefCell<Vec<WordLine>>,
+        pub current_line: Cell<i32>,
+        pub filled: Cell<Option<(i32, i32)>>,
+        pub last_time: Cell<u64>,
+        pub last_tick: Cell<i64>,
+        // 逐帧推算回调，仅在播放时存在
+        pub word_tick: RefCell<Option<gtk::TickCallbackId>>,
     }
 
     #[glib::object_subclass]
@@ -226,6 +417,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+            klass.bind_template_instance_callbacks();
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -235,8 +427,10 @@ mod imp {
 
     impl ObjectImpl for PlayListLyricsPage {
         fn constructed(&self) {
-            let _obj = self.obj();
+            let obj = self.obj();
             self.parent_constructed();
+            obj.setup_settings();
+            obj.set_offset(0);
         }
 
         fn properties() -> &'static [ParamSpec] {
@@ -258,24 +452,18 @@ mod imp {
     impl BinImpl for PlayListLyricsPage {}
 }
 
-fn get_playing_indexes(mut lyrics: Vec<(u64, String)>, time: u64) -> Option<(usize, usize)> {
-    // 填充空白行，以使用window(3)方法时可以到达最后一行
-    lyrics.push((3600000000, "".to_string()));
-    lyrics.push((3600000000, "".to_string()));
-    for (i, lyr) in lyrics.windows(3).enumerate() {
-        if (time >= lyr[0].0 && time < lyr[1].0)
-            || lyr[0].0 == lyr[1].0 && time >= lyr[0].0 && time < lyr[2].0
-        {
-            if lyr[0].0 == lyr[1].0 {
-                // 也包含翻译行
-                warn!("also has translation {}", i);
-                return Some((i, i + 1));
-            } else {
-                warn!("no translation {}", i);
-                // 不包含翻译行
-                return Some((i, i));
-            }
-        }
-    }
-    None
+// 查找指定时间正在播放或最近唱完的逐字歌词行
+fn find_word_line(word_lines: &[WordLine], time: u64) -> Option<&WordLine> {
+    word_lines.iter().rev().find(|l| l.time <= time)
+}
+
+// 返回当前时间需要高亮的行范围，同一时间的原文、罗马音与翻译一起高亮
+fn get_playing_indexes(lyrics: Vec<(u64, String)>, time: u64) -> Option<(usize, usize)> {
+    let end = lyrics.iter().rposition(|(t, _)| *t <= time)?;
+    let line_time = lyrics[end].0;
+    let start = lyrics[..end]
+        .iter()
+        .rposition(|(t, _)| *t != line_time)
+        .map_or(0, |i| i + 1);
+    Some((start, end))
 }
@@ -4,6 +4,7 @@
 // Distributed under terms of the GPL-3.0-or-later license.
 //
 
+use adw::prelude::EntryRowExt;
 use gio::Settings;
 use gtk::gio::SettingsBindFlags;
 use gtk::{glib, prelude::*, subclass::prelude::*, CompositeTemplate, *};
@@ -57,6 +58,12 @@ impl NeteaseCloudMusicGtk4Preferences {
             .flags(SettingsBindFlags::DEFAULT)
             .build();
 
+        let entry = self.imp().download_path_entry.get();
+        self.settings()
+            .bind("download-path", &entry, "text")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
         let rate = self.imp().switch_rate.get();
         self.settings()
             .bind("music-rate", &rate, "selected")
@@ -71,9 +78,145 @@ impl NeteaseCloudMusicGtk4Preferences {
 
         let desktop_lyrics = self.imp().desktop_lyrics.get();
         self.settings()
-            .bind("desktop-lyrics", &desktop_lyrics, "active")
+            .bind("desktop-lyrics", &desktop_lyrics, "enable-expansion")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        let desktop_lyrics_locked = self.imp().desktop_lyrics_locked.get();
+        self.settings()
+            .bind("desktop-lyrics-locked", &desktop_lyrics_locked, "active")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        let desktop_lyrics_font = self.imp().desktop_lyrics_font.get();
+        self.settings()
+            .bind("desktop-lyrics-font", &desktop_lyrics_font, "font-desc")
+            .mapping(|variant, _| {
+                let font = variant.get::<String>()?;
+                Some(pango::FontDescription::from_string(&font).to_value())
+            })
+            .set_mapping(|value, _| {
+                let font = value.get::<pango::FontDescription>().ok()?;
+                Some(font.to_str().to_string().to_variant())
+            })
+            .build();
+
+        let color_keys = [
+            ("desktop-lyrics-color", self.imp().desktop_lyrics_color.get()),
+            ("desktop-lyrics-next-color", self.imp().desktop_lyrics_next_color.get()),
+            ("desktop-lyrics-outline-color", self.imp().desktop_lyrics_outline_color.get()),
+        ];
+        for (key, button) in color_keys {
+            self.settings()
+                .bind(key, &button, "rgba")
+                .mapping(|variant, _| {
+                    let color = variant.get::<String>()?;
+                    gdk::RGBA::parse(color).ok().map(|rgba| rgba.to_value())
+                })
+                .set_mapping(|value, _| {
+                    let rgba = value.get::<gdk::RGBA>().ok()?;
+                    Some(rgba.to_str().to_string().to_variant())
+                })
+                .build();
+        }
+
+        let desktop_lyrics_outline_width = self.imp().desktop_lyrics_outline_width.get();
+        self.settings()
+            .bind("desktop-lyrics-outline-width", &desktop_lyrics_outline_width, "value")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        let prefer_user_lyrics = self.imp().prefer_user_lyrics.get();
+        self.settings()
+            .bind("prefer-user-lyrics", &prefer_user_lyrics, "enable-expansion")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        let user_lyrics_folders = self.imp().user_lyrics_folders.get();
+        self.settings()
+            .bind("user-lyrics-folders", &user_lyrics_folders, "text")
+            .mapping(|variant, _| {
+                let folders = variant.get::<Vec<String>>()?;
+                Some(folders.join(";").to_value())
+            })
+            .set_mapping(|value, _| {
+                let text = value.get::<String>().ok()?;
+                let folders: Vec<&str> = text
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|f| !f.is_empty())
+                    .collect();
+                Some(folders.to_variant())
+            })
+            .build();
+
+        let entry = self.imp().now_playing_entry.get();
+        self.settings()
+            .bind("now-playing-file", &entry, "text")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        let remote_api = self.imp().remote_api.get();
+        self.settings()
+            .bind("remote-api", &remote_api, "enable-expansion")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        let remote_api_port = self.imp().remote_api_port.get();
+        self.settings()
+            .bind("remote-api-port", &remote_api_port, "value")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        let remote_api_lan = self.imp().remote_api_lan.get();
+        self.settings()
+            .bind("remote-api-lan", &remote_api_lan, "active")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        // 令牌修改后会重启远程控制服务，因此仅在确认时写入
+        let remote_api_token = self.imp().remote_api_token.get();
+        self.settings()
+            .bind("remote-api-token", &remote_api_token, "text")
+            .flags(SettingsBindFlags::GET)
+            .build();
+        let settings = self.settings().clone();
+        remote_api_token.connect_apply(move |row| {
+            settings.set_string("remote-api-token", &row.text()).ok();
+        });
+
+        let mpd_server = self.imp().mpd_server.get();
+        self.settings()
+            .bind("mpd-server", &mpd_server, "enable-expansion")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        let mpd_server_port = self.imp().mpd_server_port.get();
+        self.settings()
+            .bind("mpd-server-port", &mpd_server_port, "value")
+            .flags(SettingsBindFlags::DEFAULT)
+            .build();
+
+        let mpd_server_lan = self.imp().mpd_server_lan.get();
+        self.settings()
+            .bind("mpd-server-lan", &mpd_server_lan, "active")
             .flags(SettingsBindFlags::DEFAULT)
             .build();
+
+        // 局域网访问 MPD 服务时以远程控制的令牌作为密码
+        let mpd_server_password = self.imp().mpd_server_password.get();
+        self.settings()
+            .bind("remote-api-token", &mpd_server_password, "text")
+            .flags(SettingsBindFlags::GET)
+            .build();
+        self.settings()
+            .bind("mpd-server-lan", &mpd_server_password, "sensitive")
+            .flags(SettingsBindFlags::GET)
+            .build();
+        let settings = self.settings().clone();
+        mpd_server_password.connect_apply(move |row| {
+            settings.set_string("remote-api-token", &row.text()).ok();
+        });
     }
 
     pub fn set_cache_size_label(&self, size: f64, unit: String) {
@@ -109,11 +252,47 @@ mod imp {
         #[template_child]
         pub proxy_entry: TemplateChild<Entry>,
         #[template_child]
+        pub download_path_entry: TemplateChild<Entry>,
+        #[template_child]
         pub switch_rate: TemplateChild<adw::ComboRow>,
         #[template_child]
         pub cache_clear: TemplateChild<adw::ComboRow>,
         #[template_child]
-        pub desktop_lyrics: TemplateChild<Switch>,
+        pub desktop_lyrics: TemplateChild<adw::ExpanderRow>,
+        #[template_child]
+        pub desktop_lyrics_locked: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub desktop_lyrics_font: TemplateChild<FontDialogButton>,
+        #[template_child]
+        pub desktop_lyrics_color: TemplateChild<ColorDialogButton>,
+        #[template_child]
+        pub desktop_lyrics_next_color: TemplateChild<ColorDialogButton>,
+        #[template_child]
+        pub desktop_lyrics_outline_color: TemplateChild<ColorDialogButton>,
+        #[template_child]
+        pub desktop_lyrics_outline_width: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub prefer_user_lyrics: TemplateChild<adw::ExpanderRow>,
+        #[template_child]
+        pub user_lyrics_folders: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub now_playing_entry: TemplateChild<Entry>,
+        #[template_child]
+        pub remote_api: TemplateChild<adw::ExpanderRow>,
+        #[template_child]
+        pub remote_api_port: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub remote_api_lan: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub remote_api_token: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub mpd_server: TemplateChild<adw::ExpanderRow>,
+        #[template_child]
+        pub mpd_server_port: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub mpd_server_lan: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub mpd_server_password: TemplateChild<adw::EntryRow>,
     }
 
     #[glib::object_subclass]
@@ -13,6 +13,7 @@ use once_cell::sync::{Lazy, OnceCell};
 
 use crate::{
     application::Action,
+    cloud_disk::{CloudSong, CloudSort, cloud_formats, sort_cloud_songs},
     gui::songlist_view::SongListView,
     model::{SearchResult, SearchType},
     utils::*,
@@ -43,9 +44,13 @@ impl SearchSongPage {
         let imp = self.imp();
         imp.playlist.replace(Vec::new());
         imp.songs_list.set_property("no-act-remove", true);
+        imp.songs_list.set_property("act-cloud", search_type == SearchType::CloudDisk);
         let title_clamp = imp.title_clamp.get();
         match search_type {
-            SearchType::DailyRec | SearchType::Heartbeat | SearchType::CloudDisk => {
+            SearchType::DailyRec
+            | SearchType::Heartbeat
+            | SearchType::CloudDisk
+            | SearchType::LocalMusic => {
                 title_clamp.set_visible(true);
                 imp.title_label.set_label(keyword);
             }
@@ -54,7 +59,7 @@ impl SearchSongPage {
             }
         }
         match search_type {
-            SearchType::CloudDisk | SearchType::Radio => {
+            SearchType::CloudDisk | SearchType::Radio | SearchType::LocalMusic => {
                 imp.songs_list.set_property("no-act-like", true);
                 imp.songs_list.set_property("no-act-album", true);
             }
@@ -63,6 +68,13 @@ impl SearchSongPage {
                 imp.songs_list.set_property("no-act-album", false);
             }
         }
+        imp.folder_button
+            .set_visible(search_type == SearchType::LocalMusic);
+        imp.match_button
+            .set_visible(search_type == SearchType::LocalMusic);
+        imp.upload_button
+            .set_visible(search_type == SearchType::CloudDisk);
+        imp.cloud_box.set_visible(search_type == SearchType::CloudDisk);
         self.set_property("offset", 0);
         self.set_property("keyword", keyword);
         self.set_property("search-type", search_type);
@@ -87,6 +99,57 @@ impl SearchSongPage {
 
         songs_list.init_new_list(sis, likes);
     }
+
+    pub fn update_cloud_songs(&self, songs: Vec<CloudSong>) {
+        let imp = self.imp();
+        let mut formats = vec![gettext("All formats")];
+        formats.extend(cloud_formats(&songs));
+        // 保留之前选中的格式
+        let selected = imp
+            .format_dropdown
+            .selected_item()
+            .and_downcast::<StringObject>()
+            .map(|s| s.string().to_string());
+        let position = selected
+            .and_then(|f| formats.iter().position(|s| *s == f))
+            .unwrap_or(0);
+        imp.cloud_songs.replace(songs);
+
+        imp.cloud_updating.set(true);
+        let formats: Vec<&str> = formats.iter().map(|s| s.as_str()).collect();
+        imp.format_dropdown.set_model(Some(&StringList::new(&formats)));
+        imp.format_dropdown.set_selected(position as u32);
+        imp.cloud_updating.set(false);
+
+        self.refresh_cloud_songs();
+    }
+
+    fn refresh_cloud_songs(&self) {
+        let imp = self.imp();
+        if imp.cloud_updating.get() {
+            return;
+        }
+        let sort = CloudSort::from_index(imp.sort_dropdown.selected());
+        // 第一项为全部格式
+        let format = if imp.format_dropdown.selected() == 0 {
+            String::new()
+        } else {
+            imp.format_dropdown
+                .selected_item()
+                .and_downcast::<StringObject>()
+                .map(|s| s.string().to_string())
+                .unwrap_or_default()
+        };
+        let sis: Vec<SongInfo> = sort_cloud_songs(&imp.cloud_songs.borrow(), sort, &format)
+            .into_iter()
+            .map(|s| s.song)
+            .collect();
+
+        self.set_property("offset", 0);
+        imp.playlist.replace(Vec::new());
+        imp.songs_list.get().clear_list();
+        self.update_songs(&sis, &vec![false; sis.len()]);
+    }
 }
 
 impl Default for SearchSongPage {
@@ -108,6 +171,18 @@ mod imp {
         pub title_label: TemplateChild<Label>,
         #[template_child]
         pub num_label: TemplateChild<Label>,
+        #[template_child]
+        pub folder_button: TemplateChild<Button>,
+        #[template_child]
+        pub match_button: TemplateChild<Button>,
+        #[template_child]
+        pub cloud_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub sort_dropdown: TemplateChild<DropDown>,
+        #[template_child]
+        pub format_dropdown: TemplateChild<DropDown>,
+        #[template_child]
+        pub upload_button: TemplateChild<Button>,
 
         #[template_child(id = "songs_list")]
         pub songs_list: TemplateChild<SongListView>,
@@ -117,6 +192,8 @@ mod imp {
         search_type: Cell<SearchType>,
 
         pub playlist: Rc<RefCell<Vec<SongInfo>>>,
+        pub cloud_songs: RefCell<Vec<CloudSong>>,
+        pub cloud_updating: Cell<bool>,
         pub sender: OnceCell<Sender<Action>>,
     }
 
@@ -151,6 +228,17 @@ mod imp {
                         s.scrolled_edge_cb(pos);
                     }
                 ));
+
+            self.sort_dropdown.connect_selected_notify(clone!(
+                #[weak]
+                obj,
+                move |_| obj.refresh_cloud_songs()
+            ));
+            self.format_dropdown.connect_selected_notify(clone!(
+                #[weak]
+                obj,
+                move |_| obj.refresh_cloud_songs()
+            ));
         }
 
         fn properties() -> &'static [ParamSpec] {
@@ -213,6 +301,7 @@ impl SearchSongPage {
             SearchType::DailyRec => return,
             SearchType::Heartbeat => return,
             SearchType::CloudDisk => return,
+            SearchType::LocalMusic => return,
             _ => (),
         }
         let offset = self.property::<i32>("offset");
@@ -243,6 +332,80 @@ impl SearchSongPage {
         }
     }
 
+    #[template_callback]
+    fn folder_button_clicked_cb(&self) {
+        let sender = self.imp().sender.get().unwrap().clone();
+        let window = self.root().and_downcast::<gtk::Window>();
+        let dialog = FileDialog::builder()
+            .title(gettext("Add Music Folder"))
+            .modal(true)
+            .build();
+        dialog.select_folder(window.as_ref(), gio::Cancellable::NONE, move |res| {
+            if let Some(path) = res.ok().and_then(|f| f.path()) {
+                sender
+                    .send_blocking(Action::AddLocalMusicFolder(path))
+                    .unwrap();
+            }
+        });
+    }
+
+    #[template_callback]
+    fn upload_button_clicked_cb(&self) {
+        let sender = self.imp().sender.get().unwrap().clone();
+        let window = self.root().and_downcast::<gtk::Window>();
+        let filter = FileFilter::new();
+        filter.set_name(Some(&gettext("Audio files")));
+        filter.add_mime_type("audio/*");
+        let filters = gio::ListStore::new::<FileFilter>();
+        filters.append(&filter);
+        let dialog = FileDialog::builder()
+            .title(gettext("Upload Music Files"))
+            .modal(true)
+            .filters(&filters)
+            .build();
+        dialog.open_multiple(window.as_ref(), gio::Cancellable::NONE, move |res| {
+            if let Ok(files) = res {
+                let paths: Vec<_> = files
+                    .iter::<gio::File>()
+                    .filter_map(|f| f.ok().and_then(|f| f.path()))
+                    .collect();
+                if !paths.is_empty() {
+                    sender.send_blocking(Action::UploadToCloud(paths)).unwrap();
+                }
+            }
+        });
+    }
+
+    #[template_callback]
+    fn download_all_button_clicked_cb(&self) {
+        let sis = self.imp().playlist.borrow().clone();
+        let sender = self.imp().sender.get().unwrap().clone();
+        if sis.is_empty() {
+            sender
+                .send_blocking(Action::AddToast(gettext("This is an empty song list！")))
+                .unwrap();
+            return;
+        }
+        let window = self.root().and_downcast::<gtk::Window>();
+        let dialog = FileDialog::builder()
+            .title(gettext("Download Songs to Folder"))
+            .modal(true)
+            .build();
+        dialog.select_folder(window.as_ref(), gio::Cancellable::NONE, move |res| {
+            if let Some(path) = res.ok().and_then(|f| f.path()) {
+                sender
+                    .send_blocking(Action::DownloadSongs(sis, path))
+                    .unwrap();
+            }
+        });
+    }
+
+    #[template_callback]
+    fn match_button_clicked_cb(&self) {
+        let sender = self.imp().sender.get().unwrap();
+        sender.send_blocking(Action::ShowLocalMatchDialog).unwrap();
+    }
+
     #[template_callback]
     fn play_button_clicked_cb(&self) {
         let sender = self.imp().sender.get().unwrap();
@@ -14,6 +14,7 @@ use once_cell::sync::{Lazy, OnceCell};
 use crate::{
     application::Action,
     gui::songlist_view::SongListView,
+    links::NeteaseLink,
     model::{DiscoverSubPage, ImageDownloadImpl, SongListDetail},
     path::CACHE,
     utils::*,
@@ -214,6 +215,30 @@ mod imp {
             }
         }
 
+        #[template_callback]
+        fn share_button_clicked_cb(&self) {
+            let sender = self.sender.get().unwrap();
+            let (Some(pt), Some(songlist)) = (&*self.page_type.borrow(), &*self.songlist.borrow())
+            else {
+                return;
+            };
+            let link = match pt {
+                DiscoverSubPage::SongList => NeteaseLink::Playlist(songlist.id),
+                DiscoverSubPage::Album => NeteaseLink::Album(songlist.id),
+                DiscoverSubPage::Radio => {
+                    sender
+                        .send_blocking(Action::AddToast(gettext(
+                            "Sharing radio stations is not supported!",
+                        )))
+                        .unwrap();
+                    return;
+                }
+            };
+            sender
+                .send_blocking(Action::ShareLink(link, songlist.name.to_owned(), None))
+                .unwrap();
+        }
+
         #[template_callback]
         fn like_button_clicked_cb(&self) {
             let page_type = &*self.page_type.borrow();
@@ -7,7 +7,11 @@ use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use gtk::{glib, CompositeTemplate, *};
 
-use crate::application::Action;
+use crate::{
+    application::Action,
+    audio::{is_local_song, local_song_path, netease_song_id},
+    links::NeteaseLink,
+};
 use async_channel::Sender;
 use gettextrs::gettext;
 use glib::{ParamSpec, ParamSpecBoolean, SendWeakRef, Value};
@@ -44,7 +48,9 @@ impl SonglistRow {
         self.set_album(&si.album);
         self.set_duration(si.duration);
 
-        self.set_activatable(si.copyright.playable());
+        self.set_activatable(si.copyright.playable() || is_local_song(si));
+        self.imp().download_button.set_visible(!is_local_song(si));
+        self.imp().upload_button.set_visible(is_local_song(si));
     }
 
     pub fn not_ignore_grey(&self) -> bool {
@@ -75,6 +81,12 @@ impl SonglistRow {
         imp.remove_button.set_visible(visible);
     }
 
+    pub fn set_cloud_buttons_visible(&self, visible: bool) {
+        let imp = self.imp();
+        imp.rematch_button.set_visible(visible);
+        imp.cloud_delete_button.set_visible(visible);
+    }
+
     fn set_name(&self, label: &str) {
         let imp = self.imp();
         imp.title_label.set_label(label);
@@ -109,11 +121,17 @@ impl SonglistRow {
         let imp = self.imp();
         let sender = imp.sender.get().unwrap();
         let si = { imp.song_info.borrow().clone().unwrap() };
+        let Some(id) = netease_song_id(&si) else {
+            sender
+                .send_blocking(Action::AddToast(gettext("This local song is not matched yet!")))
+                .unwrap();
+            return;
+        };
         let s_send = SendWeakRef::from(self.downgrade());
         let like = imp.like.get();
         sender
             .send_blocking(Action::LikeSong(
-                si.id,
+                id,
                 !like,
                 Some(Arc::new(move |_| {
                     if let Some(s) = s_send.upgrade() {
@@ -124,6 +142,23 @@ impl SonglistRow {
             .unwrap();
     }
 
+    #[template_callback]
+    fn share_button_clicked_cb(&self) {
+        let imp = self.imp();
+        let sender = imp.sender.get().unwrap();
+        let si = { imp.song_info.borrow().clone().unwrap() };
+        let Some(id) = netease_song_id(&si) else {
+            sender
+                .send_blocking(Action::AddToast(gettext("This local song is not matched yet!")))
+                .unwrap();
+            return;
+        };
+        let title = format!("{} - {}", si.name, si.singer);
+        sender
+            .send_blocking(Action::ShareLink(NeteaseLink::Song(id), title, None))
+            .unwrap();
+    }
+
     #[template_callback]
     fn album_button_clicked_cb(&self) {
         let imp = self.imp();
@@ -145,6 +180,42 @@ impl SonglistRow {
         }
     }
 
+    #[template_callback]
+    fn download_button_clicked_cb(&self) {
+        let imp = self.imp();
+        let sender = imp.sender.get().unwrap();
+        let si = { imp.song_info.borrow().clone().unwrap() };
+        sender.send_blocking(Action::DownloadSong(si)).unwrap();
+    }
+
+    #[template_callback]
+    fn upload_button_clicked_cb(&self) {
+        let imp = self.imp();
+        let sender = imp.sender.get().unwrap();
+        let si = { imp.song_info.borrow().clone().unwrap() };
+        if let Some(path) = local_song_path(&si) {
+            sender
+                .send_blocking(Action::UploadToCloud(vec![path]))
+                .unwrap();
+        }
+    }
+
+    #[template_callback]
+    fn rematch_button_clicked_cb(&self) {
+        let imp = self.imp();
+        let sender = imp.sender.get().unwrap();
+        let si = { imp.song_info.borrow().clone().unwrap() };
+        sender.send_blocking(Action::RematchCloudSong(si)).unwrap();
+    }
+
+    #[template_callback]
+    fn cloud_delete_button_clicked_cb(&self) {
+        let imp = self.imp();
+        let sender = imp.sender.get().unwrap();
+        let si = { imp.song_info.borrow().clone().unwrap() };
+        sender.send_blocking(Action::DeleteCloudSong(si)).unwrap();
+    }
+
     #[template_callback]
     fn remove_button_clicked_cb(&self) {
         let imp = self.imp();
@@ -178,6 +249,14 @@ mod imp {
         #[template_child]
         pub album_button: TemplateChild<Button>,
         #[template_child]
+        pub download_button: TemplateChild<Button>,
+        #[template_child]
+        pub upload_button: TemplateChild<Button>,
+        #[template_child]
+        pub rematch_button: TemplateChild<Button>,
+        #[template_child]
+        pub cloud_delete_button: TemplateChild<Button>,
+        #[template_child]
         pub remove_button: TemplateChild<Button>,
 
         pub sender: OnceCell<Sender<Action>>,
@@ -63,6 +63,7 @@ impl SongListView {
         let no_act_like = self.property::<bool>("no-act-like");
         let no_act_album = self.property::<bool>("no-act-album");
         let no_act_remove = self.property::<bool>("no-act-remove");
+        let act_cloud = self.property::<bool>("act-cloud");
         sis.iter().zip(likes.iter()).for_each(|(si, like)| {
             let sender = sender.clone();
 
@@ -71,6 +72,7 @@ impl SongListView {
             row.set_like_button_visible(!no_act_like);
             row.set_album_button_visible(!no_act_album);
             row.set_remove_button_visible(!no_act_remove);
+            row.set_cloud_buttons_visible(act_cloud);
 
             let si = si.clone();
             row.connect_activate(clone!(
@@ -167,6 +169,7 @@ mod imp {
         no_act_like: Cell<bool>,
         no_act_album: Cell<bool>,
         no_act_remove: Cell<bool>,
+        act_cloud: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -228,6 +231,7 @@ mod imp {
                     ParamSpecBoolean::builder("no-act-like").build(),
                     ParamSpecBoolean::builder("no-act-album").build(),
                     ParamSpecBoolean::builder("no-act-remove").build(),
+                    ParamSpecBoolean::builder("act-cloud").build(),
                     ParamSpecInt::builder("clamp-margin-top").build(),
                     ParamSpecInt::builder("clamp-margin-bottom").build(),
                     ParamSpecInt::builder("clamp-maximum-size").build(),
@@ -251,6 +255,10 @@ mod imp {
                     let val = value.get().unwrap();
                     self.no_act_remove.replace(val);
                 }
+                "act-cloud" => {
+                    let val = value.get().unwrap();
+                    self.act_cloud.replace(val);
+                }
                 "clamp-margin-top" => {
                     let val = value.get().unwrap();
                     self.adw_clamp.set_margin_top(val);
@@ -276,6 +284,7 @@ mod imp {
                 "no-act-like" => self.no_act_like.get().to_value(),
                 "no-act-album" => self.no_act_album.get().to_value(),
                 "no-act-remove" => self.no_act_remove.get().to_value(),
+                "act-cloud" => self.act_cloud.get().to_value(),
                 "clamp-margin-top" => self.adw_clamp.margin_top().to_value(),
                 "clamp-margin-bottom" => self.adw_clamp.margin_bottom().to_value(),
                 "clamp-maximum-size" => self.adw_clamp.maximum_size().to_value(),
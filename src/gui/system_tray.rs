@@ -7,12 +7,49 @@
 use async_channel::Sender;
 use gettextrs::gettext;
 use gtk::gdk_pixbuf::{InterpType, Pixbuf};
-use ksni::{Icon, MenuItem, Tray, TrayService, menu::StandardItem};
+use gtk::glib;
+use ksni::{
+    Icon, MenuItem, ToolTip, Tray, TrayService,
+    menu::{CheckmarkItem, RadioGroup, RadioItem, StandardItem, SubMenu},
+};
 use log::*;
+use ncm_api::SongInfo;
 use std::io::Cursor;
-use std::sync::{Arc, Mutex};
-
-use crate::application::Action;
+use std::{
+    cell::Cell,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{application::Action, audio::LoopsState};
+
+// 托盘菜单中播放模式的顺序
+const LOOPS_STATES: [LoopsState; 4] = [
+    LoopsState::None,
+    LoopsState::Track,
+    LoopsState::Playlist,
+    LoopsState::Shuffle,
+];
+
+// 与首选项中的音质选项一致，序号对应 music-rate
+const QUALITY_LABELS: [&str; 8] = [
+    "Standard (标准)",
+    "Higher (较高)",
+    "Extreme (极高)",
+    "Lossless SQ (无损)",
+    "Hi-Res HR (高解析)",
+    "Surround SA (高清臻音)",
+    "Audio Vivid (臻音全景声)",
+    "Master (超清母带)",
+];
+
+// 最近播放子菜单保留的歌曲数
+const RECENT_LIMIT: usize = 10;
+// 每次滚动调整的音量
+const VOLUME_STEP: f64 = 0.05;
+// 托盘刷新的最小间隔
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
 
 const APP_ICON: &[u8] = include_bytes!("../../data/icons/hicolor/512x512@2x.png");
 
@@ -81,6 +118,10 @@ fn load_cover_icon_data(album_id: u64) -> Vec<u8> {
         .unwrap_or_default()
 }
 
+fn format_time(sec: u64) -> String {
+    format!("{:0>2}:{:0>2}", sec / 60, sec % 60)
+}
+
 fn truncate_text(text: &str, max_chars: usize) -> String {
     let truncated: String = text.chars().take(max_chars).collect();
     if text.chars().count() > max_chars {
@@ -96,6 +137,15 @@ pub struct TrayState {
     song_title: Arc<Mutex<String>>,
     song_artist: Arc<Mutex<String>>,
     cover_icon_data: Arc<Mutex<Vec<u8>>>,
+    desktop_lyrics: Arc<Mutex<(bool, bool)>>,
+    like: Arc<Mutex<bool>>,
+    loops: Arc<Mutex<LoopsState>>,
+    // (播放位置, 时长)，单位秒
+    progress: Arc<Mutex<(u64, u64)>>,
+    lyric: Arc<Mutex<String>>,
+    recent: Arc<Mutex<Vec<SongInfo>>>,
+    quality: Arc<Mutex<u32>>,
+    heartbeat: Arc<Mutex<bool>>,
 }
 
 impl TrayState {
@@ -105,6 +155,14 @@ impl TrayState {
             song_title: Arc::new(Mutex::new(gettext("NetEase Cloud Music"))),
             song_artist: Arc::new(Mutex::new(String::new())),
             cover_icon_data: Arc::new(Mutex::new(load_cover_icon_data(0))),
+            desktop_lyrics: Arc::new(Mutex::new((false, false))),
+            like: Arc::new(Mutex::new(false)),
+            loops: Arc::new(Mutex::new(LoopsState::default())),
+            progress: Arc::new(Mutex::new((0, 0))),
+            lyric: Arc::new(Mutex::new(String::new())),
+            recent: Arc::new(Mutex::new(Vec::new())),
+            quality: Arc::new(Mutex::new(0)),
+            heartbeat: Arc::new(Mutex::new(false)),
         }
     }
 
@@ -132,6 +190,92 @@ impl TrayState {
         }
     }
 
+    // (是否显示, 是否锁定)
+    pub fn set_desktop_lyrics(&self, visible: bool, locked: bool) {
+        if let Ok(mut d) = self.desktop_lyrics.lock() {
+            *d = (visible, locked);
+        }
+    }
+
+    pub fn get_desktop_lyrics(&self) -> (bool, bool) {
+        self.desktop_lyrics.lock().ok().map(|d| *d).unwrap_or_default()
+    }
+
+    pub fn set_like(&self, like: bool) {
+        if let Ok(mut l) = self.like.lock() {
+            *l = like;
+        }
+    }
+
+    pub fn get_like(&self) -> bool {
+        self.like.lock().ok().map(|l| *l).unwrap_or(false)
+    }
+
+    pub fn set_loops(&self, loops: LoopsState) {
+        if let Ok(mut l) = self.loops.lock() {
+            *l = loops;
+        }
+    }
+
+    pub fn get_loops(&self) -> LoopsState {
+        self.loops.lock().ok().map(|l| *l).unwrap_or_default()
+    }
+
+    // 进度或歌词有变化时返回 true
+    pub fn set_progress(&self, position: u64, duration: u64, lyric: String) -> bool {
+        let mut changed = false;
+        if let Ok(mut p) = self.progress.lock() {
+            changed |= *p != (position, duration);
+            *p = (position, duration);
+        }
+        if let Ok(mut l) = self.lyric.lock() {
+            changed |= *l != lyric;
+            *l = lyric;
+        }
+        changed
+    }
+
+    pub fn get_progress(&self) -> (u64, u64) {
+        self.progress.lock().ok().map(|p| *p).unwrap_or_default()
+    }
+
+    pub fn get_lyric(&self) -> String {
+        self.lyric.lock().ok().map(|l| l.clone()).unwrap_or_default()
+    }
+
+    // 最近播放的歌曲排在最前，重复播放时移到最前
+    pub fn add_recent(&self, si: SongInfo) {
+        if let Ok(mut recent) = self.recent.lock() {
+            recent.retain(|s| s.id != si.id);
+            recent.insert(0, si);
+            recent.truncate(RECENT_LIMIT);
+        }
+    }
+
+    pub fn get_recent(&self) -> Vec<SongInfo> {
+        self.recent.lock().ok().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    pub fn set_quality(&self, quality: u32) {
+        if let Ok(mut q) = self.quality.lock() {
+            *q = quality;
+        }
+    }
+
+    pub fn get_quality(&self) -> u32 {
+        self.quality.lock().ok().map(|q| *q).unwrap_or(0)
+    }
+
+    pub fn set_heartbeat(&self, active: bool) {
+        if let Ok(mut h) = self.heartbeat.lock() {
+            *h = active;
+        }
+    }
+
+    pub fn get_heartbeat(&self) -> bool {
+        self.heartbeat.lock().ok().map(|h| *h).unwrap_or(false)
+    }
+
     #[allow(dead_code)]
     pub fn get_playing(&self) -> bool {
         self.playing.lock().ok().map(|p| *p).unwrap_or(false)
@@ -188,6 +332,38 @@ impl Tray for SystemTray {
         load_app_icon()
     }
 
+    fn tool_tip(&self) -> ToolTip {
+        let title = self.state.get_song_title();
+        let artist = self.state.get_song_artist();
+        let (position, duration) = self.state.get_progress();
+        let mut description = Vec::new();
+        if !artist.is_empty() {
+            description.push(artist);
+        }
+        if duration > 0 {
+            description.push(format!("{} / {}", format_time(position), format_time(duration)));
+        }
+        let lyric = self.state.get_lyric();
+        if !lyric.is_empty() {
+            description.push(lyric);
+        }
+        ToolTip {
+            title,
+            description: description.join("\n"),
+            icon_name: crate::APP_ID.to_string(),
+            ..Default::default()
+        }
+    }
+
+    // 在图标上滚动调整音量
+    fn scroll(&mut self, delta: i32, orientation: &str) {
+        if delta == 0 || !orientation.eq_ignore_ascii_case("vertical") {
+            return;
+        }
+        let step = if delta > 0 { VOLUME_STEP } else { -VOLUME_STEP };
+        let _ = self.sender.try_send(Action::AdjustVolume(step));
+    }
+
     fn menu(&self) -> Vec<MenuItem<Self>> {
         let title = truncate_text(&self.state.get_song_title(), 9);
         let artist = truncate_text(&self.state.get_song_artist(), 9);
@@ -207,6 +383,23 @@ impl Tray for SystemTray {
         } else {
             "media-playback-start-symbolic"
         };
+        let (lyrics_visible, lyrics_locked) = self.state.get_desktop_lyrics();
+        let loops = self.state.get_loops();
+        let recent: Vec<MenuItem<Self>> = self
+            .state
+            .get_recent()
+            .into_iter()
+            .map(|si| {
+                StandardItem {
+                    label: truncate_text(&format!("{} - {}", si.name, si.singer), 30),
+                    activate: Box::new(move |tray: &mut Self| {
+                        let _ = tray.sender.try_send(Action::AddPlay(si.clone()));
+                    }),
+                    ..Default::default()
+                }
+                .into()
+            })
+            .collect();
 
         vec![
             StandardItem {
@@ -247,6 +440,114 @@ impl Tray for SystemTray {
             }
             .into(),
             MenuItem::Separator,
+            CheckmarkItem {
+                label: gettext("Like"),
+                checked: self.state.get_like(),
+                activate: Box::new(|tray: &mut Self| {
+                    let _ = tray.sender.try_send(Action::LikeCurrentSong(None));
+                }),
+                ..Default::default()
+            }
+            .into(),
+            StandardItem {
+                label: gettext("Dislike"),
+                icon_name: "user-trash-symbolic".to_string(),
+                activate: Box::new(|tray: &mut Self| {
+                    let _ = tray.sender.try_send(Action::TrashCurrentSong);
+                }),
+                ..Default::default()
+            }
+            .into(),
+            SubMenu {
+                label: gettext("Play Mode"),
+                submenu: vec![
+                    RadioGroup {
+                        selected: LOOPS_STATES.iter().position(|s| *s == loops).unwrap_or(0),
+                        select: Box::new(|tray: &mut Self, index| {
+                            let _ = tray.sender.try_send(Action::SetLoops(LOOPS_STATES[index]));
+                        }),
+                        options: vec![
+                            RadioItem {
+                                label: gettext("Shuffle/Repeat Off"),
+                                ..Default::default()
+                            },
+                            RadioItem {
+                                label: gettext("Repeat Song"),
+                                ..Default::default()
+                            },
+                            RadioItem {
+                                label: gettext("Repeat All"),
+                                ..Default::default()
+                            },
+                            RadioItem {
+                                label: gettext("Shuffle"),
+                                ..Default::default()
+                            },
+                        ],
+                    }
+                    .into(),
+                ],
+                ..Default::default()
+            }
+            .into(),
+            SubMenu {
+                label: gettext("Sound quality"),
+                submenu: vec![
+                    RadioGroup {
+                        selected: self.state.get_quality() as usize,
+                        select: Box::new(|tray: &mut Self, index| {
+                            let _ = tray.sender.try_send(Action::SetMusicRate(index as u32));
+                        }),
+                        options: QUALITY_LABELS
+                            .iter()
+                            .map(|label| RadioItem {
+                                label: label.to_string(),
+                                ..Default::default()
+                            })
+                            .collect(),
+                    }
+                    .into(),
+                ],
+                ..Default::default()
+            }
+            .into(),
+            CheckmarkItem {
+                label: gettext("Intelligent Mode"),
+                checked: self.state.get_heartbeat(),
+                activate: Box::new(|tray: &mut Self| {
+                    let _ = tray.sender.try_send(Action::ToggleHeartbeat);
+                }),
+                ..Default::default()
+            }
+            .into(),
+            SubMenu {
+                label: gettext("Recently Played"),
+                enabled: !recent.is_empty(),
+                submenu: recent,
+                ..Default::default()
+            }
+            .into(),
+            MenuItem::Separator,
+            CheckmarkItem {
+                label: gettext("Desktop Lyrics"),
+                checked: lyrics_visible,
+                activate: Box::new(|tray: &mut Self| {
+                    let _ = tray.sender.try_send(Action::ToggleDesktopLyrics);
+                }),
+                ..Default::default()
+            }
+            .into(),
+            CheckmarkItem {
+                label: gettext("Lock Desktop Lyrics"),
+                checked: lyrics_locked,
+                enabled: lyrics_visible,
+                activate: Box::new(|tray: &mut Self| {
+                    let _ = tray.sender.try_send(Action::ToggleDesktopLyricsLock);
+                }),
+                ..Default::default()
+            }
+            .into(),
+            MenuItem::Separator,
             StandardItem {
                 label: gettext("Quit"),
                 activate: Box::new(|tray: &mut Self| {
@@ -270,6 +571,8 @@ impl Tray for SystemTray {
 pub struct TrayHandle {
     state: Option<TrayState>,
     service: Option<ksni::Handle<SystemTray>>,
+    last_refresh: Rc<Cell<Option<Instant>>>,
+    refresh_pending: Rc<Cell<bool>>,
 }
 
 impl TrayHandle {
@@ -277,6 +580,8 @@ impl TrayHandle {
         Self {
             state: None,
             service: None,
+            last_refresh: Rc::new(Cell::new(None)),
+            refresh_pending: Rc::new(Cell::new(false)),
         }
     }
 
@@ -323,9 +628,81 @@ impl TrayHandle {
         }
     }
 
+    pub fn update_desktop_lyrics(&self, visible: bool, locked: bool) {
+        if let Some(state) = &self.state {
+            state.set_desktop_lyrics(visible, locked);
+            self.refresh();
+        }
+    }
+
+    pub fn update_like(&self, like: bool) {
+        if let Some(state) = &self.state {
+            state.set_like(like);
+            self.refresh();
+        }
+    }
+
+    pub fn update_loops(&self, loops: LoopsState) {
+        if let Some(state) = &self.state {
+            state.set_loops(loops);
+            self.refresh();
+        }
+    }
+
+    pub fn update_progress(&self, position: u64, duration: u64, lyric: String) {
+        if let Some(state) = &self.state {
+            if state.set_progress(position, duration, lyric) {
+                self.refresh();
+            }
+        }
+    }
+
+    pub fn add_recent(&self, si: SongInfo) {
+        if let Some(state) = &self.state {
+            state.add_recent(si);
+            self.refresh();
+        }
+    }
+
+    pub fn update_quality(&self, quality: u32) {
+        if let Some(state) = &self.state {
+            state.set_quality(quality);
+            self.refresh();
+        }
+    }
+
+    pub fn update_heartbeat(&self, active: bool) {
+        if let Some(state) = &self.state {
+            state.set_heartbeat(active);
+            self.refresh();
+        }
+    }
+
+    // 限制刷新频率，间隔内的多次刷新合并为一次延迟刷新
     fn refresh(&self) {
-        if let Some(service) = &self.service {
-            service.update(|_| {});
+        let Some(service) = &self.service else {
+            return;
+        };
+        if self.refresh_pending.get() {
+            return;
+        }
+        let elapsed = self.last_refresh.get().map(|t| t.elapsed());
+        match elapsed {
+            Some(elapsed) if elapsed < REFRESH_INTERVAL => {
+                self.refresh_pending.set(true);
+                let service = service.clone();
+                let last_refresh = self.last_refresh.clone();
+                let refresh_pending = self.refresh_pending.clone();
+                glib::timeout_add_local_once(REFRESH_INTERVAL - elapsed, move || {
+                    refresh_pending.set(false);
+                    last_refresh.set(Some(Instant::now()));
+                    service.update(|_| {});
+                });
+            }
+            _ => {
+                self.last_refresh.set(Some(Instant::now()));
+                service.update(|_| {});
+            }
         }
     }
 
@@ -0,0 +1,248 @@
+//
+// links.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use gtk::glib;
+
+const LINK_HOST: &str = "music.163.com";
+const ORPHEUS_SCHEME: &str = "orpheus://";
+
+// 网易云音乐分享链接指向的对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NeteaseLink {
+    Song(u64),
+    Playlist(u64),
+    Album(u64),
+    Artist(u64),
+}
+
+impl NeteaseLink {
+    fn from_kind(kind: &str, id: u64) -> Option<Self> {
+        match kind {
+            "song" => Some(Self::Song(id)),
+            "playlist" => Some(Self::Playlist(id)),
+            "album" => Some(Self::Album(id)),
+            "artist" => Some(Self::Artist(id)),
+            _ => None,
+        }
+    }
+
+    fn kind(&self) -> (&'static str, u64) {
+        match *self {
+            Self::Song(id) => ("song", id),
+            Self::Playlist(id) => ("playlist", id),
+            Self::Album(id) => ("album", id),
+            Self::Artist(id) => ("artist", id),
+        }
+    }
+
+    // 网页版链接，position 为播放位置（秒），由 link_position 解析，官方客户端会忽略该参数
+    pub fn url(&self, position: Option<u64>) -> String {
+        let (kind, id) = self.kind();
+        let mut url = format!("https://{LINK_HOST}/{kind}?id={id}");
+        if let Some(position) = position {
+            url.push_str(&format!("&t={position}"));
+        }
+        url
+    }
+}
+
+// 从文本中解析网易云音乐链接，支持：
+//   https://music.163.com/song?id=1
+//   https://music.163.com/#/playlist?id=1
+//   https://y.music.163.com/m/album?id=1&userid=2
+//   https://music.163.com/artist/1
+//   orpheus://song/1
+//   orpheus://<base64 编码的 {"type":"song","id":"1"}>
+// 文本可以是聊天软件中带有说明文字的分享内容
+pub fn parse_link(text: &str) -> Option<NeteaseLink> {
+    let text = text.trim();
+    if let Some(start) = text.find(ORPHEUS_SCHEME) {
+        let rest = text[start + ORPHEUS_SCHEME.len()..]
+            .split_whitespace()
+            .next()?;
+        return parse_orpheus(rest);
+    }
+    parse_path(find_link(text)?)
+}
+
+// 解析分享歌曲时附带的播放位置（秒），如 https://music.163.com/song?id=1&t=90
+pub fn link_position(text: &str) -> Option<u64> {
+    let (_, query) = find_link(text)?.split_once('?')?;
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("t="))
+        .and_then(leading_id)
+}
+
+// 在文本中查找主机名为 music.163.com 或其子域名（如 y.music.163.com）的链接，返回主机名之后的部分
+fn find_link(text: &str) -> Option<&str> {
+    text.split_whitespace().find_map(|word| {
+        let url = word.find("://").map_or(word, |i| &word[i + 3..]);
+        let end = url.find(['/', '?', '#']).unwrap_or(url.len());
+        let host = url[..end].to_ascii_lowercase();
+        let valid = host == LINK_HOST
+            || host
+                .strip_suffix(LINK_HOST)
+                .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.'));
+        valid.then(|| &url[end..])
+    })
+}
+
+// 解析 /song?id=1、/#/song?id=1、/m/song?id=1 或 /song/1 形式的路径
+fn parse_path(url: &str) -> Option<NeteaseLink> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let mut segments = path.split('/').filter(|s| !s.is_empty() && *s != "#");
+    while let Some(segment) = segments.next() {
+        if !matches!(segment, "song" | "playlist" | "album" | "artist") {
+            continue;
+        }
+        let id = segments
+            .next()
+            .and_then(leading_id)
+            .or_else(|| query_id(query))?;
+        return NeteaseLink::from_kind(segment, id);
+    }
+    None
+}
+
+fn query_id(query: &str) -> Option<u64> {
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("id="))
+        .and_then(leading_id)
+}
+
+// 分享文本中链接后可能紧跟其他文字，只取开头的数字
+fn leading_id(text: &str) -> Option<u64> {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    text[..end].parse().ok()
+}
+
+fn parse_orpheus(payload: &str) -> Option<NeteaseLink> {
+    if let Some((kind, id)) = payload.split_once('/') {
+        let id = leading_id(id)?;
+        return NeteaseLink::from_kind(kind, id);
+    }
+
+    // 客户端生成的链接为 base64 编码的 JSON，例如 {"type":"song","id":"1","cmd":"play"}
+    let json = glib::base64_decode(payload);
+    let value: serde_json::Value = serde_json::from_slice(&json).ok()?;
+    let kind = value.get("type")?.as_str()?;
+    let id = match value.get("id")? {
+        serde_json::Value::String(id) => id.parse().ok()?,
+        id => id.as_u64()?,
+    };
+    NeteaseLink::from_kind(kind, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use NeteaseLink::*;
+
+    #[test]
+    fn links() {
+        let cases = [
+            ("https://music.163.com/song?id=1", Some(Song(1))),
+            ("https://music.163.com/#/playlist?id=2", Some(Playlist(2))),
+            (
+                "https://y.music.163.com/m/album?id=3&userid=9",
+                Some(Album(3)),
+            ),
+            ("https://music.163.com/artist/4", Some(Artist(4))),
+            ("http://music.163.com/song/5/?userid=9", Some(Song(5))),
+            ("music.163.com/#/m/song?id=6", Some(Song(6))),
+            // userid 在 id 之前
+            ("https://music.163.com/song?userid=9&id=7", Some(Song(7))),
+            (
+                "https://music.163.com/playlist?uid=9&id=8&t=1",
+                Some(Playlist(8)),
+            ),
+            // 带说明文字的分享内容
+            (
+                "分享歌曲：晴天 https://music.163.com/song?id=186016&userid=1 (来自@网易云音乐)",
+                Some(Song(186016)),
+            ),
+            (
+                "https://music.163.com/song?id=10（来自网易云音乐）",
+                Some(Song(10)),
+            ),
+            ("  https://music.163.com/album/11\n", Some(Album(11))),
+            ("orpheus://song/12", Some(Song(12))),
+            ("打开 orpheus://playlist/13 播放", Some(Playlist(13))),
+            // base64 编码的 JSON，id 为字符串或数字
+            (
+                "orpheus://eyJ0eXBlIjoic29uZyIsImlkIjoiMSIsImNtZCI6InBsYXkifQ==",
+                Some(Song(1)),
+            ),
+            (
+                "orpheus://eyJ0eXBlIjoicGxheWxpc3QiLCJpZCI6MTIzfQ==",
+                Some(Playlist(123)),
+            ),
+            ("", None),
+            ("hello", None),
+            ("186016", None),
+            ("https://example.com/song?id=1", None),
+            // 主机名须为 music.163.com 或其子域名
+            ("https://music.163.com.evil.com/song?id=1", None),
+            ("https://notmusic.163.com/song?id=1", None),
+            ("https://evil.com/music.163.com/song?id=1", None),
+            ("https://evil.com/?r=music.163.com/song?id=1", None),
+            ("HTTPS://Music.163.com/song?id=1", Some(Song(1))),
+            ("https://music.163.com/", None),
+            ("https://music.163.com/user/home?id=1", None),
+            ("https://music.163.com/song", None),
+            ("https://music.163.com/song?id=", None),
+            ("https://music.163.com/song?id=abc", None),
+            ("https://music.163.com/song?userid=1", None),
+            ("orpheus://", None),
+            ("orpheus://user/1", None),
+            ("orpheus://song/abc", None),
+            ("orpheus://not-base64!", None),
+            // {"type":"user","id":"1"}
+            ("orpheus://eyJ0eXBlIjoidXNlciIsImlkIjoiMSJ9", None),
+            // {"type":"song","id":"x"}
+            ("orpheus://eyJ0eXBlIjoic29uZyIsImlkIjoieCJ9", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_link(text), expected, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn positions() {
+        let cases = [
+            ("https://music.163.com/song?id=1&t=90", Some(90)),
+            ("https://music.163.com/song?t=5&id=1", Some(5)),
+            ("来自 https://music.163.com/song?id=1&t=30 分享", Some(30)),
+            ("https://music.163.com/song?id=1", None),
+            ("https://music.163.com/song?id=1&t=", None),
+            ("https://music.163.com/song?id=1&t=abc", None),
+            ("https://example.com/song?id=1&t=90", None),
+            ("https://music.163.com.evil.com/song?id=1&t=90", None),
+            ("https://notmusic.163.com/song?id=1&t=90", None),
+            ("", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(link_position(text), expected, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn urls() {
+        for link in [Song(1), Playlist(2), Album(3), Artist(4)] {
+            let url = link.url(None);
+            assert_eq!(parse_link(&url), Some(link), "{url}");
+            assert_eq!(link_position(&url), None, "{url}");
+        }
+        let url = Song(5).url(Some(90));
+        assert_eq!(url, "https://music.163.com/song?id=5&t=90");
+        assert_eq!(parse_link(&url), Some(Song(5)));
+        assert_eq!(link_position(&url), Some(90));
+    }
+}
@@ -0,0 +1,784 @@
+//
+// lyrics.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::audio::first_visit;
+
+// 单行歌词
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LyricLine {
+    // 开始时间（毫秒，已应用 offset）
+    pub time: u64,
+    pub text: String,
+}
+
+// 解析后的单个 LRC 文件
+#[derive(Debug, Clone, Default)]
+pub struct Lrc {
+    // 元数据标签，如 ti、ar、al、by
+    pub tags: Vec<(String, String)>,
+    // [offset:] 标签（毫秒），正值表示歌词提前
+    pub offset: i64,
+    // 按时间排序的歌词行
+    pub lines: Vec<LyricLine>,
+}
+
+impl Lrc {
+    pub fn parse(text: &str) -> Self {
+        let mut lrc = Lrc::default();
+        // 先收集原始时间，offset 可能出现在任意位置
+        let mut lines: Vec<(u64, String)> = Vec::new();
+        for line in text.trim_start_matches('\u{feff}').lines() {
+            let mut rest = line.trim();
+            let mut times = Vec::new();
+            while let Some(tag) = rest.strip_prefix('[') {
+                let Some(end) = tag.find(']') else {
+                    break;
+                };
+                let content = &tag[..end];
+                rest = tag[end + 1..].trim_start();
+                if let Some(time) = parse_timestamp(content) {
+                    times.push(time);
+                } else if let Some((key, value)) = content.split_once(':') {
+                    let key = key.trim();
+                    let value = value.trim();
+                    if key.eq_ignore_ascii_case("offset") {
+                        lrc.offset = value.parse().unwrap_or(0);
+                    } else if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphabetic()) {
+                        lrc.tags.push((key.to_owned(), value.to_owned()));
+                    }
+                }
+            }
+            let text = rest.trim_end();
+            lines.extend(times.into_iter().map(|t| (t, text.to_owned())));
+        }
+        lines.sort_by_key(|(t, _)| *t);
+        lrc.lines = lines
+            .into_iter()
+            .map(|(time, text)| LyricLine {
+                time: apply_offset(time, lrc.offset),
+                text,
+            })
+            .collect();
+        lrc
+    }
+
+    // 调整所有行的时间，用于翻译等未单独设置 offset 的歌词
+    fn shift(&mut self, offset: i64) {
+        for line in self.lines.iter_mut() {
+            line.time = apply_offset(line.time, offset);
+        }
+    }
+}
+
+// 逐字歌词中的单个字词
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Word {
+    // 开始时间与持续时间（毫秒）
+    pub time: u64,
+    pub duration: u64,
+    pub text: String,
+}
+
+// 逐字歌词（yrc）中的一行
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WordLine {
+    pub time: u64,
+    pub words: Vec<Word>,
+}
+
+impl WordLine {
+    pub fn text(&self) -> String {
+        self.words.iter().map(|w| w.text.as_str()).collect()
+    }
+
+    // 指定时间已唱过的字符数，正在唱的字按进度计入
+    pub fn filled_chars(&self, time: u64) -> usize {
+        let mut filled = 0;
+        for word in self.words.iter() {
+            let len = word.text.chars().count();
+            if time >= word.time + word.duration {
+                filled += len;
+            } else {
+                if time > word.time && word.duration > 0 {
+                    let progress = (time - word.time) as f64 / word.duration as f64;
+                    filled += (len as f64 * progress).ceil() as usize;
+                }
+                break;
+            }
+        }
+        filled
+    }
+}
+
+// 解析网易云逐字歌词，行格式为 [开始,时长](开始,时长,0)字(开始,时长,0)字……
+pub fn parse_yrc(text: &str) -> Vec<WordLine> {
+    let mut lines: Vec<WordLine> = text
+        .lines()
+        .filter_map(|line| {
+            // 以 { 开头的行为 JSON 格式的作词作曲信息
+            let rest = line.trim().strip_prefix('[')?;
+            let (head, rest) = rest.split_once(']')?;
+            let (time, duration) = head.split_once(',')?;
+            duration.trim().parse::<u64>().ok()?;
+            let mut line = WordLine {
+                time: time.trim().parse().ok()?,
+                words: Vec::new(),
+            };
+            let mut rest = rest;
+            while let Some((time, duration, len)) = parse_word_tag(rest) {
+                rest = &rest[len..];
+                // 字词内容直到下一个时间标签
+                let end = rest
+                    .char_indices()
+                    .find(|(i, c)| *c == '(' && parse_word_tag(&rest[*i..]).is_some())
+                    .map(|(i, _)| i)
+                    .unwrap_or(rest.len());
+                line.words.push(Word {
+                    time,
+                    duration,
+                    text: rest[..end].to_owned(),
+                });
+                rest = &rest[end..];
+            }
+            (!line.words.is_empty()).then_some(line)
+        })
+        .collect();
+    lines.sort_by_key(|l| l.time);
+    lines
+}
+
+// 解析 (开始,时长,0) 标签，返回开始、时长与标签长度
+fn parse_word_tag(s: &str) -> Option<(u64, u64, usize)> {
+    let rest = s.strip_prefix('(')?;
+    let end = rest.find(')')?;
+    let mut parts = rest[..end].split(',');
+    let time = parts.next()?.trim().parse().ok()?;
+    let duration = parts.next()?.trim().parse().ok()?;
+    parts.next()?.trim().parse::<i64>().ok()?;
+    Some((time, duration, end + 2))
+}
+
+// 歌词原文、翻译与罗马音
+#[derive(Debug, Clone, Default)]
+pub struct Lyrics {
+    pub original: Lrc,
+    pub translation: Lrc,
+    pub romanization: Lrc,
+    // 逐字歌词，没有时为空
+    pub words: Vec<WordLine>,
+}
+
+impl Lyrics {
+    pub fn parse(original: &str, translation: Option<&str>, romanization: Option<&str>) -> Self {
+        let original = Lrc::parse(original);
+        // 翻译与罗马音没有自己的 offset 时跟随原文，保证同一行时间一致
+        let parse = |text: &str| {
+            let mut lrc = Lrc::parse(text);
+            if lrc.offset == 0 {
+                lrc.shift(original.offset);
+            }
+            lrc
+        };
+        Self {
+            translation: translation.map(parse).unwrap_or_default(),
+            romanization: romanization.map(parse).unwrap_or_default(),
+            original,
+            words: Vec::new(),
+        }
+    }
+
+    // 设置逐字歌词，同样应用原文的 offset
+    pub fn set_words(&mut self, yrc: &str) {
+        let offset = self.original.offset;
+        self.words = parse_yrc(yrc);
+        for line in self.words.iter_mut() {
+            line.time = apply_offset(line.time, offset);
+            for word in line.words.iter_mut() {
+                word.time = apply_offset(word.time, offset);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.original.lines.is_empty()
+    }
+
+    // 指定时间正在播放的原文行及其翻译
+    pub fn line_at(&self, time: u64) -> Option<(&str, Option<&str>)> {
+        let line = self.original.lines.iter().rev().find(|l| l.time <= time)?;
+        let translation = self
+            .translation
+            .lines
+            .iter()
+            .find(|l| l.time == line.time && !l.text.is_empty())
+            .map(|l| l.text.as_str());
+        Some((&line.text, translation))
+    }
+
+    // 组织为歌词页使用的行，罗马音与翻译紧跟在同一时间的原文之后
+    // 隐藏原文时仍按原文的时间组织，没有对应翻译或罗马音的行被省略
+    pub fn to_lines(
+        &self,
+        original: bool,
+        translation: bool,
+        romanization: bool,
+    ) -> Vec<(u64, String)> {
+        let mut lines = Vec::new();
+        for line in self.original.lines.iter() {
+            if original {
+                lines.push((line.time, format!("{}\n", line.text)));
+            }
+            if romanization {
+                lines.extend(find_line(&self.romanization, line.time));
+            }
+            if translation {
+                lines.extend(find_line(&self.translation, line.time));
+            }
+        }
+        lines
+    }
+}
+
+fn find_line(lrc: &Lrc, time: u64) -> Option<(u64, String)> {
+    lrc.lines
+        .iter()
+        .find(|l| l.time == time && !l.text.is_empty())
+        .map(|l| (time, format!("{}\n", l.text)))
+}
+
+// 解析 mm:ss、mm:ss.xx、mm:ss.xxx 及不规范的 mm:ss:xx，返回毫秒
+pub fn parse_timestamp(tag: &str) -> Option<u64> {
+    let (min, rest) = tag.trim().split_once(':')?;
+    let (sec, frac) = match rest.split_once(['.', ':']) {
+        Some((sec, frac)) => (sec, frac),
+        None => (rest, ""),
+    };
+    if !is_digits(min) || !is_digits(sec) || !(frac.is_empty() || is_digits(frac)) {
+        return None;
+    }
+    let min: u64 = min.parse().ok()?;
+    let sec: u64 = sec.parse().ok()?;
+    // 小数部分按位数换算为毫秒，多于三位时截断
+    let ms = if frac.is_empty() {
+        0
+    } else {
+        let digits: String = frac.chars().chain("00".chars()).take(3).collect();
+        digits.parse().ok()?
+    };
+    min.checked_mul(60)?
+        .checked_add(sec)?
+        .checked_mul(1000)?
+        .checked_add(ms)
+}
+
+// 扫描用户歌词目录（含子目录）中的 .lrc 文件，以忽略大小写、空白与标点的文件名为键
+// 同名文件优先使用靠前目录中层级较浅的文件
+pub fn scan_lyric_files(dirs: &[PathBuf]) -> HashMap<String, PathBuf> {
+    let mut files = HashMap::new();
+    let mut visited = HashSet::new();
+    for dir in dirs {
+        scan_dir(dir, &mut files, &mut visited);
+    }
+    files
+}
+
+fn scan_dir(dir: &Path, files: &mut HashMap<String, PathBuf>, visited: &mut HashSet<PathBuf>) {
+    if !first_visit(dir, visited) {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut subdirs = Vec::new();
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.is_dir() {
+            subdirs.push(path);
+        } else if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("lrc"))
+        {
+            let key = match_key(&path.file_stem().unwrap_or_default().to_string_lossy());
+            files.entry(key).or_insert(path);
+        }
+    }
+    for dir in subdirs {
+        scan_dir(&dir, files, visited);
+    }
+}
+
+// 在扫描到的歌词文件中按歌手与歌名查找，支持“歌手 - 歌名”与“歌名 - 歌手”
+pub fn find_lyric_file<'a>(
+    files: &'a HashMap<String, PathBuf>,
+    title: &str,
+    artists: &[String],
+) -> Option<&'a PathBuf> {
+    let title = match_key(title);
+    if title.is_empty() {
+        return None;
+    }
+    artists
+        .iter()
+        .map(|a| match_key(a))
+        .filter(|a| !a.is_empty())
+        .flat_map(|a| [format!("{}{}", a, title), format!("{}{}", title, a)])
+        .find_map(|key| files.get(&key))
+}
+
+// 用于模糊匹配的文字：仅保留字母与数字并转为小写
+pub fn match_key(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// 毫秒转为 mm:ss.xx
+pub fn format_timestamp(time: u64) -> String {
+    let cs = time / 10;
+    format!("{:0>2}:{:0>2}.{:0>2}", cs / 6000, cs / 100 % 60, cs % 100)
+}
+
+// 写入或替换 [offset:] 标签，offset 为 0 时移除该标签
+pub fn set_lrc_offset(text: &str, offset: i64) -> String {
+    let mut lines: Vec<&str> = text
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .filter(|line| {
+            let line = line.trim();
+            !(line.ends_with(']')
+                && line
+                    .get(..8)
+                    .is_some_and(|head| head.eq_ignore_ascii_case("[offset:")))
+        })
+        .collect();
+    let tag = format!("[offset:{}]", offset);
+    if offset != 0 {
+        lines.insert(0, &tag);
+    }
+    lines.join("\n")
+}
+
+// 按 (原时间, 新时间) 对照改写 LRC 文本中的时间标签，原时间为已应用 offset 的时间
+// 改写后的时间已包含 offset，因此移除 [offset:] 标签；其余标签、无时间的行原样保留
+pub fn retime_lrc(text: &str, offset: i64, moved: &[(u64, u64)]) -> String {
+    let mut lines = Vec::new();
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        let mut out = String::new();
+        let mut rest = line.trim_start();
+        let mut dropped = false;
+        while let Some(tag) = rest.strip_prefix('[') {
+            let Some(end) = tag.find(']') else {
+                break;
+            };
+            let content = &tag[..end];
+            rest = &tag[end + 1..];
+            if let Some(time) = parse_timestamp(content) {
+                let time = apply_offset(time, offset);
+                let time = moved
+                    .iter()
+                    .find(|(old, _)| *old == time)
+                    .map_or(time, |(_, new)| *new);
+                out.push_str(&format!("[{}]", format_timestamp(time)));
+            } else if content
+                .split_once(':')
+                .is_some_and(|(key, _)| key.trim().eq_ignore_ascii_case("offset"))
+            {
+                dropped = true;
+            } else {
+                out.push_str(&format!("[{}]", content));
+            }
+        }
+        out.push_str(rest);
+        // 仅含 [offset:] 标签的行整行移除
+        if !(dropped && out.trim().is_empty()) {
+            lines.push(out);
+        }
+    }
+    lines.join("\n")
+}
+
+// 将不规范的时间标签 [mm:ss:xx] 修正为 [mm:ss.xx]，用于保存歌词文件
+pub fn normalize_lrc(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let mut out = String::new();
+            let mut rest = line;
+            while let Some(tag) = rest.strip_prefix('[') {
+                let Some(end) = tag.find(']') else {
+                    break;
+                };
+                let content = &tag[..end];
+                match content.rsplit_once(':') {
+                    Some((head, frac))
+                        if content.matches(':').count() == 2
+                            && parse_timestamp(content).is_some() =>
+                    {
+                        out.push_str(&format!("[{}.{}]", head, frac));
+                    }
+                    _ => out.push_str(&format!("[{}]", content)),
+                }
+                rest = &tag[end + 1..];
+            }
+            out.push_str(rest);
+            out
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
+
+fn apply_offset(time: u64, offset: i64) -> u64 {
+    if offset >= 0 {
+        time.saturating_sub(offset as u64)
+    } else {
+        time.saturating_add(offset.unsigned_abs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(lrc: &Lrc) -> Vec<(u64, &str)> {
+        lrc.lines
+            .iter()
+            .map(|l| (l.time, l.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn timestamps() {
+        let cases = [
+            ("00:00", Some(0)),
+            ("01:02", Some(62_000)),
+            ("01:02.3", Some(62_300)),
+            ("01:02.34", Some(62_340)),
+            ("01:02.345", Some(62_345)),
+            ("01:02.3456", Some(62_345)),
+            ("01:02:34", Some(62_340)),
+            ("123:04.50", Some(7_384_500)),
+            (" 00:01.00 ", Some(1_000)),
+            ("ti:title", None),
+            ("offset:500", None),
+            ("01:xx.00", None),
+            ("-1:00.00", None),
+            ("01:02.", Some(62_000)),
+            ("", None),
+            (":", None),
+            ("99999999999999999999:00", None),
+        ];
+        for (tag, expected) in cases {
+            assert_eq!(parse_timestamp(tag), expected, "{tag:?}");
+        }
+    }
+
+    #[test]
+    fn parse_lines() {
+        let cases: [(&str, Vec<(u64, &str)>); 8] = [
+            // 三位毫秒
+            ("[00:01.234]a", vec![(1_234, "a")]),
+            // 一行多个时间标签
+            (
+                "[00:03.00][00:01.00]a\n[00:02.00]b",
+                vec![(1_000, "a"), (2_000, "b"), (3_000, "a")],
+            ),
+            // 分钟数超过 99
+            ("[100:00.00]a", vec![(6_000_000, "a")]),
+            // 非 ASCII 文本
+            ("[00:01.00]  你好，世界 ♪  ", vec![(1_000, "你好，世界 ♪")]),
+            // 元数据标签不产生歌词行
+            (
+                "[ti:标题]\n[ar:歌手]\n[by:]\n[00:01.00]a",
+                vec![(1_000, "a")],
+            ),
+            // 空白行保留为空行，用于歌词间隔
+            ("[00:01.00]\n[00:02.00]b", vec![(1_000, ""), (2_000, "b")]),
+            // 不规范的冒号分隔毫秒
+            ("[00:01:50]a", vec![(1_500, "a")]),
+            // BOM 开头
+            ("\u{feff}[00:01.00]a", vec![(1_000, "a")]),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(times(&Lrc::parse(text)), expected, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn offset() {
+        let cases = [
+            ("[offset:500]\n[00:02.00]a", 500, vec![(1_500, "a")]),
+            ("[00:02.00]a\n[offset:-500]", -500, vec![(2_500, "a")]),
+            ("[OFFSET: 1000]\n[00:00.50]a", 1_000, vec![(0, "a")]),
+            ("[offset:abc]\n[00:02.00]a", 0, vec![(2_000, "a")]),
+        ];
+        for (text, offset, expected) in cases {
+            let lrc = Lrc::parse(text);
+            assert_eq!(lrc.offset, offset, "{text:?}");
+            assert_eq!(times(&lrc), expected, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn metadata_tags() {
+        let lrc = Lrc::parse("[ti:标题]\n[ar: 歌手 ]\n[offset:100]\n[x-y:z]\n[00:01.00]a");
+        assert_eq!(
+            lrc.tags,
+            vec![
+                ("ti".to_owned(), "标题".to_owned()),
+                ("ar".to_owned(), "歌手".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let cases = [
+            "",
+            "\n\n",
+            "[",
+            "]",
+            "[00:01.00",
+            "[[00:01.00]]a",
+            "[00:01.0a]a",
+            "[:]a",
+            "[99999999999999999999:00.00]a",
+            "{\"t\":0,\"c\":[{\"tx\":\"作词\"}]}",
+            "纯文本歌词",
+            "[00:01.00]a[00:02.00]b",
+            "[é:ü]",
+        ];
+        for text in cases {
+            let lrc = Lrc::parse(text);
+            assert!(lrc.lines.iter().all(|l| l.time < 10_000), "{text:?}");
+        }
+        assert!(Lrc::parse("纯文本歌词").lines.is_empty());
+        assert!(parse_yrc("[1,2](x)\n(0,1,0)\n[a,b](0,1,0)c\n").is_empty());
+    }
+
+    #[test]
+    fn translation_follows_original_offset() {
+        let lyrics = Lyrics::parse(
+            "[offset:500]\n[00:02.00]原文",
+            Some("[00:02.00]translation"),
+            Some("[offset:-500]\n[00:02.00]romaji"),
+        );
+        assert_eq!(times(&lyrics.original), vec![(1_500, "原文")]);
+        assert_eq!(times(&lyrics.translation), vec![(1_500, "translation")]);
+        assert_eq!(times(&lyrics.romanization), vec![(2_500, "romaji")]);
+        assert_eq!(lyrics.line_at(1_600), Some(("原文", Some("translation"))));
+        assert_eq!(lyrics.line_at(1_000), None);
+    }
+
+    #[test]
+    fn format_timestamps() {
+        let cases = [
+            (0, "00:00.00"),
+            (1_234, "00:01.23"),
+            (62_340, "01:02.34"),
+            (6_000_000, "100:00.00"),
+        ];
+        for (time, expected) in cases {
+            assert_eq!(format_timestamp(time), expected);
+            assert_eq!(parse_timestamp(expected), Some(time / 10 * 10));
+        }
+    }
+
+    #[test]
+    fn normalize_round_trip() {
+        let text = "[ti:标题]\n[00:01:50][00:03:00]你好\n[00:02.25]世界\n[offset:100]\n纯文本";
+        let normalized = normalize_lrc(text);
+        assert_eq!(
+            normalized,
+            "[ti:标题]\n[00:01.50][00:03.00]你好\n[00:02.25]世界\n[offset:100]\n纯文本"
+        );
+        // 修正后解析结果不变，再次修正不再改变文本
+        assert_eq!(times(&Lrc::parse(&normalized)), times(&Lrc::parse(text)));
+        assert_eq!(normalize_lrc(&normalized), normalized);
+    }
+
+    #[test]
+    fn set_offset() {
+        let text = "[ti:a]\n[offset:100]\n[00:02.00]a";
+        assert_eq!(
+            set_lrc_offset(text, 300),
+            "[offset:300]\n[ti:a]\n[00:02.00]a"
+        );
+        assert_eq!(set_lrc_offset(text, 0), "[ti:a]\n[00:02.00]a");
+        assert_eq!(Lrc::parse(&set_lrc_offset(text, -300)).lines[0].time, 2_300);
+    }
+
+    #[test]
+    fn yrc() {
+        let text = "{\"t\":0,\"c\":[{\"tx\":\"作词: \"}]}\n\
+                    [2000,1000](2000,400,0)世(2400,600,0)界 (x)\n\
+                    [1000,1000](1000,500,0)Hello (1500,500,0)world";
+        let lines = parse_yrc(text);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].time, 1_000);
+        assert_eq!(lines[0].text(), "Hello world");
+        assert_eq!(lines[1].text(), "世界 (x)");
+        assert_eq!(
+            lines[1].words[1],
+            Word {
+                time: 2_400,
+                duration: 600,
+                text: "界 (x)".to_owned()
+            }
+        );
+        // 逐字进度按字符计算
+        let cases = [
+            (0, 0),
+            (1_000, 0),
+            (1_250, 3),
+            (1_500, 6),
+            (1_750, 9),
+            (2_000, 11),
+        ];
+        for (time, filled) in cases {
+            assert_eq!(lines[0].filled_chars(time), filled, "{time}");
+        }
+    }
+
+    #[test]
+    fn yrc_applies_offset() {
+        let mut lyrics = Lyrics::parse("[offset:500]\n[00:01.00]a", None, None);
+        lyrics.set_words("[1000,500](1000,500,0)a");
+        assert_eq!(lyrics.words[0].time, 500);
+        assert_eq!(lyrics.words[0].words[0].time, 500);
+    }
+
+    #[test]
+    fn retime() {
+        let text = "[ti:标题]\n[offset:500]\n[00:02.00][00:04.00]a\n纯文本\n[x:y]\n[00:03.00]b";
+        let lrc = Lrc::parse(text);
+        assert_eq!(times(&lrc), vec![(1_500, "a"), (2_500, "b"), (3_500, "a")]);
+
+        // 只改写时间标签，其余内容原样保留
+        let retimed = retime_lrc(text, lrc.offset, &[(1_500, 1_000), (3_500, 3_200)]);
+        assert_eq!(
+            retimed,
+            "[ti:标题]\n[00:01.00][00:03.20]a\n纯文本\n[x:y]\n[00:02.50]b"
+        );
+        let again = Lrc::parse(&retimed);
+        assert_eq!(again.offset, 0);
+        assert_eq!(again.tags, lrc.tags);
+        assert_eq!(
+            times(&again),
+            vec![(1_000, "a"), (2_500, "b"), (3_200, "a")]
+        );
+
+        // 使用原有时间改写时解析结果不变，再次改写不再改变文本
+        let same = retime_lrc(text, lrc.offset, &[(1_500, 1_500)]);
+        assert_eq!(Lrc::parse(&same).lines, lrc.lines);
+        assert_eq!(retime_lrc(&same, 0, &[]), same);
+    }
+
+    #[test]
+    fn retime_translation() {
+        let original = "[offset:500]\n[00:02.00]a";
+        let translation = "[00:02.00]A";
+        let lyrics = Lyrics::parse(original, Some(translation), None);
+        let moved = [(1_500, 1_000)];
+        // 翻译没有自己的 offset 时使用原文的 offset
+        let retimed = Lyrics::parse(
+            &retime_lrc(original, lyrics.original.offset, &moved),
+            Some(&retime_lrc(translation, lyrics.original.offset, &moved)),
+            None,
+        );
+        assert_eq!(times(&retimed.original), vec![(1_000, "a")]);
+        assert_eq!(times(&retimed.translation), vec![(1_000, "A")]);
+    }
+
+    #[test]
+    fn lines_for_page() {
+        let lyrics = Lyrics::parse(
+            "[00:01.00]a\n[00:02.00]b",
+            Some("[00:01.00]A\n[00:02.00]"),
+            Some("[00:02.00]ro"),
+        );
+        assert_eq!(
+            lyrics.to_lines(true, true, true),
+            vec![
+                (1_000, "a\n".to_owned()),
+                (1_000, "A\n".to_owned()),
+                (2_000, "b\n".to_owned()),
+                (2_000, "ro\n".to_owned()),
+            ]
+        );
+        assert_eq!(
+            lyrics.to_lines(false, true, false),
+            vec![(1_000, "A\n".to_owned())]
+        );
+    }
+
+    #[test]
+    fn user_lyric_files() {
+        let root = std::env::temp_dir().join(format!("ncm-lyrics-test-{}", std::process::id()));
+        let dirs = [root.join("a"), root.join("b")];
+        fs::create_dir_all(dirs[0].join("sub")).unwrap();
+        fs::create_dir_all(&dirs[1]).unwrap();
+        for path in [
+            dirs[0].join("sub/周杰伦 - 晴天.lrc"),
+            dirs[0].join("Yesterday - The Beatles.LRC"),
+            dirs[0].join("ignored.txt"),
+            dirs[1].join("周杰伦-晴天.lrc"),
+        ] {
+            fs::write(path, "").unwrap();
+        }
+        let files = scan_lyric_files(&dirs);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            find_lyric_file(&files, "晴天", &["周杰伦".to_owned()]),
+            Some(&dirs[0].join("sub/周杰伦 - 晴天.lrc"))
+        );
+        assert_eq!(
+            find_lyric_file(
+                &files,
+                "yesterday",
+                &["x".to_owned(), "the beatles".to_owned()]
+            ),
+            Some(&dirs[0].join("Yesterday - The Beatles.LRC"))
+        );
+        assert_eq!(find_lyric_file(&files, "晴天", &[]), None);
+        assert_eq!(find_lyric_file(&files, "", &["周杰伦".to_owned()]), None);
+    }
+
+    #[test]
+    fn lyric_files_symlink_loop() {
+        let root = std::env::temp_dir().join(format!("ncm-lyrics-loop-{}", std::process::id()));
+        let sub = root.join("a");
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(sub.join("周杰伦 - 晴天.lrc"), "").unwrap();
+        std::os::unix::fs::symlink("..", sub.join("loop")).unwrap();
+        // 同一目录重复出现时只扫描一次
+        let files = scan_lyric_files(&[root.to_owned(), sub.to_owned()]);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            find_lyric_file(&files, "晴天", &["周杰伦".to_owned()]),
+            Some(&sub.join("周杰伦 - 晴天.lrc"))
+        );
+    }
+
+    #[test]
+    fn match_keys() {
+        assert_eq!(match_key("  Hello, World! "), "helloworld");
+        assert_eq!(match_key("周杰伦 - 晴天"), "周杰伦晴天");
+        assert_eq!(match_key("ÄB"), "äb");
+    }
+}
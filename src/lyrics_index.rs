@@ -0,0 +1,199 @@
+//
+// lyrics_index.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use log::*;
+use ncm_api::SongInfo;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs, path::PathBuf, sync::RwLock};
+
+use crate::{
+    audio::is_local_song,
+    lyrics::{Lrc, Lyrics, match_key},
+    ncmapi::NcmClient,
+    path::{DATA, LYRICS},
+};
+
+// 已缓存歌词的全文索引，首次使用时从文件加载
+static LYRICS_INDEX: Lazy<RwLock<LyricsIndex>> =
+    Lazy::new(|| RwLock::new(LyricsIndex::load_from_file()));
+
+fn index_file_path() -> PathBuf {
+    DATA.join("lyrics_index.json")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedSong {
+    song: SongInfo,
+    // (时间, 归一化后的歌词行)，包含原文、翻译与罗马音
+    lines: Vec<(u64, String)>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LyricsIndex {
+    songs: Vec<IndexedSong>,
+}
+
+impl LyricsIndex {
+    fn load_from_file() -> Self {
+        let path = index_file_path();
+        if !path.exists() {
+            return Self::default();
+        }
+        match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|err| {
+                error!("解析歌词索引失败: {:?}", err);
+                Self::default()
+            }),
+            Err(err) => {
+                error!("读取歌词索引失败: {:?}", err);
+                Self::default()
+            }
+        }
+    }
+
+    fn save_to_file(&self) {
+        match serde_json::to_string(self) {
+            Ok(json) => {
+                if let Err(err) = fs::write(index_file_path(), json) {
+                    error!("保存歌词索引失败: {:?}", err);
+                }
+            }
+            Err(err) => error!("序列化歌词索引失败: {:?}", err),
+        }
+    }
+
+    // 更新歌曲的索引，内容有变化时返回 true
+    fn update(&mut self, si: &SongInfo, lyrics: &Lyrics) -> bool {
+        let mut song = si.to_owned();
+        // 播放链接会过期，播放时重新获取
+        if !is_local_song(&song) {
+            song.song_url.clear();
+        }
+        let lrcs = [&lyrics.original, &lyrics.translation, &lyrics.romanization];
+        let mut lines: Vec<(u64, String)> = lrcs
+            .iter()
+            .flat_map(|lrc| lrc.lines.iter())
+            .map(|l| (l.time, match_key(&l.text)))
+            .filter(|(_, text)| !text.is_empty())
+            .collect();
+        lines.sort();
+        lines.dedup();
+        let entry = IndexedSong { song, lines };
+        match self.songs.iter_mut().find(|s| s.song.id == si.id) {
+            Some(old) if old.lines == entry.lines => false,
+            Some(old) => {
+                *old = entry;
+                true
+            }
+            None => {
+                self.songs.push(entry);
+                true
+            }
+        }
+    }
+}
+
+// 将歌词加入索引，内容有变化时保存
+pub fn index_lyrics(si: &SongInfo, lyrics: &Lyrics) {
+    let mut index = LYRICS_INDEX.write().unwrap();
+    if index.update(si, lyrics) {
+        index.save_to_file();
+    }
+}
+
+// 查找包含指定文字的歌曲，返回歌曲与第一处匹配行的时间（毫秒）
+// 匹配时忽略大小写、空白与标点
+pub fn search_lyrics(text: &str, offset: usize, limit: usize) -> Vec<(SongInfo, u64)> {
+    let key = match_key(text);
+    if key.is_empty() {
+        return Vec::new();
+    }
+    LYRICS_INDEX
+        .read()
+        .unwrap()
+        .songs
+        .iter()
+        .filter_map(|s| {
+            s.lines
+                .iter()
+                .find(|(_, line)| line.contains(&key))
+                .map(|(time, _)| (s.song.to_owned(), *time))
+        })
+        .skip(offset)
+        .take(limit)
+        .collect()
+}
+
+// 重新读取已索引及已知歌曲的歌词文件，移除已不存在的歌词
+// 歌词目录中未索引的文件依据 [ti:]、[ar:] 标签加入
+pub fn rebuild_lyrics_index(known: Vec<SongInfo>) -> usize {
+    let mut songs: Vec<SongInfo> = LYRICS_INDEX
+        .read()
+        .unwrap()
+        .songs
+        .iter()
+        .map(|s| s.song.to_owned())
+        .collect();
+    songs.extend(known);
+
+    let mut ids = HashSet::new();
+    let mut index = LyricsIndex::default();
+    for si in songs {
+        if !ids.insert(si.id) {
+            continue;
+        }
+        let Some((path, id)) = NcmClient::lyric_file(&si).filter(|(p, _)| p.exists()) else {
+            continue;
+        };
+        if let Ok(lyrics) = NcmClient::read_lyric_file(&path, id) {
+            ids.extend(id);
+            index.update(&si, &lyrics);
+        }
+    }
+
+    if let Ok(entries) = fs::read_dir(LYRICS.as_path()) {
+        for path in entries.flatten().map(|e| e.path()) {
+            let id = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<u64>().ok());
+            let Some(id) = id.filter(|id| !ids.contains(id)) else {
+                continue;
+            };
+            if !path.extension().is_some_and(|e| e == "lrc") {
+                continue;
+            }
+            let Ok(lyrics) = NcmClient::read_lyric_file(&path, Some(id)) else {
+                continue;
+            };
+            if let Some(si) = song_from_tags(id, &lyrics.original) {
+                index.update(&si, &lyrics);
+            }
+        }
+    }
+
+    let num = index.songs.len();
+    index.save_to_file();
+    *LYRICS_INDEX.write().unwrap() = index;
+    num
+}
+
+fn song_from_tags(id: u64, lrc: &Lrc) -> Option<SongInfo> {
+    let tag = |key: &str| {
+        lrc.tags
+            .iter()
+            .find(|(k, v)| k.eq_ignore_ascii_case(key) && !v.is_empty())
+            .map(|(_, v)| v.to_owned())
+    };
+    Some(SongInfo {
+        id,
+        name: tag("ti")?,
+        singer: tag("ar")?,
+        album: tag("al").unwrap_or_default(),
+        ..crate::utils::empty_song_info()
+    })
+}
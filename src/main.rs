@@ -1,11 +1,19 @@
 mod application;
 mod audio;
+mod cloud_disk;
+mod cloud_upload;
 mod config;
 mod gui;
+mod links;
+mod lyrics;
+mod lyrics_index;
 mod model;
 mod ncmapi;
+mod now_playing;
 mod path;
+mod remote;
 mod utils;
+mod webapi;
 mod window;
 
 use self::application::NeteaseCloudMusicGtk4Application;
@@ -52,7 +60,10 @@ fn main() {
     // Create a new GtkApplication. The application manages our main loop,
     // application windows, integration with the window manager/compositor, and
     // desktop features such as file opening and single-instance applications.
-    let app = NeteaseCloudMusicGtk4Application::new(APP_ID, &gio::ApplicationFlags::empty());
+    let app = NeteaseCloudMusicGtk4Application::new(
+        APP_ID,
+        &gio::ApplicationFlags::HANDLES_COMMAND_LINE,
+    );
 
     let _guard = MAINCONTEXT.acquire().unwrap();
 
@@ -193,6 +193,10 @@ pub enum SearchType {
     LikeAlbums,
     // 收藏的歌单
     LikeSongList,
+    // 本地音乐
+    LocalMusic,
+    // 搜索已缓存的歌词
+    LocalLyrics,
 }
 
 #[derive(Debug, Clone)]
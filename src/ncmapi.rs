@@ -7,13 +7,38 @@ use anyhow::Result;
 use cookie_store::{CookieStore, serde};
 use ncm_api::{CookieBuilder, CookieJar, MusicApi, SongInfo, SongQuality, SongUrl};
 
-use crate::path::{CACHE, LYRICS};
+use crate::{
+    audio::{
+        AudioFormat, TrackTags, local_song_path, netease_song_id, split_artists, write_tags,
+    },
+    cloud_disk::CloudDisk,
+    cloud_upload::CloudUploader,
+    lyrics::{
+        Lrc, Lyrics, find_lyric_file, normalize_lrc, retime_lrc, scan_lyric_files, set_lrc_offset,
+    },
+    lyrics_index::index_lyrics,
+    path::{
+        CACHE, get_cover_cache_path, get_edited_lyric_path, get_lyric_path, get_music_cache_path,
+        get_rlyric_path, get_tlyric_path, get_yrc_path,
+    },
+    utils::blocking,
+    webapi::{SongLyric, WebApi},
+};
 use log::{debug, error};
-use std::{fs, io, path::PathBuf};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
 
 const COOKIE_FILE: &str = "cookies.json";
 const MAX_CONS: usize = 32;
 
+// 启用优先使用用户歌词文件时，用户歌词目录中的歌词文件（匹配用文件名 -> 路径）
+static USER_LYRIC_FILES: Lazy<RwLock<HashMap<String, PathBuf>>> = Lazy::new(Default::default);
+
 pub const BASE_URL_LIST: [&str; 12] = [
     "https://music.163.com/",
     "https://music.163.com/eapi/clientlog",
@@ -32,23 +57,34 @@ pub const BASE_URL_LIST: [&str; 12] = [
 #[derive(Clone)]
 pub struct NcmClient {
     pub client: MusicApi,
+    // 代理地址，云盘等网页接口也需使用
+    proxy: Option<String>,
 }
 
 impl NcmClient {
     pub fn new() -> Self {
         Self {
             client: MusicApi::new(MAX_CONS),
+            proxy: None,
         }
     }
 
     pub fn from_cookie_jar(cookie_jar: CookieJar) -> Self {
         Self {
             client: MusicApi::from_cookie_jar(cookie_jar, MAX_CONS),
+            proxy: None,
         }
     }
 
     pub fn set_proxy(&mut self, proxy: String) -> Result<()> {
-        self.client.set_proxy(&proxy)
+        self.client.set_proxy(&proxy)?;
+        self.proxy = Some(proxy);
+        Ok(())
+    }
+
+    // 使用当前登录信息及代理创建网页接口客户端
+    fn web_api(&self) -> Result<WebApi> {
+        WebApi::new(self.client.cookie_jar().cloned(), self.proxy.as_deref())
     }
 
     pub fn get_api_rate(item: u32) -> u32 {
@@ -184,125 +220,306 @@ impl NcmClient {
 
     pub async fn create_qrcode(&self) -> Result<(PathBuf, String)> {
         let (qr_url, unikey) = self.client.login_qr_create().await?;
-        let mut path = CACHE.clone();
-        path.push("qrimage.png");
-        qrcode_generator::to_png_to_file(qr_url, qrcode_generator::QrCodeEcc::Low, 140, &path)?;
+        let path = Self::create_qrcode_image(&qr_url, "qrimage.png", 140)?;
         Ok((path, unikey))
     }
 
+    // 在缓存目录中生成二维码图片，用于登录及分享
+    pub fn create_qrcode_image(content: &str, file_name: &str, size: usize) -> Result<PathBuf> {
+        let path = CACHE.join(file_name);
+        qrcode_generator::to_png_to_file(content, qrcode_generator::QrCodeEcc::Low, size, &path)?;
+        Ok(path)
+    }
+
     pub async fn songs_url(&self, ids: &[u64], rate: u32) -> Result<Vec<SongUrl>> {
         self.client
             .songs_url_v1(ids, Self::get_quality(rate))
             .await
     }
 
-    pub async fn get_lyrics(&self, si: SongInfo) -> Result<Vec<(u64, String)>> {
-        // 歌词文件位置
-        let mut lyric_path = LYRICS.clone();
-        lyric_path.push(format!(
-            "{}-{}-{}.lrc",
-            si.name.replace('/', "／"),
-            si.singer,
-            si.album
-        ));
-        // 翻译歌词文件位置
-        let mut tlyric_path = LYRICS.clone();
-        tlyric_path.push(format!("{}.tlrc", si.id));
-        // 替换歌词时间
-        let re = regex::Regex::new(r"\[\d+:\d+.\d+\]").unwrap();
-        // 修正不正常的时间戳 [00:11:22]
-        let re_abnormal_ts = regex::Regex::new(r"^\[(\d+):(\d+):(\d+)\]").unwrap();
-        if !lyric_path.exists() {
-            if let Ok(lyr) = self.client.song_lyric(si.id).await {
-                debug!("歌词: {:?}", lyr);
-                // 添加歌词翻译
-                let mut lt = Vec::new();
-                for l in lyr.lyric.iter() {
-                    let mut time = 0;
-                    if l.len() >= 10 && re.is_match(l) {
-                        time = (l[1..3].parse::<u64>().unwrap() * 60
-                            + l[4..6].parse::<u64>().unwrap())
-                            * 1000
-                            + l[7..9].parse::<u64>().unwrap_or(0) * 10;
-                        let mut nl = re.replace_all(l, "").to_string();
-                        nl.push('\n');
-                        lt.push((time, nl));
-                    }
-                    for t in lyr.tlyric.iter() {
-                        if t.len() >= 10 && l.len() >= 10 && t.starts_with(&l[0..10]) {
-                            let mut nt = re.replace_all(t, "").to_string();
-                            nt.push('\n');
-                            lt.push((time, nt));
-                        }
-                    }
-                }
-                // 保存歌词文件
-                let lyric = lyr
-                    .lyric
-                    .into_iter()
-                    .map(|x| re_abnormal_ts.replace_all(&x, "[$1:$2.$3]").to_string())
-                    .collect::<Vec<String>>()
-                    .join("\n");
-                fs::write(&lyric_path, lyric)?;
-                if !lyr.tlyric.is_empty() {
-                    // 保存翻译歌词文件
-                    let tlyric = lyr
-                        .tlyric
-                        .into_iter()
-                        .map(|x| re_abnormal_ts.replace_all(&x, "[$1:$2.$3]").to_string())
-                        .collect::<Vec<String>>()
-                        .join("\n");
-                    fs::write(&tlyric_path, tlyric)?;
+    pub async fn get_lyrics(&self, si: SongInfo) -> Result<Lyrics> {
+        let Some((lyric_path, id)) = Self::lyric_file(&si) else {
+            anyhow::bail!("No lyrics found!")
+        };
+        // 本地歌曲同名 .lrc 文件没有翻译
+        let Some(id) = id else {
+            return Self::read_lyric_file(&lyric_path, None);
+        };
+        if lyric_path.exists() {
+            let lyrics = Self::read_lyric_file(&lyric_path, Some(id))?;
+            index_lyrics(&si, &lyrics);
+            return Ok(lyrics);
+        }
+        let lyric = match self.web_api()?.song_lyric(id).await {
+            Ok(lyr) => lyr,
+            Err(err) => {
+                debug!("获取逐字歌词失败: {:?}", err);
+                let Ok(lyr) = self.client.song_lyric(id).await else {
+                    anyhow::bail!("No lyrics found!")
+                };
+                SongLyric {
+                    lyric: lyr.lyric.join("\n"),
+                    tlyric: lyr.tlyric.join("\n"),
+                    ..SongLyric::default()
                 }
-                // 组织歌词+翻译
-                Ok(lt)
-            } else {
-                anyhow::bail!("No lyrics found!")
             }
+        };
+        let SongLyric {
+            lyric,
+            tlyric,
+            rlyric,
+            yrc,
+        } = lyric;
+        if lyric.trim().is_empty() {
+            anyhow::bail!("No lyrics found!")
+        }
+        // 保存歌词文件，翻译、罗马音及逐字歌词仅在存在时保存
+        let lyric = normalize_lrc(&lyric);
+        fs::write(&lyric_path, &lyric)?;
+        let tlyric = normalize_lrc(&tlyric);
+        if !tlyric.is_empty() {
+            fs::write(get_tlyric_path(id), &tlyric)?;
+        }
+        let rlyric = normalize_lrc(&rlyric);
+        if !rlyric.is_empty() {
+            fs::write(get_rlyric_path(id), &rlyric)?;
+        }
+        if !yrc.is_empty() {
+            fs::write(get_yrc_path(id), &yrc)?;
+        }
+        let mut lyrics = Lyrics::parse(&lyric, Some(&tlyric), Some(&rlyric));
+        lyrics.set_words(&yrc);
+        index_lyrics(&si, &lyrics);
+        Ok(lyrics)
+    }
+
+    // 歌词原文文件位置，以及翻译等附属文件使用的网易云歌曲 ID
+    // 依次使用校准副本、本地歌曲同名 .lrc 文件、用户歌词目录中的文件（此时没有附属文件）与网易云歌词缓存
+    pub fn lyric_file(si: &SongInfo) -> Option<(PathBuf, Option<u64>)> {
+        let edited_path = get_edited_lyric_path(si.id);
+        if edited_path.exists() {
+            return Some((edited_path, None));
+        }
+        if let Some(path) = local_song_path(si) {
+            let lyric_path = path.with_extension("lrc");
+            if lyric_path.exists() {
+                return Some((lyric_path, None));
+            }
+        }
+        let mut artists = split_artists(&si.singer);
+        artists.insert(0, si.singer.to_owned());
+        if let Some(path) = find_lyric_file(&USER_LYRIC_FILES.read().unwrap(), &si.name, &artists)
+            .filter(|p| p.exists())
+        {
+            return Some((path.to_owned(), None));
+        }
+        let id = netease_song_id(si)?;
+        Some((get_lyric_path(id), Some(id)))
+    }
+
+    // 扫描用户歌词目录，返回找到的歌词文件数量，目录为空时清除之前的结果
+    pub fn scan_user_lyric_files(dirs: &[PathBuf]) -> usize {
+        let files = scan_lyric_files(dirs);
+        let num = files.len();
+        *USER_LYRIC_FILES.write().unwrap() = files;
+        num
+    }
+
+    // 修改后的歌词保存位置：程序自己的缓存原地修改，用户提供的文件保存到校准副本
+    fn writable_lyric_file(si: &SongInfo, lyric_path: &Path, id: Option<u64>) -> PathBuf {
+        if id.is_some_and(|id| lyric_path == get_lyric_path(id)) {
+            lyric_path.to_owned()
         } else {
-            let lyric = fs::read_to_string(&lyric_path)?;
-            let lyrics: Vec<String> = lyric
-                .split('\n')
-                .collect::<Vec<&str>>()
-                .iter()
-                .map(|s| s.to_string())
-                .collect();
-            let mut tlyrics = vec![];
-            if tlyric_path.exists() {
-                let tlyric = fs::read_to_string(&tlyric_path)?;
-                tlyrics = tlyric
-                    .split('\n')
-                    .collect::<Vec<&str>>()
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect();
+            get_edited_lyric_path(si.id)
+        }
+    }
+
+    // 调整歌词的 offset（毫秒），返回调整后的值
+    pub fn adjust_lyric_offset(si: &SongInfo, delta: i64) -> Result<i64> {
+        let Some((lyric_path, id)) = Self::lyric_file(si).filter(|(p, _)| p.exists()) else {
+            anyhow::bail!("No lyrics found!")
+        };
+        let lyric = fs::read_to_string(&lyric_path)?;
+        let offset = Lrc::parse(&lyric).offset + delta;
+        let path = Self::writable_lyric_file(si, &lyric_path, id);
+        fs::write(path, set_lrc_offset(&lyric, offset))?;
+        Ok(offset)
+    }
+
+    // 按 (原时间, 新时间) 保存手动校准后的歌词，只改写时间标签
+    // 逐字歌词与新时间不再对应，一并删除
+    pub fn save_synced_lyrics(si: &SongInfo, moved: &[(u64, u64)]) -> Result<()> {
+        let Some((lyric_path, id)) = Self::lyric_file(si).filter(|(p, _)| p.exists()) else {
+            anyhow::bail!("No lyrics found!")
+        };
+        let lyric = fs::read_to_string(&lyric_path)?;
+        let offset = Lrc::parse(&lyric).offset;
+        let path = Self::writable_lyric_file(si, &lyric_path, id);
+        fs::write(path, retime_lrc(&lyric, offset, moved))?;
+        if let Some(id) = id {
+            for path in [get_tlyric_path(id), get_rlyric_path(id)] {
+                let Ok(lyric) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                // 翻译与罗马音没有自己的 offset 时跟随原文
+                let own = Lrc::parse(&lyric).offset;
+                let offset = if own == 0 { offset } else { own };
+                fs::write(path, retime_lrc(&lyric, offset, moved))?;
             }
-            // 添加歌词翻译
-            let mut lt = Vec::new();
-            for l in lyrics.iter() {
-                let mut time = 0;
-                if l.len() >= 10 && re.is_match(l) {
-                    time = (l[1..3].parse::<u64>().unwrap() * 60 + l[4..6].parse::<u64>().unwrap())
-                        * 1000
-                        + l[7..9].parse::<u64>().unwrap_or(0) * 10;
-                    let mut nl = re.replace_all(l, "").to_string();
-                    nl.push('\n');
-                    lt.push((time, nl));
-                }
-                for t in tlyrics.iter() {
-                    if t.len() >= 10 && l.len() >= 10 && t.starts_with(&l[0..10]) {
-                        let mut nt = re.replace_all(t, "").to_string();
-                        nt.push('\n');
-                        lt.push((time, nt));
-                    }
+            let yrc_path = get_yrc_path(id);
+            if yrc_path.exists() {
+                fs::remove_file(yrc_path)?;
+            }
+        }
+        Ok(())
+    }
+
+    // 读取并解析已缓存的歌词文件，有网易云歌曲 ID 时同时读取翻译、罗马音及逐字歌词
+    pub fn read_lyric_file(lyric_path: &Path, id: Option<u64>) -> Result<Lyrics> {
+        let lyric = fs::read_to_string(lyric_path)?;
+        let Some(id) = id else {
+            return Ok(Lyrics::parse(&lyric, None, None));
+        };
+        let tlyric = fs::read_to_string(get_tlyric_path(id)).ok();
+        let rlyric = fs::read_to_string(get_rlyric_path(id)).ok();
+        let mut lyrics = Lyrics::parse(&lyric, tlyric.as_deref(), rlyric.as_deref());
+        if let Ok(yrc) = fs::read_to_string(get_yrc_path(id)) {
+            lyrics.set_words(&yrc);
+        }
+        Ok(lyrics)
+    }
+
+    // 使用当前登录信息创建云盘上传器
+    pub fn cloud_uploader(&self) -> Result<CloudUploader<WebApi>> {
+        let http = self.web_api()?;
+        Ok(CloudUploader::new(http))
+    }
+
+    // 使用当前登录信息创建云盘管理
+    pub fn cloud_disk(&self) -> Result<CloudDisk<WebApi>> {
+        let http = self.web_api()?;
+        Ok(CloudDisk::new(http))
+    }
+
+    // 在线歌曲的曲目序号，SongInfo 中不包含该字段
+    pub async fn track_number(&self, id: u64) -> Result<Option<u32>> {
+        Ok(self.web_api()?.song_detail(id).await?.track)
+    }
+
+    // 下载歌曲到指定目录，并写入标签、封面及歌词
+    // 歌曲数据先写入目标目录中的临时文件，文件读写均在后台线程进行
+    pub async fn download_song(&self, si: &SongInfo, rate: u32, dir: &Path) -> Result<PathBuf> {
+        let http = self.web_api()?;
+        let part_path = dir.join(format!(".{}-{}.part", si.id, rate));
+        // 优先使用播放缓存
+        let cache_path = get_music_cache_path(si.id, rate);
+        let mut song_url = si.song_url.to_owned();
+        let fetched = if cache_path.exists() {
+            let (dir, part_path) = (dir.to_owned(), part_path.to_owned());
+            blocking(move || {
+                fs::create_dir_all(&dir)?;
+                fs::copy(&cache_path, &part_path)?;
+                Ok(AudioFormat::from_path(&part_path))
+            })
+            .await
+        } else {
+            if !song_url.starts_with("http") {
+                let urls = self.songs_url(&[si.id], rate).await?;
+                song_url = match urls.first() {
+                    Some(url) if !url.url.is_empty() => url.url.to_owned(),
+                    _ => anyhow::bail!("No song url found!"),
+                };
+            }
+            debug!("下载歌曲: {}", song_url);
+            let (dir, part_path, url, http) = (
+                dir.to_owned(),
+                part_path.to_owned(),
+                song_url.to_owned(),
+                http.clone(),
+            );
+            blocking(move || {
+                fs::create_dir_all(&dir)?;
+                http.download_to(&url, &part_path)?;
+                Ok(AudioFormat::from_path(&part_path))
+            })
+            .await
+        };
+        let format = match fetched {
+            Ok(format) => format,
+            Err(err) => {
+                fs::remove_file(&part_path).ok();
+                return Err(err);
+            }
+        };
+
+        // 无法识别文件头时沿用链接中的扩展名
+        let extension = match format {
+            AudioFormat::Unknown => url_extension(&song_url).unwrap_or(format.extension()),
+            format => format.extension(),
+        };
+        let file_name =
+            format!("{} - {}.{}", si.singer, si.name, extension).replace(['/', '\\'], "／");
+        let path = dir.join(file_name);
+
+        let mut tags = None;
+        let mut lyric_path = None;
+        if format.taggable() {
+            let detail = http
+                .song_detail(si.id)
+                .await
+                .map_err(|err| debug!("获取歌曲详情失败: {:?}", err))
+                .unwrap_or_default();
+            let mut track_tags = TrackTags::from_song_info(si, detail.track);
+            // 原尺寸封面
+            let pic_url = Some(detail.pic_url.as_str())
+                .filter(|url| !url.is_empty())
+                .unwrap_or(&si.pic_url);
+            if !pic_url.is_empty() {
+                track_tags.cover = http
+                    .get_bytes(pic_url)
+                    .await
+                    .map_err(|err| debug!("下载封面失败: {:?}", err))
+                    .ok();
+            }
+            // 歌词
+            if let Some((path, _)) = Self::lyric_file(si) {
+                if !path.exists() {
+                    self.get_lyrics(si.to_owned()).await.ok();
                 }
+                lyric_path = Some(path);
             }
-            // 组织歌词+翻译
-            Ok(lt)
+            tags = Some(track_tags);
         }
+
+        let cover_cache_path = get_cover_cache_path(si.album_id);
+        let dest = path.to_owned();
+        blocking(move || {
+            fs::rename(&part_path, &dest)?;
+            if let Some(mut tags) = tags {
+                if tags.cover.is_none() {
+                    tags.cover = fs::read(cover_cache_path).ok();
+                }
+                tags.lyrics = lyric_path
+                    .and_then(|path| fs::read_to_string(path).ok())
+                    .filter(|l| !l.trim().is_empty());
+                if let Err(err) = write_tags(&dest, &tags) {
+                    error!("写入歌曲标签失败: {:?}", err);
+                }
+            }
+            Ok(())
+        })
+        .await?;
+        Ok(path)
     }
 }
 
+// 链接路径中的文件扩展名，如 .../a.m4a?x=1 中的 m4a
+fn url_extension(url: &str) -> Option<&str> {
+    let path = url.split(['?', '#']).next()?;
+    let (_, ext) = path.rsplit_once('/')?.1.rsplit_once('.')?;
+    Some(ext).filter(|ext| !ext.is_empty() && ext.len() <= 5)
+}
+
 impl Default for NcmClient {
     fn default() -> Self {
         Self::new()
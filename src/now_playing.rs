@@ -0,0 +1,119 @@
+//
+// now_playing.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use log::*;
+use ncm_api::SongInfo;
+use serde::Serialize;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+};
+
+use crate::path::get_cover_cache_path;
+
+// 正在播放的歌曲及歌词，写入文件供状态栏、OBS 等外部工具读取
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct NowPlaying {
+    pub id: u64,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    // 已缓存封面的本地路径，否则为封面链接
+    pub cover: String,
+    pub playing: bool,
+    pub liked: bool,
+    // 音量百分比
+    pub volume: u32,
+    // 播放进度与时长（毫秒）
+    pub position: u64,
+    pub duration: u64,
+    pub lyric: String,
+    pub translation: String,
+}
+
+impl NowPlaying {
+    pub fn new(si: &SongInfo, playing: bool, position: u64) -> Self {
+        let cover_path = get_cover_cache_path(si.album_id);
+        let cover = if cover_path.exists() {
+            cover_path.to_string_lossy().to_string()
+        } else {
+            si.pic_url.to_owned()
+        };
+        Self {
+            id: si.id,
+            title: si.name.to_owned(),
+            artist: si.singer.to_owned(),
+            album: si.album.to_owned(),
+            cover,
+            playing,
+            position,
+            duration: si.duration,
+            ..Default::default()
+        }
+    }
+
+    // 文件后缀为 .json 时输出 JSON，否则输出文本
+    pub fn format(&self, path: &Path) -> String {
+        if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("json")) {
+            self.to_json()
+        } else {
+            self.to_text()
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    // “歌名 - 歌手”与当前歌词行
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!("{} - {}", self.title, self.artist)];
+        lines.extend(
+            [&self.lyric, &self.translation]
+                .into_iter()
+                .filter(|l| !l.is_empty())
+                .cloned(),
+        );
+        lines.join("\n")
+    }
+}
+
+// 先写入临时文件再重命名，避免读取方读到不完整的内容
+fn write_now_playing(path: &Path, content: &str) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)
+}
+
+// 在后台线程中写入正在播放文件，积压时只写入最新的内容
+#[derive(Debug)]
+pub struct NowPlayingWriter {
+    tx: mpsc::Sender<(PathBuf, String)>,
+}
+
+impl NowPlayingWriter {
+    pub fn spawn() -> Self {
+        let (tx, rx) = mpsc::channel::<(PathBuf, String)>();
+        thread::spawn(move || {
+            while let Ok(mut job) = rx.recv() {
+                while let Ok(next) = rx.try_recv() {
+                    job = next;
+                }
+                if let Err(err) = write_now_playing(&job.0, &job.1) {
+                    warn!("写入正在播放文件失败: {:?}", err);
+                }
+            }
+        });
+        Self { tx }
+    }
+
+    pub fn write(&self, path: PathBuf, content: String) {
+        self.tx.send((path, content)).ok();
+    }
+}
@@ -4,6 +4,7 @@
 // Distributed under terms of the GPL-3.0-or-later license.
 //
 
+use std::collections::HashSet;
 use std::path::PathBuf;
 use std::{fs, io};
 
@@ -66,6 +67,121 @@ pub fn get_music_cache_path(id: u64, rate: u32) -> PathBuf {
     CACHE.join(format!("music_{}_{}", id, rate))
 }
 
+// 专辑封面缓存位置
+pub fn get_cover_cache_path(album_id: u64) -> PathBuf {
+    CACHE.join(format!("{}-songlist.jpg", album_id))
+}
+
+// 歌词文件位置
+pub fn get_lyric_path(id: u64) -> PathBuf {
+    LYRICS.join(format!("{}.lrc", id))
+}
+
+// 用户歌词文件的校准副本位置（以播放列表中的歌曲 ID 命名），用户自己的文件保持只读
+pub fn get_edited_lyric_path(id: u64) -> PathBuf {
+    LYRICS.join(format!("{}.edited.lrc", id))
+}
+
+// 旧版以歌曲信息命名的歌词文件位置，仅用于迁移
+fn get_legacy_lyric_path(si: &ncm_api::SongInfo) -> PathBuf {
+    LYRICS.join(format!(
+        "{}-{}-{}.lrc",
+        si.name.replace('/', "／"),
+        si.singer,
+        si.album
+    ))
+}
+
+// 将旧版歌词文件重命名为以网易云歌曲 ID 命名，已存在新文件时保留旧文件
+pub fn migrate_legacy_lyric(si: &ncm_api::SongInfo, id: u64) -> bool {
+    let legacy = get_legacy_lyric_path(si);
+    let path = get_lyric_path(id);
+    if path.exists() || !legacy.is_file() {
+        return false;
+    }
+    match fs::rename(&legacy, &path) {
+        Ok(_) => {
+            debug!("迁移歌词文件: {:?} -> {:?}", legacy, path);
+            true
+        }
+        Err(err) => {
+            warn!("迁移歌词文件失败 {:?}: {:?}", legacy, err);
+            false
+        }
+    }
+}
+
+// 无法确定歌曲 ID 的旧版歌词文件名，之后启动时不再搜索
+fn unmatched_legacy_lyrics_path() -> PathBuf {
+    DATA.join("unmatched_legacy_lyrics.json")
+}
+
+fn load_unmatched_legacy_lyrics() -> HashSet<String> {
+    fs::read_to_string(unmatched_legacy_lyrics_path())
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn add_unmatched_legacy_lyrics(files: &[PathBuf]) {
+    if files.is_empty() {
+        return;
+    }
+    let mut names = load_unmatched_legacy_lyrics();
+    names.extend(
+        files
+            .iter()
+            .filter_map(|f| f.file_name())
+            .map(|f| f.to_string_lossy().to_string()),
+    );
+    match serde_json::to_string(&names) {
+        Ok(json) => {
+            if let Err(err) = fs::write(unmatched_legacy_lyrics_path(), json) {
+                error!("保存未匹配的旧版歌词文件失败: {:?}", err);
+            }
+        }
+        Err(err) => error!("序列化未匹配的旧版歌词文件失败: {:?}", err),
+    }
+}
+
+// 歌词目录中尚未迁移的旧版歌词文件（{歌名}-{歌手}-{专辑}.lrc）
+pub fn legacy_lyric_files() -> Vec<PathBuf> {
+    let unmatched = load_unmatched_legacy_lyrics();
+    let Ok(entries) = fs::read_dir(LYRICS.as_path()) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|e| e == "lrc"))
+        .filter(|path| {
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                return false;
+            };
+            // 以歌曲 ID 命名的缓存与校准副本
+            let id = stem.strip_suffix(".edited").unwrap_or(stem);
+            id.parse::<u64>().is_err()
+                && stem.matches('-').count() >= 2
+                && !unmatched.contains(&format!("{}.lrc", stem))
+        })
+        .collect()
+}
+
+// 翻译歌词文件位置
+pub fn get_tlyric_path(id: u64) -> PathBuf {
+    LYRICS.join(format!("{}.tlrc", id))
+}
+
+// 罗马音歌词文件位置
+pub fn get_rlyric_path(id: u64) -> PathBuf {
+    LYRICS.join(format!("{}.rlrc", id))
+}
+
+// 逐字歌词文件位置
+pub fn get_yrc_path(id: u64) -> PathBuf {
+    LYRICS.join(format!("{}.yrc", id))
+}
+
 pub fn get_cache_size() -> (f64, String) {
     let size: u64 = {
         match CACHE_SIZE.get() {
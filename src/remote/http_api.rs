@@ -0,0 +1,468 @@
+//
+// http_api.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use anyhow::{Result, anyhow};
+use async_channel::Sender;
+use gtk::gio;
+use log::*;
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    fs,
+    io::Read,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
+use tungstenite::{Message, WebSocket, handshake::derive_accept_key, protocol::Role};
+
+use crate::{
+    application::Action,
+    model::{SearchResult, SearchType},
+    path::CACHE,
+};
+
+use super::{RemoteEvent, RemoteSong, constant_time_eq, request_action};
+
+const SEARCH_LIMIT: u16 = 30;
+// 处理请求的线程数，WebSocket 连接另外使用独立线程
+const WORKERS: usize = 4;
+const MAX_EVENT_CLIENTS: usize = 8;
+const MAX_BODY_SIZE: u64 = 64 * 1024;
+const WEB_RESOURCE_PATH: &str = "/com/gitee/gmg137/NeteaseCloudMusicGtk4/web";
+
+type ApiResult = Result<Value, (u16, String)>;
+
+struct Context {
+    sender: Sender<Action>,
+    token: String,
+    // WebSocket 客户端，断开后在下次推送时移除
+    clients: Arc<Mutex<Vec<mpsc::Sender<String>>>>,
+    event_clients: AtomicUsize,
+}
+
+// 局域网远程控制接口，所有请求需携带令牌：
+//   请求头 Authorization: Bearer <token> 或查询参数 ?token=<token>
+// 接口：
+//   GET  /api/status                当前播放状态
+//   GET  /api/queue                 播放列表
+//   POST /api/queue   {id, play}    添加歌曲，play 为 true 时立即播放
+//   POST /api/play | /api/pause | /api/toggle | /api/next | /api/prev
+//   POST /api/seek    {position}    跳转（毫秒）
+//   POST /api/volume  {volume}      音量（0-100）
+//   GET  /api/search?q=<text>       搜索歌曲
+//   POST /api/like    {like}        收藏当前歌曲，省略 like 时切换
+//   GET  /api/events                WebSocket，推送播放事件
+//   GET  /api/cover                 当前歌曲的封面
+// 其余 GET 请求返回网页界面
+pub struct RemoteApi {
+    server: Arc<Server>,
+    clients: Arc<Mutex<Vec<mpsc::Sender<String>>>>,
+}
+
+impl RemoteApi {
+    // lan 为 false 时仅监听本机
+    pub fn start(sender: Sender<Action>, port: u16, lan: bool, token: String) -> Result<Self> {
+        let host = if lan { "0.0.0.0" } else { "127.0.0.1" };
+        let server = Server::http((host, port)).map_err(|err| anyhow!("{}", err))?;
+        let server = Arc::new(server);
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let ctx = Arc::new(Context {
+            sender,
+            token,
+            clients: clients.clone(),
+            event_clients: AtomicUsize::new(0),
+        });
+
+        for _ in 0..WORKERS {
+            let incoming = server.clone();
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                for request in incoming.incoming_requests() {
+                    handle_request(&ctx, request);
+                }
+                debug!("远程控制服务已停止");
+            });
+        }
+        info!("远程控制服务已启动: {}:{}", host, port);
+
+        Ok(Self { server, clients })
+    }
+
+    pub fn broadcast(&self, event: &RemoteEvent) {
+        let Ok(mut clients) = self.clients.lock() else {
+            return;
+        };
+        if clients.is_empty() {
+            return;
+        }
+        let Ok(message) = serde_json::to_string(event) else {
+            return;
+        };
+        clients.retain(|client| client.send(message.clone()).is_ok());
+    }
+}
+
+impl Drop for RemoteApi {
+    fn drop(&mut self) {
+        // 每次只唤醒一个等待请求的线程
+        for _ in 0..WORKERS {
+            self.server.unblock();
+        }
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.clear();
+        }
+    }
+}
+
+impl Context {
+    // authorization 为请求头 Authorization 的值
+    fn authorized(&self, authorization: Option<&str>, query: &HashMap<String, String>) -> bool {
+        if self.token.is_empty() {
+            return false;
+        }
+        authorization
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .or(query.get("token").map(String::as_str))
+            .is_some_and(|token| constant_time_eq(token.as_bytes(), self.token.as_bytes()))
+    }
+
+    fn send(&self, action: Action) -> ApiResult {
+        self.sender
+            .send_blocking(action)
+            .map_err(|_| (503, "player unavailable".to_owned()))?;
+        Ok(json!({ "ok": true }))
+    }
+}
+
+fn handle_request(ctx: &Arc<Context>, mut request: Request) {
+    let url = request.url().to_owned();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let query = parse_query(query);
+
+    // 网页界面无需令牌，页面中的接口请求仍需认证
+    if *request.method() == Method::Get && !path.starts_with("/api/") {
+        serve_web(request, path);
+        return;
+    }
+    let authorization = header_value(&request, "Authorization");
+    if !ctx.authorized(authorization.as_deref(), &query) {
+        respond(request, Err((401, "unauthorized".to_owned())));
+        return;
+    }
+    if path == "/api/events" {
+        serve_events(ctx, request);
+        return;
+    }
+    if path == "/api/cover" {
+        serve_cover(ctx, request);
+        return;
+    }
+
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_SIZE)
+        .read_to_string(&mut body)
+        .ok();
+    let body: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+
+    let result = route(ctx, request.method(), path, &query, &body);
+    respond(request, result);
+}
+
+fn route(
+    ctx: &Context,
+    method: &Method,
+    path: &str,
+    query: &HashMap<String, String>,
+    body: &Value,
+) -> ApiResult {
+    match (method, path) {
+        (Method::Get, "/api/status") => {
+            let now_playing = request_action(&ctx.sender, Action::GetNowPlaying).flatten();
+            Ok(json!(now_playing))
+        }
+        (Method::Get, "/api/queue") => {
+            let (songs, current) = request_action(&ctx.sender, Action::GetPlayList)
+                .ok_or((503, "player unavailable".to_owned()))?;
+            let songs: Vec<RemoteSong> = songs.iter().map(RemoteSong::from).collect();
+            Ok(json!({ "current": current, "songs": songs }))
+        }
+        (Method::Post, "/api/queue") => {
+            let id = body_u64(body, "id")?;
+            let play = body.get("play").and_then(Value::as_bool).unwrap_or(false);
+            ctx.send(Action::AddTrackById(id, play))
+        }
+        (Method::Post, "/api/play") => ctx.send(Action::SetPlaying(true)),
+        (Method::Post, "/api/pause") => ctx.send(Action::SetPlaying(false)),
+        (Method::Post, "/api/toggle") => ctx.send(Action::TogglePlayPause),
+        (Method::Post, "/api/next") => ctx.send(Action::PlayNextSong),
+        (Method::Post, "/api/prev") => ctx.send(Action::PlayPreviousSong),
+        (Method::Post, "/api/seek") => ctx.send(Action::SeekTo(body_u64(body, "position")?)),
+        (Method::Post, "/api/volume") => {
+            let volume = body_u64(body, "volume")?.min(100);
+            ctx.send(Action::SetVolume(volume as f64 / 100.0))
+        }
+        (Method::Get, "/api/search") => {
+            let text = query
+                .get("q")
+                .filter(|q| !q.is_empty())
+                .ok_or((400, "missing q".to_owned()))?;
+            let limit = query
+                .get("limit")
+                .and_then(|l| l.parse().ok())
+                .unwrap_or(SEARCH_LIMIT);
+            let res = request_action(&ctx.sender, |callback| {
+                Action::Search(text.to_owned(), SearchType::Song, 0, limit, callback)
+            });
+            let Some(SearchResult::Songs(sis, _)) = res else {
+                return Err((502, "search failed".to_owned()));
+            };
+            let songs: Vec<RemoteSong> = sis.iter().map(RemoteSong::from).collect();
+            Ok(json!(songs))
+        }
+        (Method::Post, "/api/like") => {
+            let like = body.get("like").and_then(Value::as_bool);
+            ctx.send(Action::LikeCurrentSong(like))
+        }
+        _ => Err((404, "not found".to_owned())),
+    }
+}
+
+// 网页界面打包在 gresource 中
+fn serve_web(request: Request, path: &str) {
+    let name = match path {
+        "/" => "index.html",
+        path => path.trim_start_matches('/'),
+    };
+    let content_type = match name.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        _ => "application/octet-stream",
+    };
+    let resource = format!("{WEB_RESOURCE_PATH}/{name}");
+    match gio::resources_lookup_data(&resource, gio::ResourceLookupFlags::NONE) {
+        Ok(data) => {
+            let response = Response::from_data(data.to_vec())
+                .with_header(make_header("Content-Type", content_type));
+            request.respond(response).ok();
+        }
+        Err(_) => respond(request, Err((404, "not found".to_owned()))),
+    }
+}
+
+// 当前歌曲已缓存的封面
+fn serve_cover(ctx: &Context, request: Request) {
+    let cover = request_action(&ctx.sender, Action::GetNowPlaying)
+        .flatten()
+        .map(|now_playing| PathBuf::from(now_playing.cover))
+        .filter(|path| path.starts_with(CACHE.as_path()));
+    match cover.and_then(|path| fs::read(path).ok()) {
+        Some(data) => {
+            let response =
+                Response::from_data(data).with_header(make_header("Content-Type", "image/jpeg"));
+            request.respond(response).ok();
+        }
+        None => respond(request, Err((404, "not found".to_owned()))),
+    }
+}
+
+// 升级为 WebSocket 并在独立线程中持续推送事件，客户端发送的消息将被忽略
+fn serve_events(ctx: &Arc<Context>, request: Request) {
+    let Some(key) = header_value(&request, "Sec-WebSocket-Key") else {
+        respond(request, Err((400, "websocket required".to_owned())));
+        return;
+    };
+    // 先占用名额再升级，避免并发升级时超出上限
+    let reserved = ctx
+        .event_clients
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+            (count < MAX_EVENT_CLIENTS).then_some(count + 1)
+        });
+    if reserved.is_err() {
+        respond(request, Err((503, "too many clients".to_owned())));
+        return;
+    }
+    let response = Response::new_empty(StatusCode(101))
+        .with_header(make_header("Upgrade", "websocket"))
+        .with_header(make_header("Connection", "Upgrade"))
+        .with_header(make_header(
+            "Sec-WebSocket-Accept",
+            &derive_accept_key(key.as_bytes()),
+        ));
+    let stream = request.upgrade("websocket", response);
+    let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+
+    let (tx, rx) = mpsc::channel();
+    if let Ok(mut clients) = ctx.clients.lock() {
+        clients.push(tx);
+    }
+    let ctx = ctx.clone();
+    thread::spawn(move || {
+        while let Ok(message) = rx.recv() {
+            if let Err(err) = socket.send(Message::text(message)) {
+                debug!("WebSocket 客户端已断开: {:?}", err);
+                break;
+            }
+        }
+        socket.close(None).ok();
+        ctx.event_clients.fetch_sub(1, Ordering::Relaxed);
+    });
+}
+
+fn respond(request: Request, result: ApiResult) {
+    let (status, value) = match result {
+        Ok(value) => (200, value),
+        Err((status, error)) => (status, json!({ "error": error })),
+    };
+    let response = Response::from_string(value.to_string())
+        .with_status_code(status)
+        .with_header(make_header(
+            "Content-Type",
+            "application/json; charset=utf-8",
+        ));
+    if let Err(err) = request.respond(response) {
+        debug!("远程控制响应失败: {:?}", err);
+    }
+}
+
+fn make_header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).unwrap()
+}
+
+fn header_value(request: &Request, field: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(field))
+        .map(|h| h.value.as_str().to_owned())
+}
+
+fn body_u64(body: &Value, key: &str) -> Result<u64, (u16, String)> {
+    body.get(key)
+        .and_then(Value::as_u64)
+        .ok_or((400, format!("missing {key}")))
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (decode_component(key), decode_component(value)))
+        .collect()
+}
+
+// 解码 URL 查询参数中的 %XX 与 +
+fn decode_component(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let byte = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match byte {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(token: &str) -> Context {
+        Context {
+            sender: async_channel::unbounded().0,
+            token: token.to_owned(),
+            clients: Arc::new(Mutex::new(Vec::new())),
+            event_clients: AtomicUsize::new(0),
+        }
+    }
+
+    #[test]
+    fn decode() {
+        let cases = [
+            ("abc", "abc"),
+            ("a+b", "a b"),
+            ("a%20b", "a b"),
+            ("%E6%99%B4%E5%A4%A9", "晴天"),
+            ("%e6%99%b4", "晴"),
+            ("100%", "100%"),
+            ("%4", "%4"),
+            ("%zz1", "%zz1"),
+            ("%2B%25", "+%"),
+            // 不完整的 UTF-8 序列
+            ("%E6", "\u{fffd}"),
+            ("", ""),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(decode_component(text), expected, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn query() {
+        let query = parse_query("q=%E6%99%B4%E5%A4%A9+live&token=a%26b&flag&=x&empty=");
+        assert_eq!(query.get("q").map(String::as_str), Some("晴天 live"));
+        assert_eq!(query.get("token").map(String::as_str), Some("a&b"));
+        assert_eq!(query.get("empty").map(String::as_str), Some(""));
+        assert_eq!(query.get("").map(String::as_str), Some("x"));
+        assert!(!query.contains_key("flag"));
+        assert!(parse_query("").is_empty());
+    }
+
+    #[test]
+    fn authorization() {
+        let query = |token: Option<&str>| {
+            token
+                .map(|t| parse_query(&format!("token={t}")))
+                .unwrap_or_default()
+        };
+        let cases = [
+            ("secret", Some("Bearer secret"), None, true),
+            ("secret", None, Some("secret"), true),
+            ("secret", Some("Bearer wrong"), Some("secret"), false),
+            ("secret", Some("Bearer secret"), Some("wrong"), true),
+            ("secret", Some("Basic secret"), Some("secret"), true),
+            ("secret", Some("Basic secret"), None, false),
+            ("secret", Some("bearer secret"), None, false),
+            ("secret", Some("Bearer secre"), None, false),
+            ("secret", None, Some("secrets"), false),
+            ("secret", None, None, false),
+            // 未设置令牌时拒绝所有请求
+            ("", Some("Bearer "), Some(""), false),
+            ("", None, None, false),
+        ];
+        for (token, authorization, query_token, expected) in cases {
+            assert_eq!(
+                context(token).authorized(authorization, &query(query_token)),
+                expected,
+                "{token:?} {authorization:?} {query_token:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//
+// mod.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+mod http_api;
+mod mpd;
+
+pub use http_api::*;
+pub use mpd::*;
+
+use async_channel::Sender;
+use ncm_api::SongInfo;
+use serde::Serialize;
+use std::{fs, io::Read, sync::Arc};
+
+use crate::application::{Action, ActionCallback};
+
+// 远程控制使用的歌曲信息
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteSong {
+    pub id: u64,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub album_id: u64,
+    pub cover: String,
+    // 时长（毫秒）
+    pub duration: u64,
+}
+
+impl From<&SongInfo> for RemoteSong {
+    fn from(si: &SongInfo) -> Self {
+        Self {
+            id: si.id,
+            title: si.name.to_owned(),
+            artist: si.singer.to_owned(),
+            album: si.album.to_owned(),
+            album_id: si.album_id,
+            cover: si.pic_url.to_owned(),
+            duration: si.duration,
+        }
+    }
+}
+
+// 推送给远程客户端的播放事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RemoteEvent {
+    PlayStart { song: RemoteSong },
+    State { state: String },
+    // 播放进度（毫秒）
+    Position { position: u64 },
+}
+
+// 通过 Action 通道请求数据，在非 GTK 线程中阻塞等待回调结果
+// Action 未调用回调便被丢弃时返回 None
+pub fn request_action<T: Send + 'static>(
+    sender: &Sender<Action>,
+    action: impl FnOnce(ActionCallback<T>) -> Action,
+) -> Option<T> {
+    let (tx, rx) = async_channel::bounded(1);
+    let action = action(Arc::new(move |res| {
+        tx.try_send(res).ok();
+    }));
+    sender.send_blocking(action).ok()?;
+    rx.recv_blocking().ok()
+}
+
+// 逐字节比较全部内容，耗时与不同之处的位置无关，用于校验令牌
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// 生成远程控制令牌，取自系统随机源的 24 字节，以十六进制表示
+pub fn generate_token() -> std::io::Result<String> {
+    let mut bytes = [0u8; 24];
+    fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
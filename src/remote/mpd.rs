@@ -0,0 +1,707 @@
+//
+// mpd.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use anyhow::Result;
+use async_channel::Sender;
+use log::*;
+use ncm_api::SongInfo;
+use std::{
+    collections::{BTreeSet, HashMap, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    application::Action,
+    audio::is_local_song,
+    links::{NeteaseLink, parse_link},
+    model::{SearchResult, SearchType},
+    now_playing::NowPlaying,
+};
+
+use super::{RemoteEvent, constant_time_eq, request_action};
+
+const MPD_VERSION: &str = "0.23.0";
+const SEARCH_LIMIT: u16 = 50;
+// 读取超时，用于在 idle 时检查事件与停止标志
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+// 单行命令的最大长度与同时连接的客户端数量
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+const MAX_CLIENTS: usize = 16;
+// 命令列表中可暂存的命令数量
+const MAX_COMMAND_LIST: usize = 256;
+
+const ACK_ERROR_ARG: u32 = 2;
+const ACK_ERROR_PASSWORD: u32 = 3;
+const ACK_ERROR_PERMISSION: u32 = 4;
+const ACK_ERROR_UNKNOWN: u32 = 5;
+const ACK_ERROR_NO_EXIST: u32 = 50;
+const ACK_ERROR_SYSTEM: u32 = 52;
+
+const COMMANDS: &[&str] = &[
+    "add",
+    "addid",
+    "clear",
+    "close",
+    "command_list_begin",
+    "command_list_end",
+    "command_list_ok_begin",
+    "commands",
+    "currentsong",
+    "find",
+    "idle",
+    "next",
+    "noidle",
+    "notcommands",
+    "outputs",
+    "password",
+    "pause",
+    "ping",
+    "play",
+    "playid",
+    "playlistid",
+    "playlistinfo",
+    "plchanges",
+    "previous",
+    "search",
+    "seek",
+    "seekcur",
+    "seekid",
+    "setvol",
+    "stats",
+    "status",
+    "stop",
+    "tagtypes",
+];
+
+type Ack = (u32, String);
+
+enum Reply {
+    Output(String),
+    Idle(Vec<String>),
+    Close,
+}
+
+struct Context {
+    sender: Sender<Action>,
+    // 连接后需先发送的密码，为 None 时无需认证
+    password: Option<String>,
+    stopped: AtomicBool,
+    connections: AtomicUsize,
+    // 客户端 idle 订阅，断开后在下次通知时移除
+    clients: Mutex<Vec<mpsc::Sender<&'static str>>>,
+    // 歌曲 ID -> MPD 歌曲 Id，同一首歌在播放列表中只出现一次，列表变化后 Id 保持不变
+    song_ids: Mutex<HashMap<u64, u32>>,
+}
+
+impl Context {
+    fn song_id(&self, id: u64) -> u32 {
+        let mut ids = self.song_ids.lock().unwrap();
+        let next = ids.len() as u32 + 1;
+        *ids.entry(id).or_insert(next)
+    }
+
+    // MPD 歌曲 Id 在播放列表中的位置
+    fn song_pos(&self, songs: &[SongInfo], id: u32) -> Option<usize> {
+        let ids = self.song_ids.lock().unwrap();
+        songs.iter().position(|si| ids.get(&si.id) == Some(&id))
+    }
+}
+
+// 连接结束时减少连接数
+struct Connection<'a>(&'a AtomicUsize);
+
+impl Drop for Connection<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// 单个客户端连接的状态
+struct Session {
+    authorized: bool,
+}
+
+// MPD 协议服务，供 ncmpcpp 等 MPD 客户端控制播放
+pub struct MpdServer {
+    ctx: Arc<Context>,
+    addr: SocketAddr,
+}
+
+impl MpdServer {
+    // lan 为 false 时仅监听本机；password 不为 None 时客户端需先通过 password 命令认证
+    pub fn start(
+        sender: Sender<Action>,
+        port: u16,
+        lan: bool,
+        password: Option<String>,
+    ) -> Result<Self> {
+        let host = if lan {
+            Ipv4Addr::UNSPECIFIED
+        } else {
+            Ipv4Addr::LOCALHOST
+        };
+        let listener = TcpListener::bind((host, port))?;
+        let ctx = Arc::new(Context {
+            sender,
+            password,
+            stopped: AtomicBool::new(false),
+            connections: AtomicUsize::new(0),
+            clients: Mutex::new(Vec::new()),
+            song_ids: Mutex::new(HashMap::new()),
+        });
+
+        let accept_ctx = ctx.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accept_ctx.stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                match stream {
+                    Ok(_) if accept_ctx.connections.load(Ordering::Relaxed) >= MAX_CLIENTS => {
+                        warn!("MPD 客户端连接过多，拒绝新连接");
+                    }
+                    Ok(stream) => {
+                        accept_ctx.connections.fetch_add(1, Ordering::Relaxed);
+                        let ctx = accept_ctx.clone();
+                        thread::spawn(move || {
+                            let _connection = Connection(&ctx.connections);
+                            if let Err(err) = handle_client(&ctx, stream) {
+                                debug!("MPD 客户端连接断开: {:?}", err);
+                            }
+                        });
+                    }
+                    Err(err) => warn!("MPD 服务接受连接失败: {:?}", err),
+                }
+            }
+            debug!("MPD 服务已停止");
+        });
+        info!("MPD 服务已启动: {}:{}", host, port);
+
+        Ok(Self {
+            ctx,
+            addr: SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+        })
+    }
+
+    pub fn broadcast(&self, event: &RemoteEvent) {
+        let subsystems: &[&'static str] = match event {
+            RemoteEvent::PlayStart { .. } => &["player", "playlist"],
+            RemoteEvent::State { .. } => &["player"],
+            RemoteEvent::Position { .. } => return,
+        };
+        if let Ok(mut clients) = self.ctx.clients.lock() {
+            clients.retain(|client| subsystems.iter().all(|s| client.send(*s).is_ok()));
+        }
+    }
+}
+
+impl Drop for MpdServer {
+    fn drop(&mut self) {
+        self.ctx.stopped.store(true, Ordering::Relaxed);
+        // 唤醒阻塞在 accept 上的线程
+        TcpStream::connect(self.addr).ok();
+    }
+}
+
+fn handle_client(ctx: &Context, stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    writer.write_all(format!("OK MPD {MPD_VERSION}\n").as_bytes())?;
+
+    let (tx, events) = mpsc::channel();
+    if let Ok(mut clients) = ctx.clients.lock() {
+        clients.push(tx);
+    }
+
+    let mut session = Session {
+        authorized: ctx.password.is_none(),
+    };
+    let mut buf = Vec::new();
+    let mut pending = BTreeSet::new();
+    let mut idle: Option<Vec<String>> = None;
+    // (是否回复 list_OK, 命令列表)
+    let mut command_list: Option<(bool, Vec<String>)> = None;
+    while !ctx.stopped.load(Ordering::Relaxed) {
+        pending.extend(events.try_iter());
+        if let Some(filter) = &idle {
+            let changed: Vec<&str> = pending
+                .iter()
+                .copied()
+                .filter(|s| filter.is_empty() || filter.iter().any(|f| f == s))
+                .collect();
+            if !changed.is_empty() {
+                let mut output = String::new();
+                for subsystem in changed {
+                    pending.remove(subsystem);
+                    output.push_str(&format!("changed: {subsystem}\n"));
+                }
+                output.push_str("OK\n");
+                writer.write_all(output.as_bytes())?;
+                idle = None;
+            }
+        }
+
+        let limit = (MAX_LINE_LENGTH - buf.len()) as u64;
+        match (&mut reader).take(limit).read_until(b'\n', &mut buf) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                continue;
+            }
+            Err(err) => return Err(err),
+        }
+        // 超时可能只读到半行，等待剩余部分
+        if !buf.ends_with(b"\n") {
+            if buf.len() >= MAX_LINE_LENGTH {
+                return Err(io::Error::new(ErrorKind::InvalidData, "line too long"));
+            }
+            continue;
+        }
+        let line = String::from_utf8_lossy(&buf).trim().to_owned();
+        buf.clear();
+
+        if idle.is_some() {
+            // idle 期间只接受 noidle，其它命令与 MPD 相同报错并断开连接
+            if line != "noidle" {
+                let err = (
+                    ACK_ERROR_UNKNOWN,
+                    "Only \"noidle\" is allowed during idle".to_owned(),
+                );
+                writer.write_all(ack(0, &line, err).as_bytes())?;
+                break;
+            }
+            idle = None;
+            writer.write_all(b"OK\n")?;
+            continue;
+        }
+
+        match line.as_str() {
+            "command_list_begin" => command_list = Some((false, Vec::new())),
+            "command_list_ok_begin" => command_list = Some((true, Vec::new())),
+            "command_list_end" => {
+                let Some((list_ok, commands)) = command_list.take() else {
+                    let err = (ACK_ERROR_ARG, "Not in command list".to_owned());
+                    writer.write_all(ack(0, &line, err).as_bytes())?;
+                    continue;
+                };
+                let mut output = String::new();
+                let mut failed = false;
+                for (index, command) in commands.iter().enumerate() {
+                    match execute(ctx, &mut session, command) {
+                        Ok(Reply::Output(out)) => {
+                            output.push_str(&out);
+                            if list_ok {
+                                output.push_str("list_OK\n");
+                            }
+                        }
+                        Ok(Reply::Idle(_) | Reply::Close) => {}
+                        Err(err) => {
+                            output.push_str(&ack(index, command, err));
+                            failed = true;
+                            break;
+                        }
+                    }
+                }
+                if !failed {
+                    output.push_str("OK\n");
+                }
+                writer.write_all(output.as_bytes())?;
+            }
+            _ if command_list
+                .as_ref()
+                .is_some_and(|(_, commands)| commands.len() >= MAX_COMMAND_LIST) =>
+            {
+                // 与 MPD 相同，命令列表过长时报错并断开连接
+                let err = (ACK_ERROR_ARG, "Command list too long".to_owned());
+                writer.write_all(ack(0, &line, err).as_bytes())?;
+                break;
+            }
+            _ if command_list.is_some() => {
+                if let Some((_, commands)) = command_list.as_mut() {
+                    commands.push(line);
+                }
+            }
+            _ => match execute(ctx, &mut session, &line) {
+                Ok(Reply::Output(out)) => writer.write_all(format!("{out}OK\n").as_bytes())?,
+                Ok(Reply::Idle(filter)) => idle = Some(filter),
+                Ok(Reply::Close) => break,
+                Err(err) => writer.write_all(ack(0, &line, err).as_bytes())?,
+            },
+        }
+    }
+    Ok(())
+}
+
+fn ack(index: usize, line: &str, (error, message): Ack) -> String {
+    let command = line.split_whitespace().next().unwrap_or_default();
+    format!("ACK [{error}@{index}] {{{command}}} {message}\n")
+}
+
+fn execute(ctx: &Context, session: &mut Session, line: &str) -> Result<Reply, Ack> {
+    let args = split_args(line);
+    let Some((command, args)) = args.split_first() else {
+        return Err((ACK_ERROR_UNKNOWN, "No command given".to_owned()));
+    };
+    if command == "password" {
+        let password = arg(args, 0)?;
+        // 未设置密码时接受任意密码
+        let valid = ctx
+            .password
+            .as_ref()
+            .is_none_or(|p| constant_time_eq(p.as_bytes(), password.as_bytes()));
+        if !valid {
+            return Err((ACK_ERROR_PASSWORD, "incorrect password".to_owned()));
+        }
+        session.authorized = true;
+        return Ok(Reply::Output(String::new()));
+    }
+    if !session.authorized && !matches!(command.as_str(), "close" | "ping") {
+        return Err((
+            ACK_ERROR_PERMISSION,
+            format!("you don't have permission for \"{command}\""),
+        ));
+    }
+    let output = match command.as_str() {
+        "ping" | "binarylimit" => String::new(),
+        "close" => return Ok(Reply::Close),
+        "idle" => return Ok(Reply::Idle(args.to_vec())),
+        "commands" => COMMANDS.iter().map(|c| format!("command: {c}\n")).collect(),
+        "notcommands" | "listplaylists" | "lsinfo" | "decoders" | "channels" | "readmessages" => {
+            String::new()
+        }
+        "tagtypes" => "tagtype: Artist\ntagtype: Album\ntagtype: Title\n".to_owned(),
+        "urlhandlers" => "handler: https://\n".to_owned(),
+        "outputs" => format!(
+            "outputid: 0\noutputname: {}\nplugin: gstreamer\noutputenabled: 1\n",
+            crate::APP_NAME
+        ),
+        "replay_gain_status" => "replay_gain_mode: off\n".to_owned(),
+        "stats" => {
+            let (songs, _) = playlist(ctx)?;
+            format!("songs: {}\nuptime: 0\nplaytime: 0\n", songs.len())
+        }
+        "status" => status(ctx)?,
+        "currentsong" => {
+            let (songs, current) = playlist(ctx)?;
+            match (now_playing(ctx), songs.get(current)) {
+                (Some(_), Some(si)) => song_lines(ctx, si, current),
+                _ => String::new(),
+            }
+        }
+        "playlistinfo" | "playlistid" | "plchanges" => {
+            let (songs, _) = playlist(ctx)?;
+            songs
+                .iter()
+                .enumerate()
+                .map(|(pos, si)| song_lines(ctx, si, pos))
+                .collect()
+        }
+        "play" | "playid" => {
+            match args.first() {
+                Some(arg) => {
+                    let (songs, _) = playlist(ctx)?;
+                    let pos = if command == "playid" {
+                        ctx.song_pos(&songs, parse_arg(arg)?)
+                    } else {
+                        Some(parse_arg(arg)?)
+                    };
+                    let si = pos.and_then(|p| songs.get(p)).ok_or_else(no_such_song)?;
+                    send(ctx, Action::AddPlay(si.to_owned()))?;
+                }
+                None => send(ctx, Action::SetPlaying(true))?,
+            }
+            String::new()
+        }
+        "pause" => {
+            match args.first().map(String::as_str) {
+                Some("1") => send(ctx, Action::SetPlaying(false))?,
+                Some("0") => send(ctx, Action::SetPlaying(true))?,
+                _ => send(ctx, Action::TogglePlayPause)?,
+            }
+            String::new()
+        }
+        "stop" => {
+            send(ctx, Action::SetPlaying(false))?;
+            String::new()
+        }
+        "next" => {
+            send(ctx, Action::PlayNextSong)?;
+            String::new()
+        }
+        "previous" => {
+            send(ctx, Action::PlayPreviousSong)?;
+            String::new()
+        }
+        "setvol" => {
+            let volume = parse_arg::<u32>(arg(args, 0)?)?.min(100);
+            send(ctx, Action::SetVolume(volume as f64 / 100.0))?;
+            String::new()
+        }
+        "seek" | "seekid" | "seekcur" => {
+            let time = if command == "seekcur" {
+                arg(args, 0)?
+            } else {
+                // 只能在当前歌曲内跳转
+                let (songs, current) = playlist(ctx)?;
+                let target = arg(args, 0)?;
+                let pos = if command == "seekid" {
+                    ctx.song_pos(&songs, parse_arg(target)?)
+                } else {
+                    Some(parse_arg(target)?)
+                };
+                if pos.is_none_or(|pos| pos != current || pos >= songs.len()) {
+                    return Err((
+                        ACK_ERROR_ARG,
+                        "Can only seek in the current song".to_owned(),
+                    ));
+                }
+                arg(args, 1)?
+            };
+            let position = if time.starts_with(['+', '-']) {
+                let current = now_playing(ctx).map(|np| np.position).unwrap_or(0) as f64;
+                current + parse_arg::<f64>(time)? * 1000.0
+            } else {
+                parse_arg::<f64>(time)? * 1000.0
+            };
+            send(ctx, Action::SeekTo(position.max(0.0) as u64))?;
+            String::new()
+        }
+        "add" | "addid" => {
+            let uri = arg(args, 0)?;
+            let Some(NeteaseLink::Song(id)) = parse_link(uri) else {
+                return Err((ACK_ERROR_NO_EXIST, "Unsupported URI".to_owned()));
+            };
+            send(ctx, Action::AddTrackById(id, false))?;
+            if command == "addid" {
+                format!("Id: {}\n", ctx.song_id(id))
+            } else {
+                String::new()
+            }
+        }
+        "clear" => {
+            send(ctx, Action::ClearPlayList)?;
+            String::new()
+        }
+        "search" | "find" => {
+            let text = search_text(args);
+            if text.is_empty() {
+                return Err((ACK_ERROR_ARG, "Missing search text".to_owned()));
+            }
+            let res = request_action(&ctx.sender, |callback| {
+                Action::Search(text, SearchType::Song, 0, SEARCH_LIMIT, callback)
+            });
+            let Some(SearchResult::Songs(sis, _)) = res else {
+                return Err((ACK_ERROR_SYSTEM, "Search failed".to_owned()));
+            };
+            sis.iter()
+                .map(|si| format!("file: {}\n{}", song_uri(si), song_tags(si)))
+                .collect()
+        }
+        _ => {
+            return Err((ACK_ERROR_UNKNOWN, format!("unknown command \"{command}\"")));
+        }
+    };
+    Ok(Reply::Output(output))
+}
+
+fn send(ctx: &Context, action: Action) -> Result<(), Ack> {
+    ctx.sender
+        .send_blocking(action)
+        .map_err(|_| (ACK_ERROR_SYSTEM, "Player unavailable".to_owned()))
+}
+
+fn playlist(ctx: &Context) -> Result<(Vec<SongInfo>, usize), Ack> {
+    request_action(&ctx.sender, Action::GetPlayList)
+        .ok_or((ACK_ERROR_SYSTEM, "Player unavailable".to_owned()))
+}
+
+fn now_playing(ctx: &Context) -> Option<NowPlaying> {
+    request_action(&ctx.sender, Action::GetNowPlaying).flatten()
+}
+
+fn status(ctx: &Context) -> Result<String, Ack> {
+    let (songs, current) = playlist(ctx)?;
+    let now_playing = now_playing(ctx);
+
+    let mut hasher = DefaultHasher::new();
+    songs.iter().for_each(|si| si.id.hash(&mut hasher));
+    // playlist 为播放列表版本号，客户端据此判断列表是否变化
+    let volume = now_playing.as_ref().map(|np| np.volume).unwrap_or(0);
+    let mut output = format!("volume: {volume}\nrepeat: 0\nrandom: 0\nsingle: 0\nconsume: 0\n");
+    output.push_str(&format!(
+        "playlist: {}\nplaylistlength: {}\n",
+        hasher.finish() as u32,
+        songs.len()
+    ));
+    match now_playing {
+        Some(np) if current < songs.len() => {
+            let state = if np.playing { "play" } else { "pause" };
+            let elapsed = np.position as f64 / 1000.0;
+            let duration = np.duration as f64 / 1000.0;
+            output.push_str(&format!(
+                "state: {state}\nsong: {current}\nsongid: {}\ntime: {}:{}\n",
+                ctx.song_id(songs[current].id),
+                elapsed as u64,
+                duration as u64,
+            ));
+            output.push_str(&format!("elapsed: {elapsed:.3}\nduration: {duration:.3}\n"));
+        }
+        _ => output.push_str("state: stop\n"),
+    }
+    Ok(output)
+}
+
+fn song_uri(si: &SongInfo) -> String {
+    if is_local_song(si) {
+        si.song_url.to_owned()
+    } else {
+        NeteaseLink::Song(si.id).url(None)
+    }
+}
+
+fn song_tags(si: &SongInfo) -> String {
+    let duration = si.duration as f64 / 1000.0;
+    format!(
+        "Title: {}\nArtist: {}\nAlbum: {}\nTime: {}\nduration: {duration:.3}\n",
+        si.name, si.singer, si.album, duration as u64
+    )
+}
+
+fn song_lines(ctx: &Context, si: &SongInfo, pos: usize) -> String {
+    format!(
+        "file: {}\n{}Pos: {pos}\nId: {}\n",
+        song_uri(si),
+        song_tags(si),
+        ctx.song_id(si.id)
+    )
+}
+
+fn no_such_song() -> Ack {
+    (ACK_ERROR_NO_EXIST, "No such song".to_owned())
+}
+
+fn arg(args: &[String], index: usize) -> Result<&str, Ack> {
+    args.get(index)
+        .map(String::as_str)
+        .ok_or((ACK_ERROR_ARG, "Missing argument".to_owned()))
+}
+
+fn parse_arg<T: std::str::FromStr>(arg: &str) -> Result<T, Ack> {
+    arg.parse()
+        .map_err(|_| (ACK_ERROR_ARG, format!("Invalid argument: {arg}")))
+}
+
+// 支持 “search any 关键字” 与过滤表达式 “search "(any contains '关键字')"”
+fn search_text(args: &[String]) -> String {
+    if let Some(expr) = args.first().filter(|a| a.starts_with('(')) {
+        let value = expr
+            .find(['\'', '"'])
+            .zip(expr.rfind(['\'', '"']))
+            .filter(|(start, end)| start < end)
+            .map(|(start, end)| &expr[start + 1..end]);
+        return value.unwrap_or_default().to_owned();
+    }
+    args.iter()
+        .skip(1)
+        .step_by(2)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// 按 MPD 规则拆分参数，支持双引号与反斜杠转义
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut arg = String::new();
+        if c == '"' {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => arg.extend(chars.next()),
+                    '"' => break,
+                    c => arg.push(c),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                arg.push(c);
+                chars.next();
+            }
+        }
+        args.push(arg);
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn split() {
+        let cases: [(&str, &[&str]); 10] = [
+            ("play", &["play"]),
+            ("seek 1 30.5", &["seek", "1", "30.5"]),
+            ("  add   \"https://a b\"  ", &["add", "https://a b"]),
+            ("\"\" x", &["", "x"]),
+            // 双引号内的转义
+            (r#"find title "a\"b\\c""#, &["find", "title", r#"a"b\c"#]),
+            (
+                r#"search "(any contains 'x \"y\"')""#,
+                &["search", r#"(any contains 'x "y"')"#],
+            ),
+            // 未闭合的引号取到行尾
+            (r#"add "abc"#, &["add", "abc"]),
+            (r#"add "abc\"#, &["add", "abc"]),
+            ("search any 晴天", &["search", "any", "晴天"]),
+            ("", &[]),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(split_args(line), strings(expected), "{line:?}");
+        }
+    }
+
+    #[test]
+    fn search() {
+        let cases: [(&[&str], &str); 9] = [
+            (&["any", "晴天"], "晴天"),
+            (&["artist", "周杰伦", "title", "晴天"], "周杰伦 晴天"),
+            (&["(any contains '晴天')"], "晴天"),
+            (&[r#"(Artist == "周杰伦")"#], "周杰伦"),
+            (&["(any contains 'it s')", "window", "0:1"], "it s"),
+            (&["(any contains x)"], ""),
+            (&["(any contains ')"], ""),
+            (&["any"], ""),
+            (&[], ""),
+        ];
+        for (args, expected) in cases {
+            assert_eq!(search_text(&strings(args)), expected, "{args:?}");
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use gettextrs::gettext;
 use glib::{timeout_add_seconds, SourceId};
-use gtk::glib;
+use gtk::{gio, glib};
 use ncm_api::{SongCopyright, SongInfo, SongQualityState};
 use std::sync::{Arc, Mutex};
 
@@ -67,6 +67,15 @@ impl Default for Debounce {
     }
 }
 
+// 在后台线程执行文件读写，避免阻塞主循环
+pub async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> anyhow::Result<T> {
+    gio::spawn_blocking(f)
+        .await
+        .map_err(|_| anyhow::anyhow!("blocking task panicked"))?
+}
+
 pub fn empty_song_info() -> SongInfo {
     SongInfo {
         id: 0,
@@ -0,0 +1,281 @@
+//
+// webapi.rs
+// Copyright (C) 2022 gmg137 <gmg137 AT live.com>
+// Distributed under terms of the GPL-3.0-or-later license.
+//
+
+use anyhow::Result;
+use gtk::glib;
+use isahc::{
+    AsyncReadResponseExt, HttpClient, ReadResponseExt, Request, config::Configurable,
+    cookies::CookieJar,
+};
+use openssl::{
+    bn::BigNum,
+    rsa::{Padding, Rsa},
+    symm::{Cipher, encrypt},
+};
+use serde_json::{Map, Value};
+use std::{fs, path::Path};
+
+use crate::cloud_upload::{BUCKET, UploadHttp};
+
+const API_BASE_URL: &str = "https://music.163.com/weapi/";
+const LBS_URL: &str = "https://wanproxy.127.net/lbs";
+const USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+// 网页端 weapi 加密参数
+const PRESET_KEY: &[u8] = b"0CoJUm6Qyw8W8jud";
+const IV: &[u8] = b"0102030405060708";
+const RSA_MODULUS: &str = "00e0b509f6259df8642dbc35662901477df22677ec152b5ff68ace615bb7b725152b3ab17a876aea8a5aa76d2e417629ec4ee341f56135fccf695280104e0312ecbda92557c93870114af6c9d05c4f7f0c3685b7a46bee255932575cce10b424d813cfe4875d3e82047b97ddef52741d546b8e289dc6935b3ece0462db0a22b8e7";
+const RSA_EXPONENT: &str = "010001";
+
+// 歌词原文、翻译、罗马音及逐字歌词，不存在时为空
+#[derive(Debug, Clone, Default)]
+pub struct SongLyric {
+    pub lyric: String,
+    pub tlyric: String,
+    pub rlyric: String,
+    pub yrc: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SongDetail {
+    pub track: Option<u32>,
+    pub pic_url: String,
+}
+
+// 网页接口客户端，与 ncm-api 共用登录信息及代理设置，请求以 weapi 方式加密
+#[derive(Clone)]
+pub struct WebApi {
+    client: HttpClient,
+    cookie_jar: Option<CookieJar>,
+    api_base: String,
+    lbs_url: String,
+}
+
+impl WebApi {
+    pub fn new(cookie_jar: Option<CookieJar>, proxy: Option<&str>) -> Result<Self> {
+        let mut builder = HttpClient::builder();
+        if let Some(cookie_jar) = cookie_jar.clone() {
+            builder = builder.cookie_jar(cookie_jar);
+        }
+        if let Some(proxy) = proxy.filter(|p| !p.is_empty()) {
+            builder = builder.proxy(Some(proxy.parse()?));
+        }
+        Ok(Self {
+            client: builder.build()?,
+            cookie_jar,
+            api_base: API_BASE_URL.to_owned(),
+            lbs_url: LBS_URL.to_owned(),
+        })
+    }
+
+    fn csrf_token(&self) -> String {
+        let Ok(uri) = "https://music.163.com/".parse() else {
+            return String::new();
+        };
+        self.cookie_jar
+            .as_ref()
+            .and_then(|jar| jar.get_by_name(&uri, "__csrf"))
+            .map(|cookie| cookie.value().to_owned())
+            .unwrap_or_default()
+    }
+
+    // 替换接口地址，用于连接本地测试服务
+    #[cfg(test)]
+    pub fn with_base_urls(mut self, api_base: &str, lbs_url: &str) -> Self {
+        self.api_base = api_base.to_owned();
+        self.lbs_url = lbs_url.to_owned();
+        self
+    }
+
+    // 下载文件数据，如歌曲及封面
+    pub async fn get_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        let mut response = self.client.get_async(url).await?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("HTTP {}: {}", status, url);
+        }
+        Ok(response.bytes().await?)
+    }
+
+    // 将文件数据边下载边写入指定路径，会阻塞当前线程，需在后台线程调用
+    pub fn download_to(&self, url: &str, path: &Path) -> Result<()> {
+        let mut response = self.client.get(url)?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("HTTP {}: {}", status, url);
+        }
+        let mut file = fs::File::create(path)?;
+        response.copy_to(&mut file)?;
+        Ok(())
+    }
+
+    // 获取歌词原文、翻译、罗马音及逐字歌词
+    pub async fn song_lyric(&self, id: u64) -> Result<SongLyric> {
+        let mut params = vec![("id", id.to_string()), ("cp", "false".to_owned())];
+        params.extend(["lv", "tv", "rv", "kv", "yv", "ytv", "yrv"].map(|k| (k, "0".to_owned())));
+        let res = self.post_api("song/lyric/v1", &params).await?;
+        let text = |key: &str| res[key]["lyric"].as_str().unwrap_or_default().to_owned();
+        Ok(SongLyric {
+            lyric: text("lrc"),
+            tlyric: text("tlyric"),
+            rlyric: text("romalrc"),
+            yrc: text("yrc"),
+        })
+    }
+
+    // 获取歌曲详情中 SongInfo 未包含的曲目序号及原尺寸封面地址
+    pub async fn song_detail(&self, id: u64) -> Result<SongDetail> {
+        let res = self
+            .post_api("v3/song/detail", &[("c", format!("[{{\"id\":{}}}]", id))])
+            .await?;
+        let song = &res["songs"][0];
+        if song.is_null() {
+            anyhow::bail!("No song detail found!");
+        }
+        Ok(SongDetail {
+            track: song["no"].as_u64().filter(|no| *no > 0).map(|no| no as u32),
+            pic_url: song["al"]["picUrl"].as_str().unwrap_or_default().to_owned(),
+        })
+    }
+
+    async fn send(&self, request: Request<Vec<u8>>) -> Result<Value> {
+        let mut response = self.client.send_async(request).await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            anyhow::bail!("HTTP {}: {}", status, text);
+        }
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+impl UploadHttp for WebApi {
+    async fn post_api(&self, path: &str, params: &[(&str, String)]) -> Result<Value> {
+        let csrf_token = self.csrf_token();
+        let mut data: Map<String, Value> = params
+            .iter()
+            .map(|(k, v)| (k.to_string(), Value::from(v.as_str())))
+            .collect();
+        data.insert("csrf_token".to_owned(), Value::from(csrf_token.as_str()));
+        let secret_key: String = (0..16).map(|_| fastrand::alphanumeric()).collect();
+        let body = weapi_form(&Value::Object(data).to_string(), secret_key.as_bytes())?;
+        let url = format!("{}{}?csrf_token={}", self.api_base, path, csrf_token);
+        let request = Request::post(url)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Referer", "https://music.163.com")
+            .header("User-Agent", USER_AGENT)
+            .body(body.into_bytes())?;
+        let value = self.send(request).await?;
+        match value["code"].as_i64() {
+            Some(200) => Ok(value),
+            code => anyhow::bail!("{} failed with code {:?}: {}", path, code, value),
+        }
+    }
+
+    async fn get_lbs(&self) -> Result<Value> {
+        let url = format!("{}?version=1.0&bucketname={}", self.lbs_url, BUCKET);
+        let request = Request::get(url).body(Vec::new())?;
+        self.send(request).await
+    }
+
+    async fn post_object(
+        &self,
+        url: &str,
+        headers: &[(&str, String)],
+        data: Vec<u8>,
+    ) -> Result<Value> {
+        let mut request = Request::post(url);
+        for (k, v) in headers {
+            request = request.header(*k, v);
+        }
+        self.send(request.body(data)?).await
+    }
+}
+
+// 按网页端 weapi 方式加密请求参数，返回表单内容
+fn weapi_form(text: &str, secret_key: &[u8]) -> Result<String> {
+    let params = aes_base64(
+        aes_base64(text.as_bytes(), PRESET_KEY)?.as_bytes(),
+        secret_key,
+    )?;
+    // 密钥倒序后以无填充 RSA 加密
+    let mut key = vec![0; 128 - secret_key.len()];
+    key.extend(secret_key.iter().rev());
+    let rsa = Rsa::from_public_components(
+        BigNum::from_hex_str(RSA_MODULUS)?,
+        BigNum::from_hex_str(RSA_EXPONENT)?,
+    )?;
+    let mut enc_sec_key = vec![0; rsa.size() as usize];
+    rsa.public_encrypt(&key, &mut enc_sec_key, Padding::NONE)?;
+    let enc_sec_key: String = enc_sec_key.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!(
+        "params={}&encSecKey={}",
+        glib::Uri::escape_string(&params, None, false),
+        enc_sec_key
+    ))
+}
+
+fn aes_base64(data: &[u8], key: &[u8]) -> Result<String> {
+    let encrypted = encrypt(Cipher::aes_128_cbc(), key, Some(IV), data)?;
+    Ok(glib::base64_encode(&encrypted).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::thread;
+
+    #[test]
+    fn weapi_params() {
+        let key = b"abcdefghijklmnop";
+        let form = weapi_form(r#"{"id":"1"}"#, key).unwrap();
+        let (params, enc_sec_key) = form.split_once("&encSecKey=").unwrap();
+        let params =
+            glib::Uri::unescape_string(params.trim_start_matches("params="), None).unwrap();
+        // 用明文密钥逐层解密还原参数
+        let decrypt = |data: &[u8], key: &[u8]| {
+            let data = glib::base64_decode(std::str::from_utf8(data).unwrap());
+            openssl::symm::decrypt(Cipher::aes_128_cbc(), key, Some(IV), &data).unwrap()
+        };
+        let inner = decrypt(params.as_bytes(), key);
+        assert_eq!(decrypt(&inner, PRESET_KEY), br#"{"id":"1"}"#);
+        assert_eq!(enc_sec_key.len(), 256);
+        assert!(enc_sec_key.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn song_lyric() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let handle = thread::spawn(move || {
+            let request = server.recv().unwrap();
+            let url = request.url().to_owned();
+            let json = json!({
+                "code": 200,
+                "lrc": {"lyric": "[00:01.00]a"},
+                "romalrc": {"lyric": "[00:01.00]r"},
+                "yrc": {"lyric": "[1000,500](1000,500,0)a"},
+            });
+            request
+                .respond(tiny_http::Response::from_string(json.to_string()))
+                .unwrap();
+            url
+        });
+        let api = WebApi::new(None, None)
+            .unwrap()
+            .with_base_urls(&format!("{}/weapi/", base), &format!("{}/lbs", base));
+        let lyric = glib::MainContext::new()
+            .block_on(api.song_lyric(1))
+            .unwrap();
+
+        assert!(handle.join().unwrap().starts_with("/weapi/song/lyric/v1?"));
+        assert_eq!(lyric.lyric, "[00:01.00]a");
+        assert_eq!(lyric.tlyric, "");
+        assert_eq!(lyric.rlyric, "[00:01.00]r");
+        assert_eq!(lyric.yrc, "[1000,500](1000,500,0)a");
+    }
+}
@@ -1,23 +1,21 @@
 use crate::{
     application::{Action, NeteaseCloudMusicGtk4Application},
-    audio::MprisController,
+    audio::Playback,
     gui::*,
+    links::{link_position, parse_link},
+    lyrics::Lyrics,
     model::*,
-    ncmapi::NcmClient,
 };
 use adw::{ColorScheme, StyleManager, Toast};
 use async_channel::Sender;
 use gettextrs::gettext;
 use gio::{Settings, SimpleAction};
-use glib::{
-    ParamSpec, ParamSpecEnum, ParamSpecObject, ParamSpecUInt64, Value, clone, source::Priority,
-};
+use glib::{ParamSpec, ParamSpecEnum, ParamSpecObject, Value, clone, source::Priority};
 use gtk::{
     CompositeTemplate,
     gio::{self, SettingsBindFlags},
     glib,
 };
-use log::*;
 use ncm_api::{BannersInfo, LoginInfo, SongInfo, SongList, TopList};
 use once_cell::sync::{Lazy, OnceCell};
 use std::{
@@ -81,29 +79,13 @@ mod imp {
         pub popover_menu: OnceCell<PopoverMenu>,
         pub settings: OnceCell<Settings>,
         pub sender: OnceCell<Sender<Action>>,
+        // 播放核心由应用持有，窗口关闭后继续播放
+        pub playback: OnceCell<Playback>,
         pub stack_child: Arc<Mutex<LinkedList<(String, String)>>>,
         pub page_stack: OnceCell<PageStack>,
-        pub tray_handle: RefCell<crate::gui::TrayHandle>,
-        pub graceful_quitting: Cell<bool>,
 
         search_type: Cell<SearchType>,
         toast: RefCell<Option<Toast>>,
-        user_info: RefCell<UserInfo>,
-    }
-
-    impl NeteaseCloudMusicGtk4Window {
-        pub fn user_like_song_contains(&self, id: &u64) -> bool {
-            self.user_info.borrow().like_songs.contains(id)
-        }
-        pub fn user_like_song_add(&self, id: u64) {
-            self.user_info.borrow_mut().like_songs.insert(id);
-        }
-        pub fn user_like_song_remove(&self, id: &u64) {
-            self.user_info.borrow_mut().like_songs.remove(id);
-        }
-        pub fn clear_user_info(&self) {
-            self.user_info.take();
-        }
     }
 
     #[glib::object_subclass]
@@ -143,17 +125,6 @@ mod imp {
 
             obj.setup_settings();
             obj.bind_settings();
-
-            // 窗口关闭时保存播放列表并清理系统托盘
-            obj.connect_close_request(|window| {
-                if !window.imp().graceful_quitting.get() {
-                    window.imp().player_controls.save_current_state();
-                    if !window.hides_on_close() {
-                        window.imp().tray_handle.borrow_mut().stop();
-                    }
-                }
-                glib::Propagation::Proceed
-            });
         }
 
         fn properties() -> &'static [ParamSpec] {
@@ -163,7 +134,6 @@ mod imp {
                         .explicit_notify()
                         .build(),
                     ParamSpecObject::builder::<Toast>("toast").build(),
-                    ParamSpecUInt64::builder("uid").build(),
                 ]
             });
             PROPERTIES.as_ref()
@@ -181,10 +151,6 @@ mod imp {
                         .expect("The value needs to be of type `SearchType`.");
                     self.search_type.replace(input_type);
                 }
-                "uid" => {
-                    let uid = value.get().unwrap();
-                    self.user_info.borrow_mut().uid = uid;
-                }
                 _ => unimplemented!(),
             }
         }
@@ -193,7 +159,6 @@ mod imp {
             match pspec.name() {
                 "toast" => self.toast.borrow().to_value(),
                 "search-type" => self.search_type.get().to_value(),
-                "uid" => self.user_info.borrow().uid.to_value(),
                 _ => unimplemented!(),
             }
         }
@@ -210,15 +175,17 @@ glib::wrapper! {
 }
 
 impl NeteaseCloudMusicGtk4Window {
-    pub fn new<P: glib::object::IsA<gtk::Application>>(
-        application: &P,
-        sender: Sender<Action>,
-    ) -> Self {
+    pub fn new(application: &NeteaseCloudMusicGtk4Application, sender: Sender<Action>) -> Self {
         let window: NeteaseCloudMusicGtk4Window = glib::Object::builder()
             .property("application", application)
             .build();
 
         window.imp().sender.set(sender).unwrap();
+        window
+            .imp()
+            .playback
+            .set(application.playback().clone())
+            .unwrap();
         window.setup_widget();
         window.setup_action();
         window.init_page_data();
@@ -341,49 +308,41 @@ impl NeteaseCloudMusicGtk4Window {
 
         imp.user_menus.set(user_menus).unwrap();
         imp.popover_menu.set(popover).unwrap();
-    }
-
-    pub fn get_uid(&self) -> u64 {
-        self.property::<u64>("uid")
-    }
 
-    pub fn set_uid(&self, val: u64) {
-        self.set_property("uid", val);
+        // 接受拖放到窗口的网易云音乐分享链接
+        let drop_target = gtk::DropTarget::new(glib::Type::INVALID, gtk::gdk::DragAction::COPY);
+        drop_target.set_types(&[String::static_type(), gtk::gdk::FileList::static_type()]);
+        let sender = sender.clone();
+        drop_target.connect_drop(move |_, value, _, _| {
+            let text = if let Ok(files) = value.get::<gtk::gdk::FileList>() {
+                files.files().first().map(|file| file.uri().to_string())
+            } else {
+                value.get::<String>().ok()
+            };
+            match text.as_deref().and_then(parse_link) {
+                Some(link) => {
+                    let position = text.as_deref().and_then(link_position);
+                    sender
+                        .send_blocking(Action::OpenLink(link, position))
+                        .unwrap();
+                    true
+                }
+                None => false,
+            }
+        });
+        self.add_controller(drop_target);
     }
 
     pub fn is_logined(&self) -> bool {
-        self.get_uid() != 0u64
-    }
-
-    pub fn logout(&self) {
-        self.imp().clear_user_info();
+        NeteaseCloudMusicGtk4Application::default().is_logined()
     }
 
     pub fn get_song_likes(&self, sis: &[SongInfo]) -> Vec<bool> {
-        sis.iter()
-            .map(|si| self.imp().user_like_song_contains(&si.id))
-            .collect()
+        NeteaseCloudMusicGtk4Application::default().get_song_likes(sis)
     }
 
-    pub fn set_like_song(&self, id: u64, val: bool) {
-        let imp = self.imp();
-        if let Some(song) = imp.player_controls.get().get_current_song() {
-            if song.id == id {
-                imp.player_controls.get().set_property("like", val);
-            }
-        }
-
-        if val {
-            imp.user_like_song_add(id);
-        } else {
-            imp.user_like_song_remove(&id);
-        }
-    }
-
-    pub fn set_user_like_songs(&self, song_ids: &[u64]) {
-        song_ids
-            .iter()
-            .for_each(|id| self.imp().user_like_song_add(id.to_owned()));
+    fn playback(&self) -> &Playback {
+        self.imp().playback.get().expect("Could not get playback.")
     }
 
     pub fn set_user_qrimage(&self, path: PathBuf) {
@@ -466,89 +425,54 @@ impl NeteaseCloudMusicGtk4Window {
         discover.setup_new_albums(song_list);
     }
 
-    pub fn add_play(&self, song_info: SongInfo) {
-        let player_controls = self.imp().player_controls.get();
-        player_controls.add_song(song_info);
+    // 刷新当前显示的播放列表页
+    pub fn update_playlist_page(&self) {
+        if self.page_cur_playlist_lyrics_page() {
+            let playback = self.playback();
+            let sis = playback.get_list();
+            let si = playback
+                .get_current_song()
+                .unwrap_or(crate::utils::empty_song_info());
+            let page = self.imp().playlist_lyrics_page.get().unwrap();
+            page.init_page(&sis, si, &self.get_song_likes(&sis));
+        }
     }
 
-    pub fn remove_from_playlist(&self, song_info: SongInfo) {
-        let player_controls = self.imp().player_controls.get();
-        player_controls.remove_song(song_info);
+    // 播放列表已清空
+    pub fn clear_playlist(&self) {
+        self.hide_player_bar();
+        if self.page_cur_playlist_lyrics_page() {
+            let sender = self.imp().sender.get().unwrap();
+            sender.send_blocking(Action::PageBack).unwrap();
+        }
+    }
 
-        let sis = player_controls.get_list();
-        let si = player_controls.get_current_song().unwrap_or(crate::utils::empty_song_info());
+    // 从播放列表移除歌曲后刷新页面
+    pub fn remove_from_playlist(&self) {
+        let playback = self.playback();
+        let sis = playback.get_list();
+        let si = playback
+            .get_current_song()
+            .unwrap_or(crate::utils::empty_song_info());
 
         self.init_playlist_lyrics_page(sis, si.to_owned());
 
         if si.id == 0 {
-            let player_revealer = self.imp().player_revealer.get();
-            player_revealer.set_reveal_child(false);
-            player_revealer.set_visible(false);
-            player_revealer.set_reveal_child(false);
+            self.hide_player_bar();
             let sender = self.imp().sender.get().unwrap();
             sender.send_blocking(Action::PageBack).unwrap();
         }
     }
 
-    pub fn add_playlist(&self, sis: Vec<SongInfo>, is_play: bool) {
-        let player_controls = self.imp().player_controls.get();
-        player_controls.add_list(sis);
-        let sender = self.imp().sender.get().unwrap();
-        if is_play {
-            sender.send_blocking(Action::PlayListStart).unwrap();
-        }
-    }
-
-    // 追加歌曲到播放列表末尾，返回实际追加的歌曲（已过滤灰歌/去重）
-    pub fn append_playlist(&self, list: &mut Vec<SongInfo>) -> Vec<SongInfo> {
-        let player_controls = self.imp().player_controls.get();
-        player_controls.append_list(list)
-    }
-
     // 心动模式开启/关闭时同步按钮状态
     pub fn set_heartbeat_active(&self, active: bool) {
         let player_controls = self.imp().player_controls.get();
         player_controls.set_heartbeat_active(active);
     }
 
-    // 根据 song_id 定位播放位置（心动模式追加续播前使用）
-    pub fn sync_playlist_position(&self, song_id: u64) {
-        let player_controls = self.imp().player_controls.get();
-        player_controls.sync_playlist_position(song_id);
-    }
-
-    pub fn playlist_position(&self) -> usize {
-        let player_controls = self.imp().player_controls.get();
-        player_controls.playlist_position()
-    }
-
-    // 从头播放列表第一首（心动模式列表循环追加失败时的回退）
-    pub fn playlist_first_restart(&self) -> Option<SongInfo> {
-        let player_controls = self.imp().player_controls.get();
-        player_controls.playlist_first_restart()
-    }
-
-    pub fn playlist_start(&self) {
-        let sender = self.imp().sender.get().unwrap();
-        let player_controls = self.imp().player_controls.get();
-        if let Some(song_info) = player_controls.get_current_song() {
-            sender.send_blocking(Action::Play(song_info)).unwrap();
-            return;
-        }
-        sender
-            .send_blocking(Action::AddToast(gettext("No playable songs found！")))
-            .unwrap();
-    }
-
-    pub fn play_next(&self) {
-        let player_controls = self.imp().player_controls.get();
-        player_controls.next_song();
-    }
-
-    pub fn play(&self, song_info: SongInfo) {
+    pub fn play(&self, song_info: &SongInfo) {
         let player_controls = self.imp().player_controls.get();
-        player_controls.set_property("like", self.imp().user_like_song_contains(&song_info.id));
-        player_controls.play(song_info);
+        player_controls.init_play_info(song_info);
         self.show_player_bar();
     }
 
@@ -561,14 +485,13 @@ impl NeteaseCloudMusicGtk4Window {
         my_page.set_sender(sender.clone());
 
         // 初始化播放栏
+        let playback = self.playback();
         let player_controls = imp.player_controls.get();
-        player_controls.set_sender(sender.clone());
+        player_controls.set_playback(sender.clone(), playback.clone());
 
         // 恢复播放列表UI（如果有保存的播放列表）
-        if player_controls.restore_playlist_ui() {
-            let player_revealer = imp.player_revealer.get();
-            player_revealer.set_visible(true);
-            player_revealer.set_reveal_child(true);
+        if playback.get_current_song().is_some() {
+            self.show_player_bar();
         }
 
         // 初始化发现页
@@ -588,9 +511,6 @@ impl NeteaseCloudMusicGtk4Window {
         let page_stack = imp.page_stack.get().unwrap();
         page_stack.set_transition_type(StackTransitionType::Crossfade);
         page_stack.set_transition_duration(100); // default 200
-
-        // 启动系统托盘
-        imp.tray_handle.borrow_mut().start(sender.clone());
     }
 
     pub fn init_toplist(&self, list: Vec<TopList>) {
@@ -600,13 +520,7 @@ impl NeteaseCloudMusicGtk4Window {
 
     pub fn update_toplist(&self, list: Vec<SongInfo>) {
         let toplist = self.imp().toplist.get();
-        toplist.update_songs_list(
-            &list,
-            &list
-                .iter()
-                .map(|si| self.imp().user_like_song_contains(&si.id))
-                .collect::<Vec<bool>>(),
-        );
+        toplist.update_songs_list(&list, &self.get_song_likes(&list));
     }
 
     // page routing
@@ -680,10 +594,6 @@ impl NeteaseCloudMusicGtk4Window {
         }
         None
     }
-    pub fn persist_volume(&self, value: f64) {
-        let imp = self.imp();
-        imp.player_controls.persist_volume(value);
-    }
     pub fn page_cur_playlist_lyrics_page(&self) -> bool {
         let imp = self.imp();
         let page = imp.playlist_lyrics_page.get().unwrap();
@@ -778,7 +688,7 @@ impl NeteaseCloudMusicGtk4Window {
     }
 
     // 更新歌词内容，不调整位置
-    pub fn update_lyrics(&self, lrc: Vec<(u64, String)>) {
+    pub fn update_lyrics(&self, lrc: Lyrics) {
         let imp = self.imp();
         let page = imp.playlist_lyrics_page.get().unwrap();
         page.update_lyrics(lrc);
@@ -800,6 +710,11 @@ impl NeteaseCloudMusicGtk4Window {
         }
     }
 
+    pub fn update_playing(&self, playing: bool) {
+        let page = self.imp().playlist_lyrics_page.get().unwrap();
+        page.set_playing(playing);
+    }
+
     pub fn update_playlist_status(&self, index: usize) {
         let imp = self.imp();
         let page = imp.playlist_lyrics_page.get().unwrap();
@@ -808,46 +723,74 @@ impl NeteaseCloudMusicGtk4Window {
         }
     }
 
-    pub fn set_song_url(&self, si: SongInfo) {
-        self.imp().player_controls.get().set_song_url(si);
+    // usec -> microseconds
+    pub fn update_duration(&self, usec: u64) {
+        self.imp().player_controls.get().update_duration(usec);
     }
-    pub fn gst_duration_changed(&self, sec: u64) {
-        self.imp().player_controls.get().gst_duration_changed(sec);
-    }
-    pub fn gst_state_changed(&self, state: gstreamer_play::PlayState) {
-        self.imp().player_controls.get().gst_state_changed(state);
-        use gstreamer_play::PlayState;
-        let is_playing = matches!(state, PlayState::Playing);
-        self.update_tray_playing(is_playing);
-    }
-    pub fn gst_volume_changed(&self, volume: f64) {
-        self.imp().player_controls.get().gst_volume_changed(volume);
-    }
-    pub fn gst_cache_download_complete(&self, loc: String) {
-        self.imp()
-            .player_controls
-            .get()
-            .gst_cache_download_complete(loc);
-    }
-    pub fn scale_seek_update(&self, sec: u64) {
-        self.imp().player_controls.get().scale_seek_update(sec);
+    // usec -> microseconds
+    pub fn update_position(&self, usec: u64) {
+        self.imp().player_controls.get().update_position(usec);
     }
     pub fn scale_value_update(&self) {
         self.imp().player_controls.get().scale_value_update();
     }
-    pub fn init_mpris(&self, mpris: MprisController) {
-        self.imp().player_controls.get().init_mpris(mpris);
-    }
 
-    pub fn update_tray_playing(&self, playing: bool) {
-        self.imp().tray_handle.borrow().update_playing(playing);
-    }
+    pub fn search(&self, text: String, search_type: SearchType) {
+        let imp = self.imp();
+        let sender = imp.sender.get().unwrap();
+        imp.label_title.set_label(&text);
+        imp.switcher_title.set_visible(false);
+        imp.label_title.set_visible(true);
+        imp.back_button.set_visible(true);
 
-    pub fn update_tray_song_title(&self, title: String, artist: String, album_id: u64) {
-        self.imp()
-            .tray_handle
-            .borrow()
-            .update_song_title(title, artist, album_id);
+        let page = match search_type {
+            SearchType::Lyrics | SearchType::Song | SearchType::LocalLyrics => {
+                let page = self.init_search_song_page(&text, search_type);
+                Some(page.upcast::<Widget>())
+            }
+            SearchType::Singer => {
+                let page = self.init_search_singer_page(&text);
+                Some(page.upcast::<Widget>())
+            }
+            SearchType::Album | SearchType::SongList => {
+                let page = self.init_search_songlist_page(&text, search_type);
+                Some(page.upcast::<Widget>())
+            }
+            _ => None,
+        };
+        if let Some(page) = page {
+            self.page_new_with_name("search", &page, text.as_str());
+            let page = glib::SendWeakRef::from(page.downgrade());
+            sender
+                .send_blocking(Action::Search(
+                    text,
+                    search_type,
+                    0,
+                    50,
+                    Arc::new(move |res| {
+                        if let Some(page) = page.upgrade() {
+                            match res {
+                                SearchResult::Songs(sis, likes) => {
+                                    page.downcast::<SearchSongPage>()
+                                        .unwrap()
+                                        .update_songs(&sis, &likes);
+                                }
+                                SearchResult::Singers(sgs) => {
+                                    page.downcast::<SearchSingerPage>()
+                                        .unwrap()
+                                        .update_singer(sgs);
+                                }
+                                SearchResult::SongLists(sls) => {
+                                    page.downcast::<SearchSongListPage>()
+                                        .unwrap()
+                                        .update_songlist(&sls);
+                                }
+                            };
+                        }
+                    }),
+                ))
+                .unwrap();
+        }
     }
 
     pub fn show_player_bar(&self) {
@@ -858,125 +801,10 @@ impl NeteaseCloudMusicGtk4Window {
         }
     }
 
-    pub fn toggle_play_pause(&self) {
-        self.imp().player_controls.get().toggle_play_pause();
-    }
-
-    pub fn play_prev(&self) {
-        self.imp().player_controls.get().prev_song();
-    }
-
-    pub async fn action_search(
-        &self,
-        ncmapi: NcmClient,
-        text: String,
-        search_type: SearchType,
-        offset: u16,
-        limit: u16,
-    ) -> Option<SearchResult> {
-        let imp = self.imp();
-        let sender = imp.sender.get().unwrap().clone();
-        let window = self;
-
-        let res = match search_type {
-            SearchType::Song => ncmapi
-                .client
-                .search_song(text, offset, limit)
-                .await
-                .map(|res| {
-                    debug!("搜索歌曲：{:?}", res);
-                    let likes = window.get_song_likes(&res);
-                    SearchResult::Songs(res, likes)
-                }),
-            SearchType::Singer => {
-                ncmapi
-                    .client
-                    .search_singer(text, offset, limit)
-                    .await
-                    .map(|res| {
-                        debug!("搜索歌手：{:?}", res);
-                        SearchResult::Singers(res)
-                    })
-            }
-            SearchType::Album => ncmapi
-                .client
-                .search_album(text, offset, limit)
-                .await
-                .map(|res| {
-                    debug!("搜索专辑：{:?}", res);
-                    SearchResult::SongLists(res)
-                }),
-            SearchType::Lyrics => {
-                ncmapi
-                    .client
-                    .search_lyrics(text, offset, limit)
-                    .await
-                    .map(|res| {
-                        debug!("搜索歌词：{:?}", res);
-                        let likes = window.get_song_likes(&res);
-                        SearchResult::Songs(res, likes)
-                    })
-            }
-            SearchType::SongList => ncmapi
-                .client
-                .search_songlist(text, offset, limit)
-                .await
-                .map(|res| {
-                    debug!("搜索歌单：{:?}", res);
-                    SearchResult::SongLists(res)
-                }),
-            SearchType::TopPicks => ncmapi
-                .client
-                .top_song_list("全部", "hot", offset, limit)
-                .await
-                .map(|res| {
-                    debug!("获取歌单：{:?}", res);
-                    SearchResult::SongLists(res)
-                }),
-            SearchType::AllAlbums => {
-                ncmapi
-                    .client
-                    .new_albums("ALL", offset, limit)
-                    .await
-                    .map(|res| {
-                        debug!("获取专辑：{:?}", res);
-                        SearchResult::SongLists(res)
-                    })
-            }
-            SearchType::Radio => ncmapi
-                .client
-                .user_radio_sublist(offset, limit)
-                .await
-                .map(|res| {
-                    debug!("获取电台：{:?}", res);
-                    SearchResult::SongLists(res)
-                }),
-            SearchType::LikeAlbums => ncmapi.client.album_sublist(offset, limit).await.map(|res| {
-                debug!("获取收藏的专辑：{:?}", res);
-                SearchResult::SongLists(res)
-            }),
-            SearchType::LikeSongList => {
-                let uid = window.get_uid();
-                ncmapi
-                    .client
-                    .user_song_list(uid, offset, limit)
-                    .await
-                    .map(|res| {
-                        debug!("获取收藏的歌单：{:?}", res);
-                        SearchResult::SongLists(res)
-                    })
-            }
-            _ => Err(anyhow::anyhow!("")),
-        };
-        if let Err(err) = &res {
-            error!("{:?}", err);
-            sender
-                .send_blocking(Action::AddToast(gettext(
-                    "Request for interface failed, please try again!",
-                )))
-                .unwrap();
-        }
-        res.ok()
+    pub fn hide_player_bar(&self) {
+        let player_revealer = self.imp().player_revealer.get();
+        player_revealer.set_reveal_child(false);
+        player_revealer.set_visible(false);
     }
 }
 
@@ -1051,6 +879,13 @@ impl NeteaseCloudMusicGtk4Window {
         self.set_property("search-type", SearchType::Lyrics);
     }
 
+    #[template_callback]
+    fn search_local_lyrics_cb(&self, check: CheckButton) {
+        let menu = self.imp().search_menu.get();
+        menu.set_label(&check.label().unwrap());
+        self.set_property("search-type", SearchType::LocalLyrics);
+    }
+
     #[template_callback]
     fn search_songlist_cb(&self, check: CheckButton) {
         let menu = self.imp().search_menu.get();
@@ -1060,64 +895,17 @@ impl NeteaseCloudMusicGtk4Window {
 
     #[template_callback]
     fn search_entry_cb(&self, entry: SearchEntry) {
-        let imp = self.imp();
-        let sender = imp.sender.get().unwrap();
         let text = entry.text().to_string();
-        imp.label_title.set_label(&text);
-        imp.switcher_title.set_visible(false);
-        imp.label_title.set_visible(true);
-        imp.back_button.set_visible(true);
-
-        let search_type = self.property::<SearchType>("search-type");
-
-        let page = match search_type {
-            SearchType::Lyrics | SearchType::Song => {
-                let page = self.init_search_song_page(&text, search_type);
-                Some(page.upcast::<Widget>())
-            }
-            SearchType::Singer => {
-                let page = self.init_search_singer_page(&text);
-                Some(page.upcast::<Widget>())
-            }
-            SearchType::Album | SearchType::SongList => {
-                let page = self.init_search_songlist_page(&text, search_type);
-                Some(page.upcast::<Widget>())
-            }
-            _ => None,
-        };
-        if let Some(page) = page {
-            self.page_new_with_name("search", &page, text.as_str());
-            let page = glib::SendWeakRef::from(page.downgrade());
+        // 粘贴的分享链接直接打开对应页面
+        if let Some(link) = parse_link(&text) {
+            let sender = self.imp().sender.get().unwrap();
             sender
-                .send_blocking(Action::Search(
-                    text,
-                    search_type,
-                    0,
-                    50,
-                    Arc::new(move |res| {
-                        if let Some(page) = page.upgrade() {
-                            match res {
-                                SearchResult::Songs(sis, likes) => {
-                                    page.downcast::<SearchSongPage>()
-                                        .unwrap()
-                                        .update_songs(&sis, &likes);
-                                }
-                                SearchResult::Singers(sgs) => {
-                                    page.downcast::<SearchSingerPage>()
-                                        .unwrap()
-                                        .update_singer(sgs);
-                                }
-                                SearchResult::SongLists(sls) => {
-                                    page.downcast::<SearchSongListPage>()
-                                        .unwrap()
-                                        .update_songlist(&sls);
-                                }
-                            };
-                        }
-                    }),
-                ))
+                .send_blocking(Action::OpenLink(link, link_position(&text)))
                 .unwrap();
+            return;
         }
+        let search_type = self.property::<SearchType>("search-type");
+        self.search(text, search_type);
     }
 }
 